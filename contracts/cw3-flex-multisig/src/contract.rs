@@ -284,7 +284,7 @@ fn return_deposit(prop: &Proposal) -> Vec<CosmosMsg> {
         };
     }
 
-    if amount.len() > 0 {
+    if !amount.is_empty() {
         vec![CosmosMsg::Bank(BankMsg::Send {
             to_address: prop.submitter.to_string(),
             amount,
@@ -302,7 +302,7 @@ fn burn_deposit(prop: &Proposal) -> Vec<CosmosMsg> {
         };
     }
 
-    if amount.len() > 0 {
+    if !amount.is_empty() {
         vec![CosmosMsg::Bank(BankMsg::Burn { amount })]
     } else {
         vec![]
@@ -544,6 +544,22 @@ mod tests {
             members,
             min_weight: 0,
             max_weight: 100,
+            weight_unit: None,
+            removal_grace_blocks: None,
+            weight_source: None,
+            strict_removals: false,
+            require_identity: false,
+            validate_hook_contracts: false,
+            emergency_recovery: None,
+            frozen: None,
+            init_callback: None,
+            expires_at: None,
+            auto_rebalance: false,
+            rebalance_target: 0,
+            rebalance_remainder_to: None,
+            require_admin_nonce: false,
+            allow_self_leave: false,
+            emit_payload_event: false,
         };
         app.instantiate_contract(group_id, Addr::unchecked(OWNER), &msg, &[], "group", None)
             .unwrap()
@@ -1835,6 +1851,7 @@ mod tests {
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
             remove: vec![VOTER3.into()],
             add: vec![member(VOTER2, 21, "VOTER2"), member(newbie, 2, "newbie")],
+            expected_total: None,
         };
         app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
@@ -2093,6 +2110,7 @@ mod tests {
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
             remove: vec![VOTER3.into()],
             add: vec![member(VOTER2, 9, "VOTER2"), member(newbie, 29, "newbie")],
+            expected_total: None,
         };
         app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
@@ -2199,6 +2217,7 @@ mod tests {
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
             remove: vec![VOTER3.into()],
             add: vec![member(VOTER2, 9, "VOTER2"), member(newbie, 29, "newbie")],
+            expected_total: None,
         };
         app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
@@ -2225,7 +2244,7 @@ mod tests {
             Addr::unchecked(VOTER2),
             flex_addr.clone(),
             &ExecuteMsg::Close { proposal_id },
-            &vec![],
+            &[],
         )
         .unwrap();
         // Check deposit was returned despite rejection
@@ -1,18 +1,55 @@
+use std::convert::TryFrom;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    SubMsg, Uint64,
+    attr, from_slice, to_binary, to_vec, Addr, Binary, BlockInfo, Decimal, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, OverflowError, OverflowOperation, Response, StdError, StdResult,
+    Storage, SubMsg, Timestamp, Uint128, Uint64,
 };
-use cw2::set_contract_version;
-use cw4::{Member, MemberListResponse, MemberResponse};
+use cw2::{set_contract_version, ContractVersion};
+use cw4::{Cw4QueryMsg, Member, MemberListResponse, MemberResponse};
 use cw4::{MemberChangedHookMsg, MemberDiff, TotalWeightResponse};
-use cw_storage_plus::Bound;
-use cw_utils::maybe_addr;
+use cw_controllers::{AdminError, HookError};
+use cw_storage_plus::{Bound, Map, PrefixBound};
+use cw_utils::{maybe_addr, Expiration};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{ADMIN, HOOKS, IDS, MAX_WEIGHT, MEMBERS, MIN_WEIGHT, TOTAL};
+use crate::merkle;
+use crate::msg::{
+    AdminChangeReadyAtResponse, AdminMemberResponse, AdminNonceResponse, Attestation,
+    AverageJoinHeightResponse, CanAddResponse, ChangedMembersResponse, ChurnResponse,
+    CompareToGroupResponse, ConfigResponse, CrossingDirection, CumulativeWeightResponse,
+    Delegation, DelegationsResponse, DissolvedResponse, EffectiveThresholdResponse,
+    EffectiveWeightResponse, EmergencyProposalResponse, ExecuteMsg, ExpirationResponse,
+    FreezeWindowResponse, FrozenResponse, GroupInstantiatedMsg, HeadroomReportResponse,
+    HealthResponse, HookInfo, HooksInfoResponse, InactiveWeightResponse, InstantiateMsg,
+    IsLockedResponse, IsMajorityResponse, LastBatchDiffResponse, LastChangeResponse,
+    ListSharesResponse, MemberByExternalIdResponse, MemberHistoryResponse, MemberRankResponse,
+    MemberShare, MemberSnapshotBoundsResponse, MemberUpdateSummary, MembersToReachResponse,
+    MergeStrategy, MigrateMsg, MinimalCoalitionResponse, NakamotoCoefficientResponse,
+    NextMemberResponse, NextScheduledResponse, OperatorsResponse, OverviewResponse,
+    PausedOpsResponse, PendingRemoval, PendingRemovalsResponse, ProvenanceResponse, QuantileTier,
+    QuantilesResponse, QueryMsg, QuorumCrossedHookMsg, RemainingBudgetResponse,
+    ResolvedWeightResponse, SealedResponse, StateHashResponse, StorageStatsResponse,
+    TagWeightResponse, TempAdminResponse, VerifyProofResponse, VestedWeightResponse,
+    WeightByTagResponse, WeightChange, WeightForShareResponse, WeightPercentileResponse,
+    WeightSourceBalanceResponse, WeightSourceQueryMsg, WeightToEnterTopResponse,
+};
+use crate::packed;
+use crate::state::{
+    Config, EmergencyProposal, EmergencyRecovery, GroupInfo, OpKind, Stats, VestingSchedule,
+    WeightDecay, ADMIN, ADMIN_NONCE, ALIASES, ATTESTATION_NONCE, ATTESTER_PUBKEY, BUDGET,
+    CHECKPOINTS, CONFIG, CREATOR, DISSOLVED_AT, EMERGENCY_PROPOSAL, EMERGENCY_VOTES, EXPIRY,
+    EXTERNAL_IDS, EXTERNAL_ID_OWNERS, FREEZE_WINDOW, FROZEN, FROZEN_MEMBERS, GROUP_INFO, HOOKS,
+    HOOK_FILTERS, HOOK_PRIORITY, IDENTITY_INDEX, IDENTITY_POOL, IDENTITY_POOL_IDS,
+    IDENTITY_POOL_SIZE, IDS, INCOMING_DELEGATIONS, INSTANTIATED_AT, JOINED_AT, LAST_BATCH_DIFF,
+    LAST_BATCH_HEIGHT, LAST_CHANGE_HEIGHT, LAST_CHANGE_TIME, LAST_DECAY_HEIGHT, LEGACY_IDS,
+    LEGACY_MAX_WEIGHT, LEGACY_MIN_WEIGHT, LOCKED_MEMBERS, MEMBERS, MEMBERS_BY_JOIN,
+    MEMBERS_BY_WEIGHT, MEMBER_COUNT, MEMBER_TAGS, OPERATORS, PARTIAL_DELEGATIONS, PAUSED_OPS,
+    PENDING_ADMIN, PENDING_REMOVALS, ROOTS, SEALED, STATS, TAGS, TEMP_ADMIN, TOTAL, VESTING,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-group";
@@ -22,21 +59,64 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    INSTANTIATED_AT.save(deps.storage, &env.block.height)?;
+    CREATOR.save(deps.storage, &info.sender)?;
+    let config = Config {
+        min_weight: msg.min_weight,
+        max_weight: msg.max_weight,
+        weight_unit: msg.weight_unit,
+        removal_grace_blocks: msg.removal_grace_blocks,
+        weight_source: msg
+            .weight_source
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        min_active_total: None,
+        strict_removals: msg.strict_removals,
+        require_identity: msg.require_identity,
+        quorum_watch: None,
+        validate_hook_contracts: msg.validate_hook_contracts,
+        emergency_recovery: msg.emergency_recovery,
+        expires_at: msg.expires_at,
+        auto_rebalance: msg.auto_rebalance,
+        require_admin_nonce: msg.require_admin_nonce,
+        allow_self_leave: msg.allow_self_leave,
+        emit_payload_event: msg.emit_payload_event,
+        rebalance_target: msg.rebalance_target,
+        rebalance_remainder_to: msg
+            .rebalance_remainder_to
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        min_member_percent: None,
+        max_weight_delta_per_op: None,
+        decay: None,
+        admin_change_notice_blocks: None,
+    };
     create(
-        deps,
+        deps.branch(),
         msg.admin,
         msg.members,
-        msg.min_weight,
-        msg.max_weight,
+        config,
         env.block.height,
     )?;
-    Ok(Response::default())
+    FROZEN.save(deps.storage, &msg.frozen.unwrap_or_default())?;
+    record_change(deps.storage, &env.block)?;
+
+    let mut res = Response::default();
+    if let Some(callback) = msg.init_callback {
+        let contract = deps.api.addr_validate(&callback.contract)?;
+        let notice = GroupInstantiatedMsg {
+            msg: callback.msg,
+            total_weight: TOTAL.load(deps.storage)?,
+        };
+        res = res.add_submessage(SubMsg::new(notice.into_cosmos_msg(contract)?));
+    }
+    Ok(res)
 }
 
 // create is the instantiation logic with set_contract_version removed so it can more
@@ -45,12 +125,10 @@ pub fn create(
     mut deps: DepsMut,
     admin: Option<String>,
     members: Vec<Member>,
-    min_weight: u64,
-    max_weight: u64,
+    config: Config,
     height: u64,
 ) -> Result<(), ContractError> {
-    MAX_WEIGHT.save(deps.storage, &max_weight)?;
-    MIN_WEIGHT.save(deps.storage, &min_weight)?;
+    CONFIG.save(deps.storage, &config)?;
 
     let admin_addr = admin
         .map(|admin| deps.api.addr_validate(&admin))
@@ -58,18 +136,83 @@ pub fn create(
     ADMIN.set(deps.branch(), admin_addr)?;
 
     let mut total = Uint64::zero();
+    let stats = Stats {
+        total_adds: members.len() as u64,
+        total_weight_added: members.iter().map(|m| m.weight).sum(),
+        ..Stats::default()
+    };
+    STATS.save(deps.storage, &stats)?;
     for member in members.into_iter() {
         let member_weight = Uint64::from(member.weight);
         total = total.checked_add(member_weight)?;
         let member_addr = deps.api.addr_validate(&member.addr)?;
-        MEMBERS.save(deps.storage, &member_addr, &member_weight.u64(), height)?;
-        IDS.save(deps.storage, &member_addr, &member.identity)?;
+        assert_identity(&config, &member_addr, &member.identity)?;
+        save_member(deps.storage, &member_addr, member_weight.u64(), height)?;
+        save_identity(deps.storage, &member_addr, &member.identity)?;
     }
-    TOTAL.save(deps.storage, &total.u64(), height)?;
+    save_total(deps.storage, total.u64(), height)?;
     assert_weights(deps.as_ref())?;
     Ok(())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // fold the separately stored weight bounds of older versions into CONFIG
+    if CONFIG.may_load(deps.storage)?.is_none() {
+        let config = Config {
+            min_weight: LEGACY_MIN_WEIGHT.load(deps.storage)?,
+            max_weight: LEGACY_MAX_WEIGHT.load(deps.storage)?,
+            weight_unit: None,
+            removal_grace_blocks: None,
+            weight_source: None,
+            min_active_total: None,
+            strict_removals: false,
+            require_identity: false,
+            quorum_watch: None,
+            validate_hook_contracts: false,
+            emergency_recovery: None,
+            expires_at: None,
+            auto_rebalance: false,
+            rebalance_target: 0,
+            rebalance_remainder_to: None,
+            require_admin_nonce: false,
+            allow_self_leave: false,
+            emit_payload_event: false,
+            min_member_percent: None,
+            max_weight_delta_per_op: None,
+            decay: None,
+            admin_change_notice_blocks: None,
+        };
+        CONFIG.save(deps.storage, &config)?;
+        LEGACY_MIN_WEIGHT.remove(deps.storage);
+        LEGACY_MAX_WEIGHT.remove(deps.storage);
+    }
+    // index members stored before the indexes existed
+    let members = MEMBERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    MEMBER_COUNT.save(deps.storage, &(members.len() as u64))?;
+    for addr in members {
+        let weight = MEMBERS.load(deps.storage, &addr)?;
+        MEMBERS_BY_WEIGHT.save(deps.storage, (weight, &addr), &())?;
+        if let Some(joined) = JOINED_AT.may_load(deps.storage, &addr)? {
+            MEMBERS_BY_JOIN.save(deps.storage, (joined, &addr), &())?;
+        }
+        // intern identities still stored as plain strings
+        let identity = match LEGACY_IDS.load(deps.storage, &addr) {
+            Ok(identity) => {
+                let id = intern_identity(deps.storage, &identity)?;
+                IDS.save(deps.storage, &addr, &id)?;
+                identity
+            }
+            Err(_) => may_load_identity(deps.storage, &addr)?.unwrap_or_default(),
+        };
+        index_identity(deps.storage, &identity, &addr)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::default())
+}
+
 // And declare a custom Error variant for the ones where you will want to make use of it
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
@@ -78,23 +221,320 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if !matches!(msg, ExecuteMsg::CheckExpiry {}) && is_expired(deps.as_ref(), &env)? {
+        return Err(ContractError::ContractExpired {});
+    }
+    if let Some(height) = DISSOLVED_AT.may_load(deps.storage)? {
+        return Err(ContractError::Dissolved { height });
+    }
+    if SEALED.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::Sealed {});
+    }
+    if let Some((_, expires)) = TEMP_ADMIN.may_load(deps.storage)? {
+        if expires.is_expired(&env.block) {
+            TEMP_ADMIN.remove(deps.storage);
+        }
+    }
+
+    let msg = match msg {
+        ExecuteMsg::WithNonce { nonce, msg } => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+            let expected = ADMIN_NONCE.may_load(deps.storage)?.unwrap_or_default();
+            if nonce != expected {
+                return Err(ContractError::BadNonce { expected });
+            }
+            ADMIN_NONCE.save(deps.storage, &(expected + 1))?;
+            *msg
+        }
+        msg => {
+            if CONFIG.load(deps.storage)?.require_admin_nonce
+                && assert_admin(deps.as_ref(), &info.sender).is_ok()
+            {
+                return Err(ContractError::BadNonce {
+                    expected: ADMIN_NONCE.may_load(deps.storage)?.unwrap_or_default(),
+                });
+            }
+            msg
+        }
+    };
+
+    if !matches!(
+        msg,
+        ExecuteMsg::SetFreezeWindow { .. } | ExecuteMsg::ClearFreezeWindow {}
+    ) {
+        if let Some((start, end)) = FREEZE_WINDOW.may_load(deps.storage)? {
+            if start <= env.block.time && env.block.time < end {
+                return Err(ContractError::InFreezeWindow { end });
+            }
+        }
+    }
+
+    if let Some(op) = control_op(&msg) {
+        assert_op_unpaused(deps.storage, op)?;
+    }
+
     let api = deps.api;
     match msg {
-        ExecuteMsg::UpdateAdmin { admin } => Ok(ADMIN.execute_update_admin(
-            deps,
-            info,
-            admin.map(|admin| api.addr_validate(&admin)).transpose()?,
-        )?),
-        ExecuteMsg::UpdateMembers { add, remove } => {
-            execute_update_members(deps, env, info, add, remove)
+        ExecuteMsg::WithNonce { .. } => {
+            Err(StdError::generic_err("Nonce wrappers cannot be nested").into())
+        }
+        ExecuteMsg::UpdateAdmin { admin } => {
+            if admin.is_some() {
+                assert_no_admin_notice(deps.storage)?;
+            }
+            count_admin_change(deps.storage)?;
+            TEMP_ADMIN.remove(deps.storage);
+            PENDING_ADMIN.remove(deps.storage);
+            Ok(ADMIN.execute_update_admin(
+                deps,
+                info,
+                admin.map(|admin| api.addr_validate(&admin)).transpose()?,
+            )?)
+        }
+        ExecuteMsg::UpdateMembers {
+            add,
+            remove,
+            expected_total,
+        } => execute_update_members(deps, env, info, add, remove, expected_total),
+        ExecuteMsg::UpdateMember {
+            addr,
+            weight,
+            identity,
+        } => execute_update_member(deps, env, info, addr, weight, identity),
+        ExecuteMsg::EnsureMember { member } => execute_ensure_member(deps, env, info, member),
+        ExecuteMsg::RevertLastBatch {} => execute_revert_last_batch(deps, env, info),
+        ExecuteMsg::BackfillWeight {
+            addr,
+            weight,
+            at_height,
+        } => execute_backfill_weight(deps, env, info, addr, weight, at_height),
+        ExecuteMsg::ImportPacked { data } => execute_import_packed(deps, env, info, data),
+        ExecuteMsg::ImportMerge { members, strategy } => {
+            execute_import_merge(deps, env, info, members, strategy)
+        }
+        ExecuteMsg::MergeFrom {
+            source,
+            start_after,
+            limit,
+        } => execute_merge_from(deps, env, info, source, start_after, limit),
+        ExecuteMsg::AddHook { addr, priority } => {
+            let hook = api.addr_validate(&addr)?;
+            assert_hook_contract(deps.as_ref(), &hook)?;
+            if let Some(priority) = priority {
+                HOOK_PRIORITY.save(deps.storage, &hook, &priority)?;
+            }
+            execute_add_hook(deps, info, hook)
         }
-        ExecuteMsg::AddHook { addr } => {
-            Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+        ExecuteMsg::AddMemberWithHook { member, as_hook } => {
+            execute_add_member_with_hook(deps, env, info, member, as_hook)
         }
+        ExecuteMsg::AddHookFiltered {
+            addr,
+            addrs_of_interest,
+        } => execute_add_hook_filtered(deps, info, addr, addrs_of_interest),
         ExecuteMsg::RemoveHook { addr } => {
-            Ok(HOOKS.execute_remove_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+            assert_admin(deps.as_ref(), &info.sender).map_err(HookError::Admin)?;
+            let addr = api.addr_validate(&addr)?;
+            HOOK_FILTERS.remove(deps.storage, &addr);
+            HOOK_PRIORITY.remove(deps.storage, &addr);
+            HOOKS.remove_hook(deps.storage, addr.clone())?;
+            Ok(Response::new()
+                .add_attribute("action", "remove_hook")
+                .add_attribute("hook", addr)
+                .add_attribute("sender", info.sender))
+        }
+        ExecuteMsg::SetHooks { addrs } => execute_set_hooks(deps, info, addrs),
+        ExecuteMsg::FinalizeRemovals {} => execute_finalize_removals(deps, env),
+        ExecuteMsg::PruneExpired {} => execute_prune_expired(deps, env, info),
+        ExecuteMsg::AddOperator { addr } => execute_set_operator(deps, info, addr, true),
+        ExecuteMsg::RemoveOperator { addr } => execute_set_operator(deps, info, addr, false),
+        ExecuteMsg::RotateControl {
+            new_admin,
+            new_operators,
+        } => execute_rotate_control(deps, info, new_admin, new_operators),
+        ExecuteMsg::CancelRemoval { addr } => execute_cancel_removal(deps, info, addr),
+        ExecuteMsg::ScaleWeights {
+            numerator,
+            denominator,
+        } => execute_scale_weights(deps, env, info, numerator, denominator),
+        ExecuteMsg::ApplyDeltas { deltas } => execute_apply_deltas(deps, env, info, deltas),
+        ExecuteMsg::SwapWeights { a, b } => execute_swap_weights(deps, env, info, a, b),
+        ExecuteMsg::SyncWeights { addrs } => execute_sync_weights(deps, env, info, addrs),
+        ExecuteMsg::SetVesting { addr, schedule } => {
+            execute_set_vesting(deps, info, addr, schedule)
+        }
+        ExecuteMsg::CrankVesting { addrs } => execute_crank_vesting(deps, env, info, addrs),
+        ExecuteMsg::SetMinActiveTotal { min } => execute_set_min_active_total(deps, info, min),
+        ExecuteMsg::SetDecay { decay } => execute_set_decay(deps, env, info, decay),
+        ExecuteMsg::DecayWeights {} => execute_decay_weights(deps, env),
+        ExecuteMsg::SetMaxWeightDelta { max } => execute_set_max_weight_delta(deps, info, max),
+        ExecuteMsg::SetBudget { amount } => execute_set_budget(deps, info, amount),
+        ExecuteMsg::AllocateFromBudget { addr, amount } => {
+            execute_allocate_from_budget(deps, env, info, addr, amount)
+        }
+        ExecuteMsg::SetMinMemberPercent { percent } => {
+            execute_set_min_member_percent(deps, info, percent)
+        }
+        ExecuteMsg::SetGroupInfo {
+            name,
+            description,
+            url,
+        } => execute_set_group_info(deps, info, name, description, url),
+        ExecuteMsg::SetMemberTags { addr, tags } => execute_set_member_tags(deps, info, addr, tags),
+        ExecuteMsg::DelegatePartial { to, amount } => {
+            execute_delegate_partial(deps, info, to, amount)
+        }
+        ExecuteMsg::Supersede { old, new } => execute_supersede(deps, env, info, old, new),
+        ExecuteMsg::TransferAdminAndSeat { new_addr, identity } => {
+            execute_transfer_admin_and_seat(deps, env, info, new_addr, identity)
+        }
+        ExecuteMsg::DelegateAdmin { addr, expires } => {
+            execute_delegate_admin(deps, env, info, addr, expires)
+        }
+        ExecuteMsg::RevokeTempAdmin {} => {
+            ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+            TEMP_ADMIN.remove(deps.storage);
+            Ok(Response::new()
+                .add_attribute("action", "revoke_temp_admin")
+                .add_attribute("sender", info.sender))
+        }
+        ExecuteMsg::ProposeAdmin { addr } => execute_propose_admin(deps, env, info, addr),
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::SetAdminChangeNotice { blocks } => {
+            execute_set_admin_change_notice(deps, info, blocks)
+        }
+        ExecuteMsg::SetMemberFrozen { addr, frozen } => {
+            execute_set_member_frozen(deps, info, addr, frozen)
+        }
+        ExecuteMsg::LockMember { addr } => execute_set_member_locked(deps, info, addr, true),
+        ExecuteMsg::UnlockMember { addr } => execute_set_member_locked(deps, info, addr, false),
+        ExecuteMsg::SetAlias { alias, canonical } => {
+            execute_set_alias(deps, info, alias, canonical)
+        }
+        ExecuteMsg::RemoveAlias { alias } => execute_remove_alias(deps, info, alias),
+        ExecuteMsg::SetMemberExpiry { addr, expires } => {
+            execute_set_member_expiry(deps, info, addr, expires)
+        }
+        ExecuteMsg::SetQuorumWatch { threshold } => execute_set_quorum_watch(deps, info, threshold),
+        ExecuteMsg::RewriteIdentities {
+            from_prefix,
+            to_prefix,
+        } => execute_rewrite_identities(deps, info, from_prefix, to_prefix),
+        ExecuteMsg::SetExternalId { addr, external_id } => {
+            execute_set_external_id(deps, info, addr, external_id)
+        }
+        ExecuteMsg::ProposeEmergencyAdmin { addr } => {
+            execute_propose_emergency_admin(deps, env, info, addr)
+        }
+        ExecuteMsg::VoteEmergencyAdmin {} => execute_vote_emergency_admin(deps, env, info),
+        ExecuteMsg::Freeze {} => execute_set_frozen(deps, info, true),
+        ExecuteMsg::Unfreeze {} => execute_set_frozen(deps, info, false),
+        ExecuteMsg::SetPausedOps { ops } => execute_set_paused_ops(deps, info, ops),
+        ExecuteMsg::SetFreezeWindow { start, end } => {
+            execute_set_freeze_window(deps, info, start, end)
+        }
+        ExecuteMsg::ClearFreezeWindow {} => execute_clear_freeze_window(deps, info),
+        ExecuteMsg::SetAttester { pubkey } => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+            match &pubkey {
+                Some(pubkey) => ATTESTER_PUBKEY.save(deps.storage, pubkey)?,
+                None => ATTESTER_PUBKEY.remove(deps.storage),
+            }
+            Ok(Response::new()
+                .add_attribute("action", "set_attester")
+                .add_attribute(
+                    "pubkey",
+                    pubkey.map_or("none".to_string(), |p| p.to_base64()),
+                )
+                .add_attribute("sender", info.sender))
+        }
+        ExecuteMsg::ApplyAttestation {
+            attestation,
+            signature,
+        } => execute_apply_attestation(deps, env, info, attestation, signature),
+        ExecuteMsg::Leave {} => execute_leave(deps, env, info),
+        ExecuteMsg::SaveCheckpoint { name } => execute_save_checkpoint(deps, env, info, name),
+        ExecuteMsg::Seal {} => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+            SEALED.save(deps.storage, &true)?;
+            Ok(Response::new()
+                .add_attribute("action", "seal")
+                .add_attribute("sender", info.sender))
+        }
+        ExecuteMsg::CheckExpiry {} => execute_check_expiry(deps, env, info),
+        ExecuteMsg::Dissolve {} => execute_dissolve(deps, env, info),
+    }
+}
+
+pub fn execute_add_hook_filtered(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    addrs_of_interest: Vec<String>,
+) -> Result<Response, ContractError> {
+    let hook = deps.api.addr_validate(&addr)?;
+    assert_hook_contract(deps.as_ref(), &hook)?;
+    let filter = addrs_of_interest
+        .iter()
+        .map(|a| deps.api.addr_validate(a))
+        .collect::<StdResult<Vec<_>>>()?;
+    HOOK_FILTERS.save(deps.storage, &hook, &filter)?;
+    Ok(execute_add_hook(deps, info, hook)?.add_attribute("filter_size", filter.len().to_string()))
+}
+
+pub fn execute_set_hooks(
+    deps: DepsMut,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender).map_err(HookError::Admin)?;
+    let mut hooks: Vec<Addr> = vec![];
+    for addr in addrs {
+        let hook = deps.api.addr_validate(&addr)?;
+        assert_hook_contract(deps.as_ref(), &hook)?;
+        if !hooks.contains(&hook) {
+            hooks.push(hook);
+        }
+    }
+
+    let mut removed = 0u32;
+    for old in HOOKS.query_hooks(deps.as_ref())?.hooks {
+        let old = Addr::unchecked(old);
+        if !hooks.contains(&old) {
+            HOOK_FILTERS.remove(deps.storage, &old);
+            HOOK_PRIORITY.remove(deps.storage, &old);
+            HOOKS.remove_hook(deps.storage, old)?;
+            removed += 1;
+        }
+    }
+    let mut added = 0u32;
+    for hook in hooks {
+        match HOOKS.add_hook(deps.storage, hook) {
+            Ok(()) => added += 1,
+            Err(HookError::HookAlreadyRegistered {}) => {}
+            Err(err) => return Err(err.into()),
         }
     }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_hooks")
+        .add_attribute("added", added.to_string())
+        .add_attribute("removed", removed.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+// mirrors Hooks::execute_add_hook, accepting the temporary admin as well
+fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    hook: Addr,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender).map_err(HookError::Admin)?;
+    HOOKS.add_hook(deps.storage, hook.clone())?;
+    Ok(Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", hook)
+        .add_attribute("sender", info.sender))
 }
 
 pub fn execute_update_members(
@@ -103,7 +543,15 @@ pub fn execute_update_members(
     info: MessageInfo,
     add: Vec<Member>,
     remove: Vec<String>,
+    expected_total: Option<u64>,
 ) -> Result<Response, ContractError> {
+    if let Some(expected) = expected_total {
+        let actual = TOTAL.load(deps.storage)?;
+        if actual != expected {
+            return Err(ContractError::TotalMismatch { expected, actual });
+        }
+    }
+
     let attributes = vec![
         attr("action", "update_members"),
         attr("added", add.len().to_string()),
@@ -112,135 +560,4445 @@ pub fn execute_update_members(
     ];
 
     // make the local update
-    let diff = update_members(deps.branch(), env.block.height, info.sender, add, remove)?;
+    let mut diff = update_members(deps.branch(), env.block.height, info.sender, add, remove)?;
+    diff.diffs
+        .extend(rebalance(deps.storage, env.block.height)?);
+    record_change(deps.storage, &env.block)?;
     // call all registered hooks
-    let messages = HOOKS.prepare_hooks(deps.storage, |h| {
-        diff.clone().into_cosmos_msg(h).map(SubMsg::new)
-    })?;
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    assert_weights(deps.as_ref())?;
+    let mut res = Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes);
+    if CONFIG.load(deps.storage)?.emit_payload_event {
+        let summary = MemberUpdateSummary {
+            diffs: diff.diffs,
+            total_weight: TOTAL.load(deps.storage)?,
+            height: env.block.height,
+        };
+        res = res.add_event(
+            Event::new("member_update").add_attribute("payload", to_binary(&summary)?.to_base64()),
+        );
+    }
+    Ok(res)
+}
+
+pub fn execute_apply_attestation(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    attestation: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let pubkey = ATTESTER_PUBKEY
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoAttester {})?;
+    let hash = Sha256::digest(attestation.as_slice());
+    // malformed signatures or keys fail verification like wrong ones
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &signature, &pubkey)
+        .unwrap_or(false);
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let Attestation { nonce, add, remove } = from_slice(&attestation)?;
+    let expected = ATTESTATION_NONCE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::StaleAttestation { expected });
+    }
+    ATTESTATION_NONCE.save(deps.storage, &(nonce + 1))?;
+
+    let attributes = vec![
+        attr("action", "apply_attestation"),
+        attr("nonce", nonce.to_string()),
+        attr("added", add.len().to_string()),
+        attr("removed", remove.len().to_string()),
+        attr("sender", &info.sender),
+    ];
+    let mut diff = apply_member_changes(deps.branch(), env.block.height, add, remove)?;
+    diff.diffs
+        .extend(rebalance(deps.storage, env.block.height)?);
+    record_change(deps.storage, &env.block)?;
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
     assert_weights(deps.as_ref())?;
     Ok(Response::new()
         .add_submessages(messages)
         .add_attributes(attributes))
 }
 
-// the logic from execute_update_members extracted for easier import
-pub fn update_members(
+pub fn execute_backfill_weight(
     deps: DepsMut,
-    height: u64,
-    sender: Addr,
-    to_add: Vec<Member>,
-    to_remove: Vec<String>,
-) -> Result<MemberChangedHookMsg, ContractError> {
-    ADMIN.assert_admin(deps.as_ref(), &sender)?;
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+    weight: u64,
+    at_height: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    assert_unlocked(deps.storage, &addr)?;
+    let height = env.block.height;
+    let invalid = || ContractError::InvalidBackfill {
+        addr: addr.to_string(),
+        height: at_height,
+    };
+    if at_height == 0 || at_height > height {
+        return Err(invalid());
+    }
+    let prev = MEMBERS
+        .may_load_at_height(deps.storage, &addr, at_height)?
+        .ok_or_else(invalid)?;
 
-    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
-    let mut diffs: Vec<MemberDiff> = vec![];
+    // the changelog keeps the value from before each change, so the first entry
+    // from at_height on holds what queries up to that entry see
+    let next_change = |storage: &dyn Storage| {
+        MEMBERS
+            .changelog()
+            .prefix(&addr)
+            .keys(
+                storage,
+                Some(Bound::inclusive(at_height)),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()
+    };
+    let mut messages = vec![];
+    if next_change(deps.storage)?.is_none() {
+        if weight == prev {
+            return Ok(Response::new()
+                .add_attribute("action", "backfill_weight")
+                .add_attribute("addr", addr)
+                .add_attribute("sender", info.sender));
+        }
+        // unchanged since at_height, so the correction carries over to now
+        save_member(deps.storage, &addr, weight, height)?;
+        let total = Uint64::from(TOTAL.load(deps.storage)?)
+            .checked_sub(Uint64::from(prev))?
+            .checked_add(Uint64::from(weight))?;
+        if weight < prev {
+            assert_min_active_total(&CONFIG.load(deps.storage)?, total.u64())?;
+        }
+        save_total(deps.storage, total.u64(), height)?;
+        assert_weights(deps.as_ref())?;
+        let diffs = vec![MemberDiff::new(addr.as_str(), Some(prev), Some(weight))];
+        assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+        assert_diffs_unpaused(deps.storage, &diffs)?;
+        update_stats(deps.storage, &diffs)?;
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
 
-    // add all new members and update total
-    for add in to_add.into_iter() {
-        let add_addr = deps.api.addr_validate(&add.addr)?;
-        IDS.save(deps.storage, &add_addr, &add.identity)?;
-        MEMBERS.update(deps.storage, &add_addr, height, |old| -> StdResult<_> {
-            total = total.checked_sub(Uint64::from(old.clone().unwrap_or_default()))?;
-            total = total.checked_add(Uint64::from(add.weight))?;
-            diffs.push(MemberDiff::new(add.addr, old, Some(add.weight)));
-            Ok(add.weight)
-        })?;
+    let next = next_change(deps.storage)?.ok_or_else(invalid)?;
+    let mut change = MEMBERS.changelog().load(deps.storage, (&addr, next))?;
+    change.old = Some(weight);
+    MEMBERS
+        .changelog()
+        .save(deps.storage, (&addr, next), &change)?;
+    // pin the earlier value down right before at_height, unless a change already does
+    if !MEMBERS
+        .changelog()
+        .has(deps.storage, (&addr, at_height - 1))
+    {
+        change.old = Some(prev);
+        MEMBERS
+            .changelog()
+            .save(deps.storage, (&addr, at_height - 1), &change)?;
     }
 
-    for remove in to_remove.into_iter() {
-        let remove_addr = deps.api.addr_validate(&remove)?;
-        let old = MEMBERS.may_load(deps.storage, &remove_addr)?;
-        // Only process this if they were actually in the list before
-        if let Some(weight) = old {
-            diffs.push(MemberDiff::new(remove, Some(weight), None));
-            total = total.checked_sub(Uint64::from(weight))?;
-            MEMBERS.remove(deps.storage, &remove_addr, height)?;
+    // totals over the same range follow the corrected weight. Pinning the values
+    // either side first leaves every height outside at_height..=next as it was
+    for pin in [at_height - 1, next] {
+        if !TOTAL.changelog().has(deps.storage, pin) {
+            change.old = TOTAL.may_load_at_height(deps.storage, pin)?;
+            TOTAL.changelog().save(deps.storage, pin, &change)?;
+        }
+    }
+    let delta = weight as i128 - prev as i128;
+    let totals = TOTAL
+        .changelog()
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(at_height)),
+            Some(Bound::inclusive(next)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for (total_height, mut total) in totals {
+        total.old = total
+            .old
+            .map(|old| {
+                u64::try_from(old as i128 + delta)
+                    .map_err(|_| StdError::generic_err("Historical total out of range"))
+            })
+            .transpose()?;
+        TOTAL.changelog().save(deps.storage, total_height, &total)?;
+    }
+    // and so do the roots serving those heights, the ones committed before next
+    if !ROOTS.has(deps.storage, next) {
+        let root = ROOTS
+            .range(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(next)),
+                Order::Descending,
+            )
+            .next()
+            .transpose()?;
+        if let Some((_, root)) = root {
+            ROOTS.save(deps.storage, next, &root)?;
         }
     }
+    let mut root_heights = ROOTS
+        .keys(
+            deps.storage,
+            Some(Bound::exclusive(at_height - 1)),
+            Some(Bound::exclusive(next)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    root_heights.insert(0, at_height - 1);
+    for root_height in root_heights {
+        let root = historical_root(deps.storage, root_height + 1)?;
+        ROOTS.save(deps.storage, root_height, &root)?;
+    }
 
-    TOTAL.save(deps.storage, &total.u64(), height)?;
-    assert_weights(deps.as_ref())?;
-    Ok(MemberChangedHookMsg { diffs })
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "backfill_weight")
+        .add_attribute("addr", addr)
+        .add_attribute("weight", weight.to_string())
+        .add_attribute("at_height", at_height.to_string())
+        .add_attribute("sender", info.sender))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Member {
-            addr,
-            at_height: height,
-        } => to_binary(&query_member(deps, addr, height)?),
-        QueryMsg::ListMembers { start_after, limit } => {
-            to_binary(&query_list_members(deps, start_after, limit)?)
+pub fn execute_revert_last_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    let batch = LAST_BATCH_DIFF
+        .may_load(deps.storage)?
+        .filter(|diffs| !diffs.is_empty())
+        .ok_or(ContractError::NoBatchToRevert {})?;
+    let height = env.block.height;
+
+    // undo the diffs back to front, so an address changed twice ends at its first `old`
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    for diff in batch.into_iter().rev() {
+        let addr = Addr::unchecked(&diff.key);
+        let current = MEMBERS.may_load(deps.storage, &addr)?;
+        if current != diff.new {
+            return Err(ContractError::StaleBatch {});
         }
-        QueryMsg::TotalWeight { at_height: height } => {
-            to_binary(&query_total_weight(deps, height)?)
+        assert_unlocked(deps.storage, &addr)?;
+        total = total.checked_sub(Uint64::from(current.unwrap_or_default()))?;
+        match diff.old {
+            Some(weight) => {
+                save_member(deps.storage, &addr, weight, height)?;
+                // removed members keep their identity, it only needs indexing again
+                if let Some(identity) = may_load_identity(deps.storage, &addr)? {
+                    index_identity(deps.storage, &identity, &addr)?;
+                }
+                total = total.checked_add(Uint64::from(weight))?;
+            }
+            None => {
+                remove_member(deps.storage, &addr, height)?;
+            }
         }
-        QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
-        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        diffs.push(MemberDiff::new(diff.key, diff.new, diff.old));
     }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+    // only the most recent batch can be reverted, and only once
+    LAST_BATCH_DIFF.remove(deps.storage);
+
+    let reverted = diffs.len();
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "revert_last_batch")
+        .add_attribute("reverted", reverted.to_string())
+        .add_attribute("sender", info.sender))
 }
 
-pub fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
-    let weight = match height {
-        Some(h) => TOTAL.may_load_at_height(deps.storage, h),
-        None => TOTAL.may_load(deps.storage),
-    }?
-    .unwrap_or_default();
-    Ok(TotalWeightResponse { weight })
+pub fn execute_ensure_member(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    member: Member,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&member.addr)?;
+    let present = MEMBERS.may_load(deps.storage, &addr)? == Some(member.weight)
+        && may_load_identity(deps.storage, &addr)?.as_deref() == Some(member.identity.as_str());
+
+    let mut messages = vec![];
+    if !present {
+        let diff = apply_member_changes(deps.branch(), env.block.height, vec![member], vec![])?;
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "ensure_member")
+        .add_attribute("addr", addr)
+        .add_attribute("changed", (!present).to_string())
+        .add_attribute("sender", info.sender))
 }
 
-pub fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<MemberResponse> {
+pub fn execute_update_member(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+    weight: Option<u64>,
+    identity: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
     let addr = deps.api.addr_validate(&addr)?;
-    let res = match height {
-        Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h),
-        None => MEMBERS.may_load(deps.storage, &addr),
-    }?;
-    match res {
-        Some(weight) => Ok(MemberResponse {
-            weight: Some(weight),
-            identity: IDS.load(deps.storage, &addr).ok(),
-        }),
-        None => Ok(MemberResponse {
-            weight: None,
-            identity: None,
-        }),
+    assert_unlocked(deps.storage, &addr)?;
+    let old = MEMBERS
+        .may_load(deps.storage, &addr)?
+        .ok_or_else(|| ContractError::NotAMember {
+            addr: addr.to_string(),
+        })?;
+    let height = env.block.height;
+
+    let new = weight.unwrap_or(old);
+    if new != old {
+        let total = Uint64::from(TOTAL.load(deps.storage)?)
+            .checked_sub(Uint64::from(old))?
+            .checked_add(Uint64::from(new))?;
+        if new < old {
+            assert_min_active_total(&CONFIG.load(deps.storage)?, total.u64())?;
+        }
+        save_member(deps.storage, &addr, new, height)?;
+        save_total(deps.storage, total.u64(), height)?;
+        assert_weights(deps.as_ref())?;
     }
+    let old_identity = may_load_identity(deps.storage, &addr)?;
+    let identity = identity.filter(|identity| old_identity.as_ref() != Some(identity));
+    if let Some(identity) = &identity {
+        assert_identity(&CONFIG.load(deps.storage)?, &addr, identity)?;
+        save_identity(deps.storage, &addr, identity)?;
+    }
+
+    let mut messages = vec![];
+    if new != old || identity.is_some() {
+        let diffs = vec![MemberDiff::new(addr.as_str(), Some(old), Some(new))];
+        assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+        assert_diffs_unpaused(deps.storage, &diffs)?;
+        update_stats(deps.storage, &diffs)?;
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "update_member")
+        .add_attribute("addr", addr)
+        .add_attribute("weight", new.to_string())
+        .add_attribute("sender", info.sender))
 }
 
-// settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
+pub fn execute_import_packed(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    data: Binary,
+) -> Result<Response, ContractError> {
+    let members = packed::decode(&data)?;
+    let imported = members.len();
 
-pub fn query_list_members(
-    deps: Deps,
+    let diff = update_members(
+        deps.branch(),
+        env.block.height,
+        info.sender.clone(),
+        members,
+        vec![],
+    )?;
+    record_change(deps.storage, &env.block)?;
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "import_packed")
+        .add_attribute("imported", imported.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_import_merge(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    members: Vec<Member>,
+    strategy: MergeStrategy,
+) -> Result<Response, ContractError> {
+    let mut merged: Vec<Member> = vec![];
+    for member in members {
+        let addr = deps.api.addr_validate(&member.addr)?;
+        let pos = merged.iter().position(|m| m.addr == addr.as_str());
+        let present = match pos {
+            Some(pos) => Some(merged[pos].clone()),
+            None => match MEMBERS.may_load(deps.storage, &addr)? {
+                Some(weight) => Some(Member {
+                    addr: addr.to_string(),
+                    weight,
+                    identity: may_load_identity(deps.storage, &addr)?.unwrap_or_default(),
+                }),
+                None => None,
+            },
+        };
+        let member = match (present, &strategy) {
+            (Some(present), MergeStrategy::Sum) => Member {
+                weight: Uint64::from(present.weight)
+                    .checked_add(Uint64::from(member.weight))?
+                    .u64(),
+                ..present
+            },
+            (Some(present), MergeStrategy::Max) => Member {
+                weight: present.weight.max(member.weight),
+                ..present
+            },
+            _ => Member {
+                addr: addr.to_string(),
+                ..member
+            },
+        };
+        match pos {
+            Some(pos) => merged[pos] = member,
+            None => merged.push(member),
+        }
+    }
+
+    let count = merged.len();
+    let diff = update_members(
+        deps.branch(),
+        env.block.height,
+        info.sender.clone(),
+        merged,
+        vec![],
+    )?;
+    record_change(deps.storage, &env.block)?;
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "import_merge")
+        .add_attribute("merged", count.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_merge_from(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    source: String,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<MemberListResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let addr = maybe_addr(deps.api, start_after)?;
-    let start = addr.as_ref().map(Bound::exclusive);
+) -> Result<Response, ContractError> {
+    let source = deps.api.addr_validate(&source)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let page: MemberListResponse = deps.querier.query_wasm_smart(
+        &source,
+        &Cw4QueryMsg::ListMembers {
+            start_after,
+            limit: Some(limit),
+        },
+    )?;
+    let last = page.members.last().map(|m| m.addr.clone());
 
-    let members = MEMBERS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            item.map(|(addr, weight)| Member {
-                addr: addr.to_string(),
-                weight,
-                // This should always have been set
-                identity: IDS.load(deps.storage, &addr).unwrap(),
-            })
-        })
-        .collect::<StdResult<Vec<Member>>>()?;
+    let mut merged = vec![];
+    for member in page.members {
+        let addr = deps.api.addr_validate(&member.addr)?;
+        // existing members keep their own identity
+        let member = match MEMBERS.may_load(deps.storage, &addr)? {
+            Some(weight) => Member {
+                weight: Uint64::from(weight)
+                    .checked_add(Uint64::from(member.weight))?
+                    .u64(),
+                identity: may_load_identity(deps.storage, &addr)?.unwrap_or_default(),
+                addr: addr.into_string(),
+            },
+            None => member,
+        };
+        merged.push(member);
+    }
 
-    Ok(MemberListResponse { members })
+    let count = merged.len();
+    let diff = update_members(
+        deps.branch(),
+        env.block.height,
+        info.sender.clone(),
+        merged,
+        vec![],
+    )?;
+    record_change(deps.storage, &env.block)?;
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "merge_from")
+        .add_attribute("source", source)
+        .add_attribute("merged", count.to_string())
+        .add_attribute("last", last.unwrap_or_default())
+        .add_attribute("has_more", page.has_more.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+/// Adds or updates a member in one batch, registering it as a hook if `as_hook`
+pub fn execute_add_member_with_hook(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    member: Member,
+    as_hook: bool,
+) -> Result<Response, ContractError> {
+    let addr = deps.api.addr_validate(&member.addr)?;
+    let member = Member {
+        addr: addr.to_string(),
+        ..member
+    };
+
+    let diff = update_members(
+        deps.branch(),
+        env.block.height,
+        info.sender.clone(),
+        vec![member],
+        vec![],
+    )?;
+    if as_hook {
+        assert_hook_contract(deps.as_ref(), &addr)?;
+        HOOKS.add_hook(deps.storage, addr.clone())?;
+    }
+    record_change(deps.storage, &env.block)?;
+    // a new hook is told about its own addition
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "add_member_with_hook")
+        .add_attribute("addr", addr)
+        .add_attribute("as_hook", as_hook.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+/// Saves the new total and the merkle root of the member set at this height.
+/// Computing the root reads every member, so each change costs gas linear
+/// in the group size.
+pub fn save_total(storage: &mut dyn Storage, total: u64, height: u64) -> StdResult<()> {
+    TOTAL.save(storage, &total, height)?;
+    let leaves = MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(addr, weight)| merkle::leaf(addr.as_str(), weight)))
+        .collect::<StdResult<Vec<_>>>()?;
+    ROOTS.save(
+        storage,
+        height,
+        &Binary::from(merkle::root(&leaves).to_vec()),
+    )
+}
+
+/// Merkle root of the member set at the start of `height`, including members removed since
+fn historical_root(storage: &dyn Storage, height: u64) -> StdResult<Binary> {
+    let mut addrs = MEMBERS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut cursor: Option<Addr> = None;
+    loop {
+        let next = MEMBERS
+            .changelog()
+            .prefix_range(
+                storage,
+                cursor.as_ref().map(PrefixBound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?;
+        match next {
+            Some(((addr, _), _)) => {
+                addrs.push(addr.clone());
+                cursor = Some(addr);
+            }
+            None => break,
+        }
+    }
+    addrs.sort();
+    addrs.dedup();
+    let mut leaves = vec![];
+    for addr in addrs {
+        if let Some(weight) = MEMBERS.may_load_at_height(storage, &addr, height)? {
+            leaves.push(merkle::leaf(addr.as_str(), weight));
+        }
+    }
+    Ok(Binary::from(merkle::root(&leaves).to_vec()))
+}
+
+/// Scales all weights to sum to the configured target, if auto rebalancing is on.
+/// Weights are rounded down, then the remainder is added to the designated member.
+/// Fails if a locked member would move or the result breaks the weight guards.
+/// The diffs are appended to the last batch, so reverting it undoes both
+fn rebalance(storage: &mut dyn Storage, height: u64) -> Result<Vec<MemberDiff>, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let total = TOTAL.load(storage)?;
+    let target = config.rebalance_target;
+    // without any weight there is nothing to scale from
+    if !config.auto_rebalance || total == 0 || total == target {
+        return Ok(vec![]);
+    }
+
+    let members = MEMBERS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    // the heaviest member, the lowest address among equals
+    let heaviest = (0..members.len()).fold(0, |best, i| {
+        if members[i].1 > members[best].1 {
+            i
+        } else {
+            best
+        }
+    });
+    let absorber = config
+        .rebalance_remainder_to
+        .as_ref()
+        .and_then(|to| members.iter().position(|(addr, _)| addr == to))
+        .unwrap_or(heaviest);
+    let mut scaled: Vec<_> = members
+        .iter()
+        .map(|(_, weight)| (*weight as u128 * target as u128 / total as u128) as u64)
+        .collect();
+    scaled[absorber] += target - scaled.iter().sum::<u64>();
+
+    let mut diffs = vec![];
+    for ((addr, old), new) in members.into_iter().zip(scaled) {
+        assert_min_member_percent(&config, addr.as_str(), new, target)?;
+        if new != old {
+            assert_unlocked(storage, &addr)?;
+            save_member(storage, &addr, new, height)?;
+            diffs.push(MemberDiff::new(addr, Some(old), Some(new)));
+        }
+    }
+    assert_weight_delta(&config, &diffs)?;
+    save_total(storage, target, height)?;
+    assert_diffs_unpaused(storage, &diffs)?;
+    update_stats(storage, &diffs)?;
+    let mut batch = LAST_BATCH_DIFF.may_load(storage)?.unwrap_or_default();
+    batch.extend(diffs.iter().cloned());
+    LAST_BATCH_DIFF.save(storage, &batch)?;
+    Ok(diffs)
+}
+
+/// Remembers when the member set was last touched
+pub fn record_change(storage: &mut dyn Storage, block: &BlockInfo) -> StdResult<()> {
+    LAST_CHANGE_HEIGHT.save(storage, &block.height)?;
+    LAST_CHANGE_TIME.save(storage, &block.time)
+}
+
+/// Builds the submessages informing registered hooks of a membership diff.
+/// Hooks with a filter are skipped unless the diff touches one of their addresses.
+pub fn prepare_member_hooks(deps: Deps, diff: &MemberChangedHookMsg) -> StdResult<Vec<SubMsg>> {
+    let mut messages = vec![];
+    for hook in ordered_hooks(deps)? {
+        let filter = HOOK_FILTERS
+            .may_load(deps.storage, &hook)?
+            .unwrap_or_default();
+        let relevant = filter.is_empty()
+            || diff
+                .diffs
+                .iter()
+                .any(|d| filter.iter().any(|a| a.as_str() == d.key));
+        if relevant {
+            messages.push(SubMsg::new(diff.clone().into_cosmos_msg(hook)?));
+        }
+    }
+    messages.extend(prepare_quorum_hooks(deps, diff)?);
+    Ok(messages)
+}
+
+/// Notifies all hooks if the change moved the (already saved) total across the watched threshold
+fn prepare_quorum_hooks(deps: Deps, diff: &MemberChangedHookMsg) -> StdResult<Vec<SubMsg>> {
+    let threshold = match CONFIG.load(deps.storage)?.quorum_watch {
+        Some(threshold) => threshold,
+        None => return Ok(vec![]),
+    };
+    let new_total = TOTAL.load(deps.storage)?;
+    let added: u64 = diff.diffs.iter().filter_map(|d| d.new).sum();
+    let removed: u64 = diff.diffs.iter().filter_map(|d| d.old).sum();
+    let old_total = new_total + removed - added;
+
+    let direction = match (old_total >= threshold, new_total >= threshold) {
+        (false, true) => CrossingDirection::Up,
+        (true, false) => CrossingDirection::Down,
+        _ => return Ok(vec![]),
+    };
+    let msg = QuorumCrossedHookMsg {
+        threshold,
+        new_total,
+        direction,
+    };
+    ordered_hooks(deps)?
+        .into_iter()
+        .map(|hook| Ok(SubMsg::new(msg.clone().into_cosmos_msg(hook)?)))
+        .collect()
+}
+
+/// All registered hooks, highest priority first. The sort is stable, so hooks
+/// sharing a priority keep their registration order
+fn ordered_hooks(deps: Deps) -> StdResult<Vec<Addr>> {
+    let mut hooks = HOOKS
+        .query_hooks(deps)?
+        .hooks
+        .into_iter()
+        .map(|hook| {
+            // hooks are validated when registered
+            let hook = Addr::unchecked(hook);
+            let priority = HOOK_PRIORITY
+                .may_load(deps.storage, &hook)?
+                .unwrap_or_default();
+            Ok((priority, hook))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    hooks.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+    Ok(hooks.into_iter().map(|(_, hook)| hook).collect())
+}
+
+// the logic from execute_update_members extracted for easier import
+pub fn update_members(
+    deps: DepsMut,
+    height: u64,
+    sender: Addr,
+    to_add: Vec<Member>,
+    to_remove: Vec<String>,
+) -> Result<MemberChangedHookMsg, ContractError> {
+    assert_admin(deps.as_ref(), &sender)?;
+    apply_member_changes(deps, height, to_add, to_remove)
+}
+
+// update_members without the sender check, for callers authorized otherwise
+fn apply_member_changes(
+    deps: DepsMut,
+    height: u64,
+    to_add: Vec<Member>,
+    to_remove: Vec<String>,
+) -> Result<MemberChangedHookMsg, ContractError> {
+    assert_not_frozen(deps.storage)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs: Vec<MemberDiff> = vec![];
+    let mut removed = false;
+
+    // add all new members and update total
+    for add in to_add.into_iter() {
+        let add_addr = deps.api.addr_validate(&add.addr)?;
+        add_checks(deps.storage, &config, &add_addr, &add.identity)
+            .into_iter()
+            .collect::<Result<(), _>>()?;
+        save_identity(deps.storage, &add_addr, &add.identity)?;
+        // re-adding a member cancels any scheduled removal
+        PENDING_REMOVALS.remove(deps.storage, &add_addr);
+        let old = save_member(deps.storage, &add_addr, add.weight, height)?;
+        total = total.checked_sub(Uint64::from(old.unwrap_or_default()))?;
+        total = total.checked_add(Uint64::from(add.weight))?;
+        diffs.push(MemberDiff::new(add.addr, old, Some(add.weight)));
+    }
+
+    for remove in to_remove.into_iter() {
+        let remove_addr = deps.api.addr_validate(&remove)?;
+        assert_unlocked(deps.storage, &remove_addr)?;
+        if config.strict_removals && MEMBERS.may_load(deps.storage, &remove_addr)?.is_none() {
+            return Err(ContractError::NotAMember { addr: remove });
+        }
+        // with a grace period, members keep their weight until the removal is finalized
+        if let Some(grace) = config.removal_grace_blocks {
+            if MEMBERS.may_load(deps.storage, &remove_addr)?.is_some() {
+                assert_op_unpaused(deps.storage, OpKind::Remove)?;
+                PENDING_REMOVALS.save(deps.storage, &remove_addr, &(height + grace))?;
+            }
+            continue;
+        }
+        // Only process this if they were actually in the list before
+        if let Some(weight) = remove_member(deps.storage, &remove_addr, height)? {
+            diffs.push(MemberDiff::new(remove, Some(weight), None));
+            total = total.checked_sub(Uint64::from(weight))?;
+            removed = true;
+        }
+    }
+
+    if removed {
+        assert_min_active_total(&config, total.u64())?;
+    }
+    // the floor applies to the shares left after the whole batch
+    for diff in &diffs {
+        if let Some(weight) = diff.new {
+            assert_min_member_percent(&config, &diff.key, weight, total.u64())?;
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_weight_delta(&config, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    LAST_BATCH_DIFF.save(deps.storage, &diffs)?;
+    LAST_BATCH_HEIGHT.save(deps.storage, &height)?;
+    Ok(MemberChangedHookMsg { diffs })
+}
+
+/// Folds a batch of member diffs into the lifetime counters
+pub fn update_stats(storage: &mut dyn Storage, diffs: &[MemberDiff]) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    for diff in diffs {
+        let old = diff.old.unwrap_or_default();
+        let new = diff.new.unwrap_or_default();
+        match (diff.old, diff.new) {
+            (None, Some(_)) => stats.total_adds += 1,
+            (Some(_), None) => stats.total_removals += 1,
+            _ => {}
+        }
+        stats.total_weight_added += new.saturating_sub(old);
+    }
+    STATS.save(storage, &stats)
+}
+
+/// Bumps the admin change counter. Groups migrated from before it was kept start at 0
+fn count_admin_change(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    stats.admin_changes += 1;
+    STATS.save(storage, &stats)
+}
+
+fn index_identity(storage: &mut dyn Storage, identity: &str, addr: &Addr) -> StdResult<()> {
+    let mut addrs = IDENTITY_INDEX
+        .may_load(storage, identity)?
+        .unwrap_or_default();
+    if let Err(pos) = addrs.binary_search(addr) {
+        addrs.insert(pos, addr.clone());
+        IDENTITY_INDEX.save(storage, identity, &addrs)?;
+    }
+    Ok(())
+}
+
+fn unindex_identity(storage: &mut dyn Storage, identity: &str, addr: &Addr) -> StdResult<()> {
+    let mut addrs = IDENTITY_INDEX
+        .may_load(storage, identity)?
+        .unwrap_or_default();
+    addrs.retain(|a| a != addr);
+    if addrs.is_empty() {
+        IDENTITY_INDEX.remove(storage, identity);
+        Ok(())
+    } else {
+        IDENTITY_INDEX.save(storage, identity, &addrs)
+    }
+}
+
+// sets a member's identity, keeping the identity index in sync
+fn save_identity(storage: &mut dyn Storage, addr: &Addr, identity: &str) -> StdResult<()> {
+    if let Some(old) = may_load_identity(storage, addr)? {
+        unindex_identity(storage, &old, addr)?;
+    }
+    index_identity(storage, identity, addr)?;
+    let id = intern_identity(storage, identity)?;
+    IDS.save(storage, addr, &id)
+}
+
+// returns the pool id of an identity, adding it to the pool if new
+fn intern_identity(storage: &mut dyn Storage, identity: &str) -> StdResult<u64> {
+    if let Some(id) = IDENTITY_POOL_IDS.may_load(storage, identity)? {
+        return Ok(id);
+    }
+    let id = IDENTITY_POOL_SIZE.may_load(storage)?.unwrap_or_default();
+    IDENTITY_POOL.save(storage, id, &identity.to_string())?;
+    IDENTITY_POOL_IDS.save(storage, identity, &id)?;
+    IDENTITY_POOL_SIZE.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
+fn may_load_identity(storage: &dyn Storage, addr: &Addr) -> StdResult<Option<String>> {
+    IDS.may_load(storage, addr)?
+        .map(|id| IDENTITY_POOL.load(storage, id))
+        .transpose()
+}
+
+fn load_identity(storage: &dyn Storage, addr: &Addr) -> StdResult<String> {
+    IDS.load(storage, addr)
+        .and_then(|id| IDENTITY_POOL.load(storage, id))
+}
+
+// sets a member's join height, keeping the join index in sync
+fn set_joined_at(storage: &mut dyn Storage, addr: &Addr, height: u64) -> StdResult<()> {
+    if let Some(old) = JOINED_AT.may_load(storage, addr)? {
+        MEMBERS_BY_JOIN.remove(storage, (old, addr));
+    }
+    JOINED_AT.save(storage, addr, &height)?;
+    MEMBERS_BY_JOIN.save(storage, (height, addr), &())
+}
+
+// sets a member's weight, keeping the weight index and member count in sync.
+// Returns the previous weight
+fn save_member(
+    storage: &mut dyn Storage,
+    addr: &Addr,
+    weight: u64,
+    height: u64,
+) -> StdResult<Option<u64>> {
+    let old = MEMBERS.may_load(storage, addr)?;
+    match old {
+        Some(old) => MEMBERS_BY_WEIGHT.remove(storage, (old, addr)),
+        None => {
+            update_member_count(storage, 1)?;
+            set_joined_at(storage, addr, height)?;
+        }
+    }
+    MEMBERS.save(storage, addr, &weight, height)?;
+    MEMBERS_BY_WEIGHT.save(storage, (weight, addr), &())?;
+    if old.is_some_and(|old| weight < old) {
+        trim_delegations(storage, addr, weight)?;
+    }
+    Ok(old)
+}
+
+// trims the delegations made by a member, in address order, to fit its weight
+fn trim_delegations(storage: &mut dyn Storage, addr: &Addr, weight: u64) -> StdResult<()> {
+    let outgoing = PARTIAL_DELEGATIONS
+        .prefix(addr)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut remaining = weight;
+    for (to, amount) in outgoing {
+        let kept = amount.min(remaining);
+        remaining -= kept;
+        if kept != amount {
+            save_delegation(storage, addr, &to, kept)?;
+        }
+    }
+    Ok(())
+}
+
+// drops a member from the group, returning the weight it held
+fn remove_member(storage: &mut dyn Storage, addr: &Addr, height: u64) -> StdResult<Option<u64>> {
+    let old = MEMBERS.may_load(storage, addr)?;
+    if old.is_some() {
+        MEMBERS.remove(storage, addr, height)?;
+        MEMBERS_BY_WEIGHT.remove(storage, (old.unwrap_or_default(), addr));
+        update_member_count(storage, -1)?;
+        if let Some(joined) = JOINED_AT.may_load(storage, addr)? {
+            MEMBERS_BY_JOIN.remove(storage, (joined, addr));
+            JOINED_AT.remove(storage, addr);
+        }
+        PENDING_REMOVALS.remove(storage, addr);
+        if let Some(identity) = may_load_identity(storage, addr)? {
+            unindex_identity(storage, &identity, addr)?;
+        }
+        clear_tags(storage, addr)?;
+        clear_delegations(storage, addr)?;
+        FROZEN_MEMBERS.remove(storage, addr);
+        LOCKED_MEMBERS.remove(storage, addr);
+        VESTING.remove(storage, addr);
+        EXPIRY.remove(storage, addr);
+        if let Some(external_id) = EXTERNAL_IDS.may_load(storage, addr)? {
+            EXTERNAL_ID_OWNERS.remove(storage, &external_id);
+            EXTERNAL_IDS.remove(storage, addr);
+        }
+        for alias in aliases_of(storage, addr)? {
+            ALIASES.remove(storage, &alias);
+        }
+    }
+    Ok(old)
+}
+
+/// Aliases pointing at `addr`
+fn aliases_of(storage: &dyn Storage, addr: &Addr) -> StdResult<Vec<Addr>> {
+    ALIASES
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((alias, target)) if target == *addr => Some(Ok(alias)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
+fn update_member_count(storage: &mut dyn Storage, delta: i64) -> StdResult<()> {
+    let count = MEMBER_COUNT.may_load(storage)?.unwrap_or_default();
+    MEMBER_COUNT.save(storage, &((count as i64 + delta) as u64))
+}
+
+fn save_delegation(
+    storage: &mut dyn Storage,
+    from: &Addr,
+    to: &Addr,
+    amount: u64,
+) -> StdResult<()> {
+    if amount == 0 {
+        PARTIAL_DELEGATIONS.remove(storage, (from, to));
+        INCOMING_DELEGATIONS.remove(storage, (to, from));
+        Ok(())
+    } else {
+        PARTIAL_DELEGATIONS.save(storage, (from, to), &amount)?;
+        INCOMING_DELEGATIONS.save(storage, (to, from), &amount)
+    }
+}
+
+// drops all delegations made or received by a member
+fn clear_delegations(storage: &mut dyn Storage, addr: &Addr) -> StdResult<()> {
+    let outgoing = PARTIAL_DELEGATIONS
+        .prefix(addr)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for to in outgoing {
+        save_delegation(storage, addr, &to, 0)?;
+    }
+    let incoming = INCOMING_DELEGATIONS
+        .prefix(addr)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for from in incoming {
+        save_delegation(storage, &from, addr, 0)?;
+    }
+    Ok(())
+}
+
+// frozen and expired members keep their seat but control no weight
+fn is_inactive(storage: &dyn Storage, block: &BlockInfo, addr: &Addr) -> StdResult<bool> {
+    Ok(is_frozen_member(storage, addr)? || is_expired_member(storage, block, addr)?)
+}
+
+fn is_frozen_member(storage: &dyn Storage, addr: &Addr) -> StdResult<bool> {
+    Ok(FROZEN_MEMBERS.may_load(storage, addr)?.is_some())
+}
+
+fn is_expired_member(storage: &dyn Storage, block: &BlockInfo, addr: &Addr) -> StdResult<bool> {
+    Ok(EXPIRY
+        .may_load(storage, addr)?
+        .is_some_and(|expires| expires.is_expired(block)))
+}
+
+fn sum_delegations(
+    storage: &dyn Storage,
+    map: Map<(&Addr, &Addr), u64>,
+    addr: &Addr,
+) -> StdResult<u64> {
+    map.prefix(addr)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, amount)| amount))
+        .sum()
+}
+
+fn clear_tags(storage: &mut dyn Storage, addr: &Addr) -> StdResult<()> {
+    for tag in MEMBER_TAGS.may_load(storage, addr)?.unwrap_or_default() {
+        TAGS.remove(storage, (&tag, addr));
+    }
+    MEMBER_TAGS.remove(storage, addr);
+    Ok(())
+}
+
+pub fn execute_finalize_removals(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    assert_not_frozen(deps.storage)?;
+    let height = env.block.height;
+    let due = PENDING_REMOVALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| matches!(item, Ok((_, remove_at)) if *remove_at <= height))
+        .map(|item| item.map(|(addr, _)| addr))
+        .filter(|item| !matches!(item, Ok(addr) if LOCKED_MEMBERS.has(deps.storage, addr)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    for addr in due {
+        if let Some(weight) = remove_member(deps.storage, &addr, height)? {
+            diffs.push(MemberDiff::new(addr, Some(weight), None));
+            total = total.checked_sub(Uint64::from(weight))?;
+        }
+    }
+    if !diffs.is_empty() {
+        assert_min_active_total(&CONFIG.load(deps.storage)?, total.u64())?;
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+
+    let removed = diffs.len();
+    let mut messages = vec![];
+    if removed > 0 {
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "finalize_removals")
+        .add_attribute("removed", removed.to_string()))
+}
+
+pub fn execute_prune_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin_or_operator(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    let height = env.block.height;
+    let expired = EXPIRY
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(addr, expires)| {
+                expires.is_expired(&env.block) && !LOCKED_MEMBERS.has(deps.storage, addr)
+            })
+        })
+        .take(MAX_LIMIT as usize)
+        .map(|item| item.map(|(addr, _)| addr))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    for addr in expired {
+        if let Some(weight) = remove_member(deps.storage, &addr, height)? {
+            diffs.push(MemberDiff::new(addr, Some(weight), None));
+            total = total.checked_sub(Uint64::from(weight))?;
+        }
+    }
+    assert_min_active_total(&CONFIG.load(deps.storage)?, total.u64())?;
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+
+    let removed = diffs.len();
+    let mut messages = vec![];
+    if removed > 0 {
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "prune_expired")
+        .add_attribute("removed", removed.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    operator: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    let action = if operator {
+        OPERATORS.save(deps.storage, &addr, &())?;
+        "add_operator"
+    } else {
+        OPERATORS.remove(deps.storage, &addr);
+        "remove_operator"
+    };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("addr", addr)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_propose_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    PENDING_ADMIN.save(deps.storage, &(addr.clone(), env.block.height))?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_admin")
+        .add_attribute("proposed", addr)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_accept_admin(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (proposed, height) = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoAdminProposal {})?;
+    if info.sender != proposed {
+        return Err(ContractError::Unauthorized {});
+    }
+    let notice = CONFIG
+        .load(deps.storage)?
+        .admin_change_notice_blocks
+        .unwrap_or_default();
+    let ready_at = height + notice;
+    if env.block.height < ready_at {
+        return Err(ContractError::NoticePeriodNotMet { ready_at });
+    }
+
+    ADMIN.set(deps.branch(), Some(proposed.clone()))?;
+    TEMP_ADMIN.remove(deps.storage);
+    PENDING_ADMIN.remove(deps.storage);
+    count_admin_change(deps.storage)?;
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", proposed))
+}
+
+pub fn execute_set_admin_change_notice(
+    deps: DepsMut,
+    info: MessageInfo,
+    blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.admin_change_notice_blocks = blocks;
+        Ok(config)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_admin_change_notice")
+        .add_attribute(
+            "blocks",
+            blocks.map_or("none".to_string(), |b| b.to_string()),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+// with a notice period, the admin may only be handed over through ProposeAdmin
+fn assert_no_admin_notice(storage: &dyn Storage) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.admin_change_notice_blocks.is_some() {
+        return Err(ContractError::AdminChangeNeedsNotice {});
+    }
+    Ok(())
+}
+
+pub fn execute_rotate_control(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+    new_operators: Vec<String>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_no_admin_notice(deps.storage)?;
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    let new_operators = new_operators
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let old_operators = OPERATORS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for addr in old_operators {
+        OPERATORS.remove(deps.storage, &addr);
+    }
+    for addr in &new_operators {
+        OPERATORS.save(deps.storage, addr, &())?;
+    }
+    ADMIN.set(deps.branch(), Some(new_admin.clone()))?;
+    TEMP_ADMIN.remove(deps.storage);
+    PENDING_ADMIN.remove(deps.storage);
+    count_admin_change(deps.storage)?;
+    Ok(Response::new()
+        .add_attribute("action", "rotate_control")
+        .add_attribute("admin", new_admin)
+        .add_attribute("operators", new_operators.len().to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_cancel_removal(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if !PENDING_REMOVALS.has(deps.storage, &addr) {
+        return Err(ContractError::NoPendingRemoval {
+            addr: addr.into_string(),
+        });
+    }
+    PENDING_REMOVALS.remove(deps.storage, &addr);
+    Ok(Response::new()
+        .add_attribute("action", "cancel_removal")
+        .add_attribute("addr", addr)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_supersede(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    old: String,
+    new: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    let old = deps.api.addr_validate(&old)?;
+    let new = deps.api.addr_validate(&new)?;
+    assert_unlocked(deps.storage, &old)?;
+    let height = env.block.height;
+    let weight =
+        MEMBERS
+            .may_load(deps.storage, &old)?
+            .ok_or_else(|| ContractError::NotAMember {
+                addr: old.to_string(),
+            })?;
+    if MEMBERS.may_load(deps.storage, &new)?.is_some() {
+        return Err(ContractError::AlreadyAMember {
+            addr: new.into_string(),
+        });
+    }
+
+    // collect everything before remove_member clears it
+    let identity = may_load_identity(deps.storage, &old)?.unwrap_or_default();
+    let joined_at = JOINED_AT.may_load(deps.storage, &old)?;
+    let tags = MEMBER_TAGS
+        .may_load(deps.storage, &old)?
+        .unwrap_or_default();
+    let outgoing = PARTIAL_DELEGATIONS
+        .prefix(&old)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let incoming = INCOMING_DELEGATIONS
+        .prefix(&old)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let external_id = EXTERNAL_IDS.may_load(deps.storage, &old)?;
+    let expiry = EXPIRY.may_load(deps.storage, &old)?;
+    let vesting = VESTING.may_load(deps.storage, &old)?;
+    let frozen = FROZEN_MEMBERS.has(deps.storage, &old);
+    let aliases = aliases_of(deps.storage, &old)?;
+
+    remove_member(deps.storage, &old, height)?;
+    IDS.remove(deps.storage, &old);
+    save_identity(deps.storage, &new, &identity)?;
+    PENDING_REMOVALS.remove(deps.storage, &new);
+    save_member(deps.storage, &new, weight, height)?;
+    if let Some(joined_at) = joined_at {
+        set_joined_at(deps.storage, &new, joined_at)?;
+    }
+    for tag in &tags {
+        TAGS.save(deps.storage, (tag, &new), &())?;
+    }
+    if !tags.is_empty() {
+        MEMBER_TAGS.save(deps.storage, &new, &tags)?;
+    }
+    for (to, amount) in &outgoing {
+        save_delegation(deps.storage, &new, to, *amount)?;
+    }
+    for (from, amount) in &incoming {
+        save_delegation(deps.storage, from, &new, *amount)?;
+    }
+    if let Some(external_id) = &external_id {
+        EXTERNAL_IDS.save(deps.storage, &new, external_id)?;
+        EXTERNAL_ID_OWNERS.save(deps.storage, external_id, &new)?;
+    }
+    if let Some(expiry) = &expiry {
+        EXPIRY.save(deps.storage, &new, expiry)?;
+    }
+    if let Some(vesting) = &vesting {
+        VESTING.save(deps.storage, &new, vesting)?;
+    }
+    if frozen {
+        FROZEN_MEMBERS.save(deps.storage, &new, &())?;
+    }
+    // aliases follow the seat, and `new` can no longer be an alias itself
+    ALIASES.remove(deps.storage, &new);
+    for alias in &aliases {
+        ALIASES.save(deps.storage, alias, &new)?;
+    }
+    // the total is unchanged, the seat only moves, but the root must follow it
+    let total = TOTAL.load(deps.storage)?;
+    save_total(deps.storage, total, height)?;
+
+    let diffs = vec![
+        MemberDiff::new(old.as_str(), Some(weight), None),
+        MemberDiff::new(new.as_str(), None, Some(weight)),
+    ];
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    let event = Event::new("supersede")
+        .add_attribute("old", &old)
+        .add_attribute("new", &new)
+        .add_attribute("weight", weight.to_string())
+        .add_attribute("identity", identity)
+        .add_attribute("tags", tags.join(","))
+        .add_attribute("delegations_out", outgoing.len().to_string())
+        .add_attribute("delegations_in", incoming.len().to_string())
+        .add_attribute("external_id", external_id.unwrap_or_default())
+        .add_attribute("frozen", frozen.to_string());
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_event(event)
+        .add_attribute("action", "supersede")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_transfer_admin_and_seat(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_addr: String,
+    identity: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_no_admin_notice(deps.storage)?;
+    assert_not_frozen(deps.storage)?;
+    let new_admin = deps.api.addr_validate(&new_addr)?;
+    let height = env.block.height;
+    assert_identity(&CONFIG.load(deps.storage)?, &new_admin, &identity)?;
+    assert_unlocked(deps.storage, &info.sender)?;
+    assert_unlocked(deps.storage, &new_admin)?;
+
+    let weight = MEMBERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NotAMember {
+            addr: info.sender.to_string(),
+        })?;
+
+    // the seat moves immediately, regardless of any removal grace period
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    remove_member(deps.storage, &info.sender, height)?;
+    total = total.checked_sub(Uint64::from(weight))?;
+    diffs.push(MemberDiff::new(info.sender.as_str(), Some(weight), None));
+
+    save_identity(deps.storage, &new_admin, &identity)?;
+    PENDING_REMOVALS.remove(deps.storage, &new_admin);
+    let old = save_member(deps.storage, &new_admin, weight, height)?;
+    total = total.checked_sub(Uint64::from(old.unwrap_or_default()))?;
+    total = total.checked_add(Uint64::from(weight))?;
+    diffs.push(MemberDiff::new(new_admin.as_str(), old, Some(weight)));
+    save_total(deps.storage, total.u64(), height)?;
+
+    ADMIN.set(deps.branch(), Some(new_admin.clone()))?;
+    TEMP_ADMIN.remove(deps.storage);
+    PENDING_ADMIN.remove(deps.storage);
+    count_admin_change(deps.storage)?;
+    assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+    assert_weights(deps.as_ref())?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "transfer_admin_and_seat")
+        .add_attribute("new_addr", new_admin)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_quorum_watch(
+    deps: DepsMut,
+    info: MessageInfo,
+    threshold: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.quorum_watch = threshold;
+        Ok(config)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_quorum_watch")
+        .add_attribute(
+            "threshold",
+            threshold.map_or("none".to_string(), |t| t.to_string()),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_rewrite_identities(
+    deps: DepsMut,
+    info: MessageInfo,
+    from_prefix: String,
+    to_prefix: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut matches = vec![];
+    for bucket in IDENTITY_INDEX.range(
+        deps.storage,
+        Some(Bound::inclusive(from_prefix.as_str())),
+        None,
+        Order::Ascending,
+    ) {
+        let (identity, addrs) = bucket?;
+        if !identity.starts_with(&from_prefix) {
+            break;
+        }
+        matches.extend(addrs.into_iter().map(|addr| (identity.clone(), addr)));
+        // all or nothing, rather than leaving a half rewritten group
+        if matches.len() > MAX_REWRITES {
+            return Err(ContractError::TooManyAddresses {
+                max: MAX_REWRITES as u32,
+            });
+        }
+    }
+
+    let rewritten = matches.len();
+    for (identity, addr) in matches {
+        let identity = format!("{}{}", to_prefix, &identity[from_prefix.len()..]);
+        assert_identity(&config, &addr, &identity)?;
+        save_identity(deps.storage, &addr, &identity)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "rewrite_identities")
+        .add_attribute("from_prefix", from_prefix)
+        .add_attribute("to_prefix", to_prefix)
+        .add_attribute("rewritten", rewritten.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_external_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    external_id: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+    if let Some(owner) = EXTERNAL_ID_OWNERS.may_load(deps.storage, &external_id)? {
+        if owner != addr {
+            return Err(ContractError::ExternalIdTaken {
+                external_id,
+                owner: owner.into_string(),
+            });
+        }
+    }
+
+    if let Some(previous) = EXTERNAL_IDS.may_load(deps.storage, &addr)? {
+        EXTERNAL_ID_OWNERS.remove(deps.storage, &previous);
+    }
+    EXTERNAL_IDS.save(deps.storage, &addr, &external_id)?;
+    EXTERNAL_ID_OWNERS.save(deps.storage, &external_id, &addr)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_external_id")
+        .add_attribute("addr", addr)
+        .add_attribute("external_id", external_id)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_propose_emergency_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let recovery = CONFIG
+        .load(deps.storage)?
+        .emergency_recovery
+        .ok_or(ContractError::RecoveryDisabled {})?;
+    if MEMBERS.may_load(deps.storage, &info.sender)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: info.sender.into_string(),
+        });
+    }
+    if let Some(proposal) = EMERGENCY_PROPOSAL.may_load(deps.storage)? {
+        if env.block.height < proposal.expires_at {
+            return Err(ContractError::EmergencyProposalOpen {});
+        }
+        clear_emergency_proposal(deps.storage)?;
+    }
+
+    let proposal = EmergencyProposal {
+        addr: deps.api.addr_validate(&addr)?,
+        expires_at: env.block.height + recovery.voting_blocks,
+    };
+    EMERGENCY_PROPOSAL.save(deps.storage, &proposal)?;
+    let res = Response::new()
+        .add_attribute("action", "propose_emergency_admin")
+        .add_attribute("addr", &proposal.addr)
+        .add_attribute("expires_at", proposal.expires_at.to_string());
+    // the proposer votes for their own proposal
+    let vote = vote_emergency_admin(deps, &recovery, proposal, info.sender)?;
+    Ok(res.add_attributes(vote.attributes))
+}
+
+pub fn execute_vote_emergency_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let recovery = CONFIG
+        .load(deps.storage)?
+        .emergency_recovery
+        .ok_or(ContractError::RecoveryDisabled {})?;
+    if MEMBERS.may_load(deps.storage, &info.sender)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: info.sender.into_string(),
+        });
+    }
+    let proposal = match EMERGENCY_PROPOSAL.may_load(deps.storage)? {
+        Some(proposal) if env.block.height < proposal.expires_at => proposal,
+        _ => return Err(ContractError::NoEmergencyProposal {}),
+    };
+    let vote = vote_emergency_admin(deps, &recovery, proposal, info.sender)?;
+    Ok(Response::new()
+        .add_attribute("action", "vote_emergency_admin")
+        .add_attributes(vote.attributes))
+}
+
+/// Records the vote and appoints the proposed admin once the supermajority is exceeded
+fn vote_emergency_admin(
+    mut deps: DepsMut,
+    recovery: &EmergencyRecovery,
+    proposal: EmergencyProposal,
+    voter: Addr,
+) -> Result<Response, ContractError> {
+    EMERGENCY_VOTES.save(deps.storage, &voter, &())?;
+    let votes = emergency_votes(deps.as_ref())?;
+    let total = TOTAL.load(deps.storage)?;
+
+    let one = Decimal::one().atomics().u128();
+    let passed = votes as u128 * one > total as u128 * recovery.supermajority.atomics().u128();
+    if passed {
+        clear_emergency_proposal(deps.storage)?;
+        ADMIN.set(deps.branch(), Some(proposal.addr.clone()))?;
+        TEMP_ADMIN.remove(deps.storage);
+        PENDING_ADMIN.remove(deps.storage);
+        count_admin_change(deps.storage)?;
+    }
+    Ok(Response::new()
+        .add_attribute("voter", voter)
+        .add_attribute("votes", votes.to_string())
+        .add_attribute("passed", passed.to_string()))
+}
+
+fn emergency_votes(deps: Deps) -> StdResult<u64> {
+    EMERGENCY_VOTES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|voter| Ok(MEMBERS.may_load(deps.storage, &voter?)?.unwrap_or_default()))
+        .sum()
+}
+
+fn clear_emergency_proposal(storage: &mut dyn Storage) -> StdResult<()> {
+    let voters = EMERGENCY_VOTES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters {
+        EMERGENCY_VOTES.remove(storage, &voter);
+    }
+    EMERGENCY_PROPOSAL.remove(storage);
+    Ok(())
+}
+
+pub fn execute_save_checkpoint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_length("name", &name, 1, MAX_NAME_LEN)?;
+    CHECKPOINTS.save(deps.storage, &name, &env.block.height)?;
+    Ok(Response::new()
+        .add_attribute("action", "save_checkpoint")
+        .add_attribute("name", name)
+        .add_attribute("height", env.block.height.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    FROZEN.save(deps.storage, &frozen)?;
+    let action = if frozen { "freeze" } else { "unfreeze" };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_paused_ops(
+    deps: DepsMut,
+    info: MessageInfo,
+    ops: Vec<OpKind>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let mut paused: Vec<OpKind> = vec![];
+    for op in ops {
+        if !paused.contains(&op) {
+            paused.push(op);
+        }
+    }
+    let names: Vec<String> = paused.iter().map(|op| format!("{:?}", op)).collect();
+    PAUSED_OPS.save(deps.storage, &paused)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_paused_ops")
+        .add_attribute("ops", names.join(","))
+        .add_attribute("sender", info.sender))
+}
+
+// messages that change who controls the group, as opposed to member changes,
+// which are judged per diff by assert_diffs_unpaused
+fn control_op(msg: &ExecuteMsg) -> Option<OpKind> {
+    match msg {
+        ExecuteMsg::UpdateAdmin { .. }
+        | ExecuteMsg::ProposeAdmin { .. }
+        | ExecuteMsg::AcceptAdmin {}
+        | ExecuteMsg::DelegateAdmin { .. }
+        | ExecuteMsg::RotateControl { .. }
+        | ExecuteMsg::TransferAdminAndSeat { .. } => Some(OpKind::AdminChange),
+        ExecuteMsg::AddHook { .. }
+        | ExecuteMsg::AddHookFiltered { .. }
+        | ExecuteMsg::RemoveHook { .. }
+        | ExecuteMsg::SetHooks { .. }
+        | ExecuteMsg::AddMemberWithHook { as_hook: true, .. } => Some(OpKind::HookChange),
+        _ => None,
+    }
+}
+
+fn assert_op_unpaused(storage: &dyn Storage, op: OpKind) -> Result<(), ContractError> {
+    if PAUSED_OPS
+        .may_load(storage)?
+        .unwrap_or_default()
+        .contains(&op)
+    {
+        return Err(ContractError::OperationPaused { op });
+    }
+    Ok(())
+}
+
+fn assert_diffs_unpaused(storage: &dyn Storage, diffs: &[MemberDiff]) -> Result<(), ContractError> {
+    for diff in diffs {
+        let op = match (diff.old, diff.new) {
+            (None, Some(_)) => OpKind::Add,
+            (Some(_), None) => OpKind::Remove,
+            (Some(old), Some(new)) if old != new => OpKind::Reweight,
+            _ => continue,
+        };
+        assert_op_unpaused(storage, op)?;
+    }
+    Ok(())
+}
+
+pub fn execute_set_freeze_window(
+    deps: DepsMut,
+    info: MessageInfo,
+    start: Timestamp,
+    end: Timestamp,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    if end <= start {
+        return Err(ContractError::InvalidFreezeWindow {});
+    }
+    FREEZE_WINDOW.save(deps.storage, &(start, end))?;
+    Ok(Response::new()
+        .add_attribute("action", "set_freeze_window")
+        .add_attribute("start", start.to_string())
+        .add_attribute("end", end.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_clear_freeze_window(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    FREEZE_WINDOW.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "clear_freeze_window")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_dissolve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    Ok(dissolve(deps, &env, "dissolve")?.add_attribute("sender", info.sender))
+}
+
+pub fn execute_check_expiry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if !is_expired(deps.as_ref(), &env)? {
+        return Ok(Response::new()
+            .add_attribute("action", "check_expiry")
+            .add_attribute("expired", "false"));
+    }
+    Ok(dissolve(deps, &env, "check_expiry")?
+        .add_attribute("expired", "true")
+        .add_attribute("sender", info.sender))
+}
+
+fn is_expired(deps: Deps, env: &Env) -> StdResult<bool> {
+    Ok(CONFIG
+        .load(deps.storage)?
+        .expires_at
+        .is_some_and(|expiration| expiration.is_expired(&env.block)))
+}
+
+// removes every member and blocks further changes
+fn dissolve(deps: DepsMut, env: &Env, action: &str) -> Result<Response, ContractError> {
+    let height = env.block.height;
+
+    let members = MEMBERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut diffs = vec![];
+    let mut removed_weight = 0u64;
+    for addr in members {
+        if let Some(weight) = remove_member(deps.storage, &addr, height)? {
+            removed_weight += weight;
+            diffs.push(MemberDiff::new(addr, Some(weight), None));
+        }
+    }
+    save_total(deps.storage, 0, height)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+    DISSOLVED_AT.save(deps.storage, &height)?;
+
+    let removed = diffs.len();
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", action)
+        .add_attribute("removed", removed.to_string())
+        .add_attribute("removed_weight", removed_weight.to_string())
+        .add_attribute("height", height.to_string()))
+}
+
+pub fn execute_apply_deltas(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    deltas: Vec<(String, i64)>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let height = env.block.height;
+    let old_total = TOTAL.load(deps.storage)?;
+    let mut total = Uint64::from(old_total);
+    let mut diffs = vec![];
+    for (addr, delta) in deltas {
+        let addr = deps.api.addr_validate(&addr)?;
+        assert_unlocked(deps.storage, &addr)?;
+        let weight =
+            MEMBERS
+                .may_load(deps.storage, &addr)?
+                .ok_or_else(|| ContractError::NotAMember {
+                    addr: addr.to_string(),
+                })?;
+        let new_weight = if delta >= 0 {
+            Uint64::from(weight).checked_add(Uint64::from(delta.unsigned_abs()))?
+        } else {
+            Uint64::from(weight).checked_sub(Uint64::from(delta.unsigned_abs()))?
+        }
+        .u64();
+        total = total
+            .checked_sub(Uint64::from(weight))?
+            .checked_add(Uint64::from(new_weight))?;
+        save_member(deps.storage, &addr, new_weight, height)?;
+        diffs.push(MemberDiff::new(addr, Some(weight), Some(new_weight)));
+    }
+
+    if total.u64() < old_total {
+        assert_min_active_total(&config, total.u64())?;
+    }
+    for diff in &diffs {
+        if let Some(weight) = diff.new {
+            assert_min_member_percent(&config, &diff.key, weight, total.u64())?;
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_weight_delta(&config, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let applied = diffs.len();
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "apply_deltas")
+        .add_attribute("applied", applied.to_string())
+        .add_attribute("total", total.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_leave(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.allow_self_leave {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_frozen(deps.storage)?;
+    assert_unlocked(deps.storage, &info.sender)?;
+
+    let height = env.block.height;
+    let weight = MEMBERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NotAMember {
+            addr: info.sender.to_string(),
+        })?;
+    let total = TOTAL.load(deps.storage)? - weight;
+    if total < config.min_weight {
+        return Err(ContractError::WouldViolateMinWeight {
+            projected_total: total,
+            min: config.min_weight,
+        });
+    }
+    assert_min_active_total(&config, total)?;
+
+    remove_member(deps.storage, &info.sender, height)?;
+    IDS.remove(deps.storage, &info.sender);
+    save_total(deps.storage, total, height)?;
+    let diffs = vec![MemberDiff::new(info.sender.as_str(), Some(weight), None)];
+    assert_weight_delta(&config, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "leave")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_swap_weights(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    a: String,
+    b: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+
+    let height = env.block.height;
+    let load = |addr: &str| -> Result<(Addr, u64), ContractError> {
+        let addr = deps.api.addr_validate(addr)?;
+        assert_unlocked(deps.storage, &addr)?;
+        let weight =
+            MEMBERS
+                .may_load(deps.storage, &addr)?
+                .ok_or_else(|| ContractError::NotAMember {
+                    addr: addr.to_string(),
+                })?;
+        Ok((addr, weight))
+    };
+    let (a, weight_a) = load(&a)?;
+    let (b, weight_b) = load(&b)?;
+
+    save_member(deps.storage, &a, weight_b, height)?;
+    save_member(deps.storage, &b, weight_a, height)?;
+    // the total is unchanged, but the root must follow the new weights
+    let total = TOTAL.load(deps.storage)?;
+    save_total(deps.storage, total, height)?;
+    let diffs = vec![
+        MemberDiff::new(a.as_str(), Some(weight_a), Some(weight_b)),
+        MemberDiff::new(b.as_str(), Some(weight_b), Some(weight_a)),
+    ];
+    assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "swap_weights")
+        .add_attribute("a", a)
+        .add_attribute("b", b)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_scale_weights(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    numerator: u64,
+    denominator: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_frozen(deps.storage)?;
+    if denominator == 0 {
+        return Err(ContractError::ZeroDenominator {});
+    }
+
+    let height = env.block.height;
+    let members = MEMBERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut total = Uint64::zero();
+    let mut diffs = vec![];
+    for (addr, weight) in members {
+        // rounds down, so the scaled total never exceeds the exact result
+        let scaled = weight as u128 * numerator as u128 / denominator as u128;
+        let scaled = u64::try_from(scaled)
+            .map_err(|_| OverflowError::new(OverflowOperation::Mul, weight, numerator))?;
+        total = total.checked_add(Uint64::from(scaled))?;
+        // members whose weight is unaffected produce no diff
+        if scaled != weight {
+            assert_unlocked(deps.storage, &addr)?;
+            save_member(deps.storage, &addr, scaled, height)?;
+            diffs.push(MemberDiff::new(addr, Some(weight), Some(scaled)));
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "scale_weights")
+        .add_attribute("numerator", numerator.to_string())
+        .add_attribute("denominator", denominator.to_string())
+        .add_attribute("total", total)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_decay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    decay: Option<WeightDecay>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    if let Some(decay) = &decay {
+        if decay.percent > Decimal::one() {
+            return Err(StdError::generic_err("Percentage must not exceed 1").into());
+        }
+        if decay.interval == 0 {
+            return Err(StdError::generic_err("Decay interval must not be zero").into());
+        }
+    }
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.decay = decay.clone();
+        Ok(config)
+    })?;
+    LAST_DECAY_HEIGHT.save(deps.storage, &env.block.height)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_decay")
+        .add_attribute(
+            "percent",
+            decay.map_or("none".to_string(), |d| d.percent.to_string()),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_decay_weights(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    assert_not_frozen(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    let decay = config
+        .decay
+        .clone()
+        .ok_or(ContractError::DecayNotConfigured {})?;
+    let last = LAST_DECAY_HEIGHT.load(deps.storage)?;
+    let intervals = (env.block.height - last) / decay.interval;
+    if intervals == 0 {
+        return Err(ContractError::DecayNotDue {
+            next_height: last + decay.interval,
+        });
+    }
+    // keep the interval phase, so calling late loses nothing
+    LAST_DECAY_HEIGHT.save(deps.storage, &(last + intervals * decay.interval))?;
+    let keep = (Decimal::one() - decay.percent)
+        .checked_pow(u32::try_from(intervals).unwrap_or(u32::MAX))
+        .map_err(StdError::from)?;
+
+    let height = env.block.height;
+    let members = MEMBERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut total = Uint64::zero();
+    let mut diffs = vec![];
+    for (addr, weight) in members {
+        let decayed = if LOCKED_MEMBERS.has(deps.storage, &addr) {
+            weight
+        } else {
+            (Uint128::from(weight) * keep).u128() as u64
+        };
+        if decayed == 0 {
+            remove_member(deps.storage, &addr, height)?;
+            diffs.push(MemberDiff::new(addr, Some(weight), None));
+            continue;
+        }
+        total = total.checked_add(Uint64::from(decayed))?;
+        if decayed != weight {
+            save_member(deps.storage, &addr, decayed, height)?;
+            diffs.push(MemberDiff::new(addr, Some(weight), Some(decayed)));
+        }
+    }
+    // decay is expected to wear the total down, so the weight bounds are not enforced,
+    // but the admin's floors are
+    assert_min_active_total(&config, total.u64())?;
+    for diff in &diffs {
+        if let Some(weight) = diff.new {
+            assert_min_member_percent(&config, &diff.key, weight, total.u64())?;
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "decay_weights")
+        .add_attribute("intervals", intervals.to_string())
+        .add_attribute("total", total))
+}
+
+pub fn execute_sync_weights(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_not_frozen(deps.storage)?;
+    let source = CONFIG
+        .load(deps.storage)?
+        .weight_source
+        .ok_or(ContractError::NoWeightSource {})?;
+    if addrs.len() > MAX_LIMIT as usize {
+        return Err(ContractError::TooManyAddresses { max: MAX_LIMIT });
+    }
+
+    let height = env.block.height;
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    for addr in addrs {
+        let addr = deps.api.addr_validate(&addr)?;
+        let old = match MEMBERS.may_load(deps.storage, &addr)? {
+            Some(weight) if !LOCKED_MEMBERS.has(deps.storage, &addr) => weight,
+            _ => continue,
+        };
+        let res: WeightSourceBalanceResponse = deps.querier.query_wasm_smart(
+            &source,
+            &WeightSourceQueryMsg::Balance {
+                address: addr.to_string(),
+            },
+        )?;
+        let new = u64::try_from(res.balance.u128())
+            .map_err(|_| OverflowError::new(OverflowOperation::Add, old, res.balance))?;
+        if new != old {
+            total = total.checked_sub(Uint64::from(old))?;
+            total = total.checked_add(Uint64::from(new))?;
+            save_member(deps.storage, &addr, new, height)?;
+            diffs.push(MemberDiff::new(addr, Some(old), Some(new)));
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_weight_delta(&CONFIG.load(deps.storage)?, &diffs)?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+
+    let updated = diffs.len();
+    let mut messages = vec![];
+    if updated > 0 {
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "sync_weights")
+        .add_attribute("updated", updated.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_vesting(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    schedule: Option<VestingSchedule>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+
+    match &schedule {
+        Some(schedule) => {
+            if schedule.end_height <= schedule.start_height {
+                return Err(ContractError::InvalidVestingSchedule {});
+            }
+            VESTING.save(deps.storage, &addr, schedule)?;
+        }
+        None => VESTING.remove(deps.storage, &addr),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_vesting")
+        .add_attribute("addr", addr)
+        .add_attribute("vesting", schedule.is_some().to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_crank_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addrs: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_not_frozen(deps.storage)?;
+    if addrs.len() > MAX_LIMIT as usize {
+        return Err(ContractError::TooManyAddresses { max: MAX_LIMIT });
+    }
+
+    let height = env.block.height;
+    let mut total = Uint64::from(TOTAL.load(deps.storage)?);
+    let mut diffs = vec![];
+    for addr in addrs {
+        let addr = deps.api.addr_validate(&addr)?;
+        // schedules are dropped along with the member
+        let schedule = match VESTING.may_load(deps.storage, &addr)? {
+            Some(schedule) => schedule,
+            None => continue,
+        };
+        assert_unlocked(deps.storage, &addr)?;
+        let old = MEMBERS.load(deps.storage, &addr)?;
+        let new = schedule.weight_at(height);
+        if new != old {
+            total = total.checked_sub(Uint64::from(old))?;
+            total = total.checked_add(Uint64::from(new))?;
+            save_member(deps.storage, &addr, new, height)?;
+            diffs.push(MemberDiff::new(addr, Some(old), Some(new)));
+        }
+    }
+    save_total(deps.storage, total.u64(), height)?;
+    assert_weights(deps.as_ref())?;
+    assert_diffs_unpaused(deps.storage, &diffs)?;
+    update_stats(deps.storage, &diffs)?;
+
+    let updated = diffs.len();
+    let mut messages = vec![];
+    if updated > 0 {
+        record_change(deps.storage, &env.block)?;
+        messages = prepare_member_hooks(deps.as_ref(), &MemberChangedHookMsg { diffs })?;
+    }
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "crank_vesting")
+        .add_attribute("updated", updated.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_min_active_total(
+    deps: DepsMut,
+    info: MessageInfo,
+    min: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.min_active_total = min;
+        Ok(config)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_min_active_total")
+        .add_attribute("min", min.map_or("none".to_string(), |m| m.to_string()))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_budget(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    BUDGET.save(deps.storage, &amount)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_budget")
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_allocate_from_budget(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+    amount: u64,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let remaining = BUDGET.may_load(deps.storage)?.unwrap_or_default();
+    if amount > remaining {
+        return Err(ContractError::InsufficientBudget { remaining });
+    }
+
+    let member_addr = deps.api.addr_validate(&addr)?;
+    let weight = MEMBERS
+        .may_load(deps.storage, &member_addr)?
+        .unwrap_or_default();
+    let member = Member {
+        addr: addr.clone(),
+        weight: Uint64::from(weight)
+            .checked_add(Uint64::from(amount))?
+            .u64(),
+        identity: may_load_identity(deps.storage, &member_addr)?.unwrap_or_default(),
+    };
+    let diff = apply_member_changes(deps.branch(), env.block.height, vec![member], vec![])?;
+    BUDGET.save(deps.storage, &(remaining - amount))?;
+    record_change(deps.storage, &env.block)?;
+
+    let messages = prepare_member_hooks(deps.as_ref(), &diff)?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "allocate_from_budget")
+        .add_attribute("addr", addr)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("remaining", (remaining - amount).to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_max_weight_delta(
+    deps: DepsMut,
+    info: MessageInfo,
+    max: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.max_weight_delta_per_op = max;
+        Ok(config)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_max_weight_delta")
+        .add_attribute("max", max.map_or("none".to_string(), |m| m.to_string()))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_min_member_percent(
+    deps: DepsMut,
+    info: MessageInfo,
+    percent: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    if matches!(percent, Some(p) if p > Decimal::one()) {
+        return Err(StdError::generic_err("Percentage must not exceed 1").into());
+    }
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.min_member_percent = percent;
+        Ok(config)
+    })?;
+    Ok(Response::new()
+        .add_attribute("action", "set_min_member_percent")
+        .add_attribute(
+            "percent",
+            percent.map_or("none".to_string(), |p| p.to_string()),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_group_info(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    description: String,
+    url: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    assert_length("name", &name, 1, MAX_NAME_LEN)?;
+    assert_length("description", &description, 0, MAX_DESCRIPTION_LEN)?;
+    if let Some(url) = &url {
+        assert_length("url", url, 1, MAX_URL_LEN)?;
+    }
+
+    GROUP_INFO.save(
+        deps.storage,
+        &GroupInfo {
+            name: name.clone(),
+            description,
+            url,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set_group_info")
+        .add_attribute("name", name)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_member_tags(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    mut tags: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+
+    tags.sort();
+    tags.dedup();
+    clear_tags(deps.storage, &addr)?;
+    for tag in &tags {
+        TAGS.save(deps.storage, (tag, &addr), &())?;
+    }
+    MEMBER_TAGS.save(deps.storage, &addr, &tags)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_member_tags")
+        .add_attribute("addr", addr)
+        .add_attribute("tags", tags.join(","))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_delegate_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: String,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidTempAdminExpiry {});
+    }
+    TEMP_ADMIN.save(deps.storage, &(addr.clone(), expires))?;
+    Ok(Response::new()
+        .add_attribute("action", "delegate_admin")
+        .add_attribute("addr", addr)
+        .add_attribute("expires", expires.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_member_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+
+    if frozen {
+        FROZEN_MEMBERS.save(deps.storage, &addr, &())?;
+    } else {
+        FROZEN_MEMBERS.remove(deps.storage, &addr);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_member_frozen")
+        .add_attribute("addr", addr)
+        .add_attribute("frozen", frozen.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_member_locked(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    locked: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+
+    if locked {
+        LOCKED_MEMBERS.save(deps.storage, &addr, &())?;
+    } else {
+        LOCKED_MEMBERS.remove(deps.storage, &addr);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_member_locked")
+        .add_attribute("addr", addr)
+        .add_attribute("locked", locked.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_alias(
+    deps: DepsMut,
+    info: MessageInfo,
+    alias: String,
+    canonical: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let alias = deps.api.addr_validate(&alias)?;
+    let canonical = deps.api.addr_validate(&canonical)?;
+    if MEMBERS.may_load(deps.storage, &canonical)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: canonical.into_string(),
+        });
+    }
+    if MEMBERS.may_load(deps.storage, &alias)?.is_some() {
+        return Err(ContractError::AlreadyAMember {
+            addr: alias.into_string(),
+        });
+    }
+    // aliases must point straight at a canonical address, never at another alias
+    if alias == canonical || ALIASES.has(deps.storage, &canonical) {
+        return Err(ContractError::AliasChain {
+            addr: canonical.into_string(),
+        });
+    }
+    let is_canonical = ALIASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, target)| target))
+        .collect::<StdResult<Vec<_>>>()?
+        .contains(&alias);
+    if is_canonical {
+        return Err(ContractError::AliasChain {
+            addr: alias.into_string(),
+        });
+    }
+
+    ALIASES.save(deps.storage, &alias, &canonical)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_alias")
+        .add_attribute("alias", alias)
+        .add_attribute("canonical", canonical)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_remove_alias(
+    deps: DepsMut,
+    info: MessageInfo,
+    alias: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let alias = deps.api.addr_validate(&alias)?;
+    ALIASES.remove(deps.storage, &alias);
+    Ok(Response::new()
+        .add_attribute("action", "remove_alias")
+        .add_attribute("alias", alias)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_member_expiry(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&addr)?;
+    if MEMBERS.may_load(deps.storage, &addr)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: addr.into_string(),
+        });
+    }
+
+    match &expires {
+        Some(expires) => EXPIRY.save(deps.storage, &addr, expires)?,
+        None => EXPIRY.remove(deps.storage, &addr),
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_member_expiry")
+        .add_attribute("addr", addr)
+        .add_attribute(
+            "expires",
+            expires.map_or_else(|| "none".to_string(), |e| e.to_string()),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_delegate_partial(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+    amount: u64,
+) -> Result<Response, ContractError> {
+    let weight = MEMBERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NotAMember {
+            addr: info.sender.to_string(),
+        })?;
+    let to = deps.api.addr_validate(&to)?;
+    if to == info.sender {
+        return Err(ContractError::SelfDelegation {});
+    }
+    if MEMBERS.may_load(deps.storage, &to)?.is_none() {
+        return Err(ContractError::NotAMember {
+            addr: to.into_string(),
+        });
+    }
+
+    // outgoing delegations other than the one being replaced
+    let previous = PARTIAL_DELEGATIONS
+        .may_load(deps.storage, (&info.sender, &to))?
+        .unwrap_or_default();
+    let others = sum_delegations(deps.storage, PARTIAL_DELEGATIONS, &info.sender)? - previous;
+    let available = weight.saturating_sub(others);
+    if amount > available {
+        return Err(ContractError::OverDelegated { available });
+    }
+
+    save_delegation(deps.storage, &info.sender, &to, amount)?;
+    Ok(Response::new()
+        .add_attribute("action", "delegate_partial")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", to)
+        .add_attribute("amount", amount.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Member {
+            addr,
+            at_height: height,
+        } => to_binary(&query_member(deps, addr, height)?),
+        QueryMsg::IsMajority {
+            addrs,
+            fraction,
+            at_height,
+        } => to_binary(&query_is_majority(deps, addrs, fraction, at_height)?),
+        QueryMsg::CompareToGroup {
+            other,
+            start_after,
+            limit,
+        } => to_binary(&query_compare_to_group(deps, other, start_after, limit)?),
+        QueryMsg::IntersectMembers { addrs } => to_binary(&query_intersect_members(deps, addrs)?),
+        QueryMsg::ListMembers { start_after, limit } => {
+            to_binary(&query_list_members(deps, start_after, limit)?)
+        }
+        QueryMsg::TotalWeightExcluding { addrs, at_height } => {
+            to_binary(&query_total_weight_excluding(deps, addrs, at_height)?)
+        }
+        QueryMsg::ListShares {
+            start_after,
+            limit,
+            at_height,
+        } => to_binary(&query_list_shares(deps, start_after, limit, at_height)?),
+        QueryMsg::TotalWeight { at_height: height } => {
+            to_binary(&query_total_weight(deps, height)?)
+        }
+        QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::HooksInfo {} => to_binary(&query_hooks_info(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::NextMember { after } => to_binary(&query_next_member(deps, after)?),
+        QueryMsg::EffectiveWeight { addr } => to_binary(&query_effective_weight(deps, addr)?),
+        QueryMsg::ResolvedWeight { addr, at_height } => {
+            to_binary(&query_resolved_weight(deps, &env, addr, at_height)?)
+        }
+        QueryMsg::Delegations { addr } => to_binary(&query_delegations(deps, addr)?),
+        QueryMsg::StateHash { at_height } => to_binary(&query_state_hash(deps, at_height)?),
+        QueryMsg::VerifyProof {
+            addr,
+            weight,
+            height,
+            proof,
+        } => to_binary(&query_verify_proof(deps, addr, weight, height, proof)?),
+        QueryMsg::DiffFromCheckpoint {
+            name,
+            start_after,
+            limit,
+        } => to_binary(&query_diff_from_checkpoint(
+            deps,
+            &env,
+            name,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Churn {
+            from_height,
+            to_height,
+            start_after,
+            limit,
+        } => to_binary(&query_churn(
+            deps,
+            from_height,
+            to_height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ChangedMembers {
+            from_height,
+            to_height,
+            start_after,
+            limit,
+        } => to_binary(&query_changed_members(
+            deps,
+            from_height,
+            to_height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::MemberSnapshotBounds { addr } => {
+            to_binary(&query_member_snapshot_bounds(deps, addr)?)
+        }
+        QueryMsg::MemberHistory {
+            addr,
+            from_height,
+            to_height,
+            limit,
+        } => to_binary(&query_member_history(
+            deps,
+            addr,
+            from_height,
+            to_height,
+            limit,
+        )?),
+        QueryMsg::EffectiveThreshold {
+            absolute,
+            percent,
+            at_height,
+        } => to_binary(&query_effective_threshold(
+            deps, absolute, percent, at_height,
+        )?),
+        QueryMsg::WeightForShare {
+            addr,
+            target_percent,
+        } => to_binary(&query_weight_for_share(deps, addr, target_percent)?),
+        QueryMsg::VestedWeight { addr, at_height } => {
+            let addr = deps.api.addr_validate(&addr)?;
+            let height = at_height.unwrap_or(env.block.height);
+            to_binary(&VestedWeightResponse {
+                weight: VESTING
+                    .may_load(deps.storage, &addr)?
+                    .map(|schedule| schedule.weight_at(height)),
+            })
+        }
+        QueryMsg::MembersWithWeight {
+            weight,
+            start_after,
+            limit,
+        } => to_binary(&query_members_with_weight(
+            deps,
+            weight,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::WeightToEnterTop { n } => to_binary(&query_weight_to_enter_top(deps, n)?),
+        QueryMsg::CumulativeWeight { top_n } => to_binary(&query_cumulative_weight(deps, top_n)?),
+        QueryMsg::WeightPercentile { weight } => to_binary(&query_weight_percentile(deps, weight)?),
+        QueryMsg::MemberRank { addr } => to_binary(&query_member_rank(deps, addr)?),
+        QueryMsg::MinimalCoalition { threshold_percent } => {
+            to_binary(&query_minimal_coalition(deps, threshold_percent)?)
+        }
+        QueryMsg::Provenance {} => to_binary(&ProvenanceResponse {
+            instantiated_at: INSTANTIATED_AT.may_load(deps.storage)?,
+            creator: CREATOR.may_load(deps.storage)?.map(Addr::into_string),
+        }),
+        QueryMsg::AverageJoinHeight {} => to_binary(&query_average_join_height(deps)?),
+        QueryMsg::NakamotoCoefficient { threshold } => {
+            to_binary(&query_nakamoto_coefficient(deps, threshold)?)
+        }
+        QueryMsg::MembersToReach {
+            threshold_percent,
+            from_bottom,
+        } => to_binary(&query_members_to_reach(
+            deps,
+            threshold_percent,
+            from_bottom,
+        )?),
+        QueryMsg::Quantiles { n } => to_binary(&query_quantiles(deps, n)?),
+        QueryMsg::CanAdd { member } => to_binary(&query_can_add(deps, member)?),
+        QueryMsg::ListByIdentity { start_after, limit } => {
+            to_binary(&query_list_by_identity(deps, start_after, limit)?)
+        }
+        QueryMsg::SearchByIdentityPrefix { prefix, limit } => {
+            to_binary(&query_search_by_identity_prefix(deps, prefix, limit)?)
+        }
+        QueryMsg::MemberByExternalId { external_id } => {
+            to_binary(&query_member_by_external_id(deps, external_id)?)
+        }
+        QueryMsg::ListByTag {
+            tag,
+            start_after,
+            limit,
+        } => to_binary(&query_list_by_tag(deps, tag, start_after, limit)?),
+        QueryMsg::ListByJoinHeight {
+            start_after,
+            limit,
+            reverse,
+        } => to_binary(&query_list_by_join_height(
+            deps,
+            start_after,
+            limit,
+            reverse.unwrap_or_default(),
+        )?),
+        QueryMsg::TagWeight { tag } => to_binary(&query_tag_weight(deps, tag)?),
+        QueryMsg::WeightByTag { tags } => to_binary(&query_weight_by_tag(deps, tags)?),
+        QueryMsg::ListOperators {} => to_binary(&OperatorsResponse {
+            operators: OPERATORS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(|addr| addr.map(String::from))
+                .collect::<StdResult<_>>()?,
+        }),
+        QueryMsg::PendingRemovals {} => to_binary(&query_pending_removals(deps)?),
+        QueryMsg::AdminMember {} => to_binary(&query_admin_member(deps)?),
+        QueryMsg::NextScheduledFor { addr } => {
+            let addr = deps.api.addr_validate(&addr)?;
+            let at_height = PENDING_REMOVALS.may_load(deps.storage, &addr)?;
+            to_binary(&NextScheduledResponse {
+                at_height,
+                new_weight: at_height.map(|_| 0),
+            })
+        }
+        QueryMsg::InactiveWeight {} => to_binary(&query_inactive_weight(deps, &env)?),
+        QueryMsg::StorageStats {} => to_binary(&query_storage_stats(deps)?),
+        QueryMsg::Health {} => to_binary(&query_health(deps)?),
+        QueryMsg::HeadroomReport {} => to_binary(&query_headroom_report(deps)?),
+        QueryMsg::Overview {} => to_binary(&query_overview(deps)?),
+        QueryMsg::EmergencyProposal {} => to_binary(&query_emergency_proposal(deps, &env)?),
+        QueryMsg::Frozen {} => to_binary(&FrozenResponse {
+            frozen: FROZEN.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::PausedOps {} => to_binary(&PausedOpsResponse {
+            ops: PAUSED_OPS.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::FreezeWindow {} => {
+            let window = FREEZE_WINDOW.may_load(deps.storage)?;
+            to_binary(&FreezeWindowResponse {
+                start: window.map(|(start, _)| start),
+                end: window.map(|(_, end)| end),
+                active: window
+                    .is_some_and(|(start, end)| start <= env.block.time && env.block.time < end),
+            })
+        }
+        QueryMsg::IsSealed {} => to_binary(&SealedResponse {
+            sealed: SEALED.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::IsLocked { addr } => {
+            let addr = deps.api.addr_validate(&addr)?;
+            to_binary(&IsLockedResponse {
+                locked: LOCKED_MEMBERS.has(deps.storage, &addr),
+            })
+        }
+        QueryMsg::RemainingBudget {} => to_binary(&RemainingBudgetResponse {
+            remaining: BUDGET.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::AdminNonce {} => to_binary(&AdminNonceResponse {
+            nonce: ADMIN_NONCE.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::AdminChangeReadyAt {} => {
+            let notice = CONFIG
+                .load(deps.storage)?
+                .admin_change_notice_blocks
+                .unwrap_or_default();
+            let pending = PENDING_ADMIN.may_load(deps.storage)?;
+            to_binary(&AdminChangeReadyAtResponse {
+                proposed: pending.as_ref().map(|(addr, _)| addr.to_string()),
+                ready_at: pending.map(|(_, height)| height + notice),
+            })
+        }
+        QueryMsg::TempAdmin {} => {
+            let active = TEMP_ADMIN
+                .may_load(deps.storage)?
+                .filter(|(_, expires)| !expires.is_expired(&env.block));
+            to_binary(&TempAdminResponse {
+                addr: active.as_ref().map(|(addr, _)| addr.to_string()),
+                expires: active.map(|(_, expires)| expires),
+            })
+        }
+        QueryMsg::Expiration {} => to_binary(&ExpirationResponse {
+            expires_at: CONFIG.load(deps.storage)?.expires_at,
+            expired: is_expired(deps, &env)?,
+        }),
+        QueryMsg::Dissolved {} => to_binary(&DissolvedResponse {
+            dissolved_at: DISSOLVED_AT.may_load(deps.storage)?,
+        }),
+        QueryMsg::Stats {} => to_binary(&STATS.may_load(deps.storage)?.unwrap_or_default()),
+        QueryMsg::GroupInfo {} => {
+            to_binary(&GROUP_INFO.may_load(deps.storage)?.unwrap_or_default())
+        }
+        QueryMsg::LastBatchDiff {} => to_binary(&LastBatchDiffResponse {
+            height: LAST_BATCH_HEIGHT.may_load(deps.storage)?,
+            diffs: LAST_BATCH_DIFF.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::LastChange {} => to_binary(&query_last_change(deps)?),
+    }
+}
+
+pub fn query_storage_stats(deps: Deps) -> StdResult<StorageStatsResponse> {
+    let mut member_count = 0u64;
+    let mut total_identity_bytes = 0u64;
+    let mut estimated_bytes = 0u64;
+    let mut pooled = vec![];
+    let mut members = MEMBERS.range(deps.storage, None, None, Order::Ascending);
+    for item in members.by_ref().take(STORAGE_STATS_LIMIT) {
+        let (addr, weight) = item?;
+        member_count += 1;
+        estimated_bytes += (addr.as_str().len() + to_vec(&weight)?.len()) as u64;
+        if let Some(id) = IDS.may_load(deps.storage, &addr)? {
+            estimated_bytes += (addr.as_str().len() + to_vec(&id)?.len()) as u64;
+            let identity = IDENTITY_POOL.load(deps.storage, id)?;
+            total_identity_bytes += identity.len() as u64;
+            // interned identities are stored once, however many members share them
+            if !pooled.contains(&id) {
+                pooled.push(id);
+                estimated_bytes += identity.len() as u64;
+            }
+        }
+    }
+    Ok(StorageStatsResponse {
+        member_count,
+        total_identity_bytes,
+        estimated_bytes,
+        truncated: members.next().is_some(),
+    })
+}
+
+pub fn query_overview(deps: Deps) -> StdResult<OverviewResponse> {
+    let config = query_config(deps)?;
+    Ok(OverviewResponse {
+        admin: ADMIN.query_admin(deps)?.admin,
+        total_weight: query_total_weight(deps, None)?.weight,
+        member_count: MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        min_weight: config.min_weight,
+        max_weight: config.max_weight,
+        frozen: FROZEN.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_health(deps: Deps) -> StdResult<HealthResponse> {
+    let config = CONFIG.may_load(deps.storage)?;
+    let total_weight = TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    Ok(HealthResponse {
+        initialized: config.is_some(),
+        total_weight,
+        member_count: MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        within_bounds: config
+            .is_some_and(|c| (c.min_weight..=c.max_weight).contains(&total_weight)),
+        frozen: FROZEN.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_headroom_report(deps: Deps) -> StdResult<HeadroomReportResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let total = TOTAL.load(deps.storage)?;
+    let count = MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    let weight_to_max = config.max_weight.saturating_sub(total);
+    let average = match count {
+        0 => 0,
+        count => total / count,
+    };
+    Ok(HeadroomReportResponse {
+        weight_to_max,
+        weight_to_min: total.saturating_sub(config.min_weight),
+        members_to_max_count: weight_to_max.checked_div(average),
+    })
+}
+
+pub fn query_emergency_proposal(deps: Deps, env: &Env) -> StdResult<EmergencyProposalResponse> {
+    match EMERGENCY_PROPOSAL.may_load(deps.storage)? {
+        Some(proposal) if env.block.height < proposal.expires_at => Ok(EmergencyProposalResponse {
+            addr: Some(proposal.addr.into_string()),
+            expires_at: Some(proposal.expires_at),
+            votes: emergency_votes(deps)?,
+        }),
+        _ => Ok(EmergencyProposalResponse {
+            addr: None,
+            expires_at: None,
+            votes: 0,
+        }),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        min_weight: config.min_weight,
+        max_weight: config.max_weight,
+        weight_unit: config.weight_unit,
+        removal_grace_blocks: config.removal_grace_blocks,
+        weight_source: config.weight_source.map(String::from),
+        min_active_total: config.min_active_total,
+        strict_removals: config.strict_removals,
+        require_identity: config.require_identity,
+        quorum_watch: config.quorum_watch,
+        validate_hook_contracts: config.validate_hook_contracts,
+        emergency_recovery: config.emergency_recovery,
+        expires_at: config.expires_at,
+        auto_rebalance: config.auto_rebalance,
+        rebalance_target: config.rebalance_target,
+        rebalance_remainder_to: config.rebalance_remainder_to.map(Addr::into_string),
+        require_admin_nonce: config.require_admin_nonce,
+        allow_self_leave: config.allow_self_leave,
+        emit_payload_event: config.emit_payload_event,
+        min_member_percent: config.min_member_percent,
+        max_weight_delta_per_op: config.max_weight_delta_per_op,
+        decay: config.decay,
+        admin_change_notice_blocks: config.admin_change_notice_blocks,
+        default_limit: DEFAULT_LIMIT,
+        max_limit: MAX_LIMIT,
+    })
+}
+
+pub fn query_pending_removals(deps: Deps) -> StdResult<PendingRemovalsResponse> {
+    let removals = PENDING_REMOVALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(addr, remove_at)| PendingRemoval {
+                addr: addr.into_string(),
+                remove_at,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PendingRemovalsResponse { removals })
+}
+
+pub fn query_inactive_weight(deps: Deps, env: &Env) -> StdResult<InactiveWeightResponse> {
+    let frozen = FROZEN_MEMBERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let expired = EXPIRY
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, expires)| expires.is_expired(&env.block))
+        })
+        .map(|item| item.map(|(addr, _)| addr))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // a member both frozen and expired only counts once
+    let mut inactive_members: Vec<Addr> = frozen.into_iter().chain(expired).collect();
+    inactive_members.sort();
+    inactive_members.dedup();
+
+    let mut inactive = 0u64;
+    for addr in inactive_members {
+        inactive += MEMBERS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    }
+    let total = TOTAL.load(deps.storage)?;
+    Ok(InactiveWeightResponse {
+        inactive,
+        active: total - inactive,
+    })
+}
+
+pub fn query_last_change(deps: Deps) -> StdResult<LastChangeResponse> {
+    Ok(LastChangeResponse {
+        height: LAST_CHANGE_HEIGHT.may_load(deps.storage)?,
+        time: LAST_CHANGE_TIME.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
+    let weight = match height {
+        Some(h) => TOTAL.may_load_at_height(deps.storage, h),
+        None => TOTAL.may_load(deps.storage),
+    }?
+    .unwrap_or_default();
+    Ok(TotalWeightResponse { weight })
+}
+
+pub fn query_total_weight_excluding(
+    deps: Deps,
+    addrs: Vec<String>,
+    height: Option<u64>,
+) -> StdResult<TotalWeightResponse> {
+    assert_addr_count(&addrs)?;
+    let mut addrs = addrs
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    addrs.sort();
+    addrs.dedup();
+
+    let mut weight = query_total_weight(deps, height)?.weight;
+    for addr in addrs {
+        let excluded = match height {
+            Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h)?,
+            None => MEMBERS.may_load(deps.storage, &addr)?,
+        };
+        weight = weight.saturating_sub(excluded.unwrap_or_default());
+    }
+    Ok(TotalWeightResponse { weight })
+}
+
+pub fn query_is_majority(
+    deps: Deps,
+    addrs: Vec<String>,
+    fraction: Decimal,
+    height: Option<u64>,
+) -> StdResult<IsMajorityResponse> {
+    assert_addr_count(&addrs)?;
+    if fraction > Decimal::one() {
+        return Err(StdError::generic_err("Fraction must not exceed 1"));
+    }
+    let total_weight = query_total_weight(deps, height)?.weight;
+    let excluded = query_total_weight_excluding(deps, addrs, height)?.weight;
+    let their_weight = total_weight - excluded;
+
+    let one = Decimal::one().atomics().u128();
+    let is_majority = their_weight as u128 * one > total_weight as u128 * fraction.atomics().u128();
+    Ok(IsMajorityResponse {
+        is_majority,
+        their_weight,
+        total_weight,
+    })
+}
+
+pub fn query_compare_to_group(
+    deps: Deps,
+    other: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CompareToGroupResponse> {
+    let other = deps.api.addr_validate(&other)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let here = query_list_members(deps, start_after.clone(), Some(limit))?;
+    let there: MemberListResponse = deps.querier.query_wasm_smart(
+        &other,
+        &Cw4QueryMsg::ListMembers {
+            start_after,
+            limit: Some(limit),
+        },
+    )?;
+    // the other group may cap the page lower, or not report has_more at all
+    let there_more = there.has_more || there.members.len() as u32 >= limit;
+    let here_more = here.has_more;
+    let here: Vec<String> = here.members.into_iter().map(|m| m.addr).collect();
+    let there: Vec<String> = there.members.into_iter().map(|m| m.addr).collect();
+
+    // both pages are complete up to the end of the shorter unfinished one
+    let mut ends = vec![];
+    if here_more {
+        ends.extend(here.last().cloned());
+    }
+    if there_more {
+        ends.extend(there.last().cloned());
+    }
+    let last = ends.into_iter().min();
+    let covered = |addr: &&String| last.as_ref().is_none_or(|last| *addr <= last);
+
+    let only_here = here
+        .iter()
+        .filter(covered)
+        .filter(|addr| !there.contains(addr))
+        .cloned()
+        .collect();
+    let only_there = there
+        .iter()
+        .filter(covered)
+        .filter(|addr| !here.contains(addr))
+        .cloned()
+        .collect();
+    Ok(CompareToGroupResponse {
+        only_here,
+        only_there,
+        last,
+    })
+}
+
+fn assert_addr_count(addrs: &[String]) -> StdResult<()> {
+    if addrs.len() > MAX_LIMIT as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot check more than {} addresses at once",
+            MAX_LIMIT
+        )));
+    }
+    Ok(())
+}
+
+pub fn query_intersect_members(deps: Deps, addrs: Vec<String>) -> StdResult<MemberListResponse> {
+    assert_addr_count(&addrs)?;
+    let mut seen = vec![];
+    let mut members = vec![];
+    for addr in addrs {
+        let addr = deps.api.addr_validate(&addr)?;
+        if seen.contains(&addr) {
+            continue;
+        }
+        if let Some(weight) = MEMBERS.may_load(deps.storage, &addr)? {
+            members.push(Member {
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.to_string(),
+                weight,
+            });
+        }
+        seen.push(addr);
+    }
+    Ok(MemberListResponse {
+        members,
+        has_more: false,
+    })
+}
+
+pub fn query_effective_threshold(
+    deps: Deps,
+    absolute: Option<u64>,
+    percent: Option<Decimal>,
+    height: Option<u64>,
+) -> StdResult<EffectiveThresholdResponse> {
+    if absolute.is_none() && percent.is_none() {
+        return Err(StdError::generic_err(
+            "Either an absolute or a percentage threshold is required",
+        ));
+    }
+    if matches!(percent, Some(p) if p > Decimal::one()) {
+        return Err(StdError::generic_err("Percentage must not exceed 1"));
+    }
+
+    let total_weight = query_total_weight(deps, height)?.weight;
+    // rounded up, as reaching the percentage must not fall short of it
+    let from_percent = percent
+        .map(|p| {
+            let weight = total_weight as u128 * p.atomics().u128();
+            weight.div_ceil(Decimal::one().atomics().u128()) as u64
+        })
+        .unwrap_or_default();
+    Ok(EffectiveThresholdResponse {
+        threshold: absolute.unwrap_or_default().max(from_percent),
+        total_weight,
+    })
+}
+
+pub fn query_weight_for_share(
+    deps: Deps,
+    addr: String,
+    target: Decimal,
+) -> StdResult<WeightForShareResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = MEMBERS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let total_weight = TOTAL.load(deps.storage)?;
+
+    // (weight + x) / (total + x) >= p  <=>  x * (1 - p) >= p * total - weight
+    let one = Decimal::one().atomics().u128();
+    let p = target.atomics().u128();
+    if p > one {
+        return Ok(WeightForShareResponse {
+            weight,
+            total_weight,
+            additional: None,
+        });
+    }
+    // with p at most one this cannot overflow
+    let needed = (p * total_weight as u128).saturating_sub(weight as u128 * one);
+    let additional = if total_weight == 0 {
+        // any weight at all makes up the whole group
+        Some(if target.is_zero() { 0 } else { 1 })
+    } else if needed == 0 {
+        Some(0)
+    } else if p == one {
+        // only reachable by already holding everything, covered above
+        None
+    } else {
+        u64::try_from(needed.div_ceil(one - p)).ok()
+    };
+    Ok(WeightForShareResponse {
+        weight,
+        total_weight,
+        additional,
+    })
+}
+
+pub fn query_members_with_weight(
+    deps: Deps,
+    weight: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let mut members = MEMBERS_BY_WEIGHT
+        .prefix(weight)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .map(|addr| {
+            let addr = addr?;
+            Ok(Member {
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+                weight,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let has_more = members.len() > limit;
+    members.truncate(limit);
+
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_hooks_info(deps: Deps) -> StdResult<HooksInfoResponse> {
+    let hooks = HOOKS
+        .query_hooks(deps)?
+        .hooks
+        .into_iter()
+        .take(MAX_HOOKS_INFO)
+        .map(|addr| {
+            // reads the raw cw2 item, so a hook without it, or one that is not a contract
+            // at all, is still listed
+            let info = deps
+                .querier
+                .query_wasm_raw(&addr, b"contract_info".as_slice())
+                .ok()
+                .flatten()
+                .and_then(|raw| from_slice::<ContractVersion>(&raw).ok());
+            HookInfo {
+                addr,
+                name: info.as_ref().map(|info| info.contract.clone()),
+                version: info.map(|info| info.version),
+            }
+        })
+        .collect();
+    Ok(HooksInfoResponse { hooks })
+}
+
+pub fn query_weight_percentile(deps: Deps, weight: u64) -> StdResult<WeightPercentileResponse> {
+    let count = MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    if count == 0 {
+        return Ok(WeightPercentileResponse {
+            percentile: Decimal::zero(),
+        });
+    }
+    let at_most = MEMBERS_BY_WEIGHT
+        .prefix_range(
+            deps.storage,
+            None,
+            Some(PrefixBound::inclusive(weight)),
+            Order::Ascending,
+        )
+        .count();
+    Ok(WeightPercentileResponse {
+        percentile: Decimal::from_ratio(at_most as u64, count),
+    })
+}
+
+pub fn query_weight_to_enter_top(deps: Deps, n: u64) -> StdResult<WeightToEnterTopResponse> {
+    if n == 0 {
+        return Err(StdError::generic_err("n must be at least 1"));
+    }
+    let nth = MEMBERS_BY_WEIGHT
+        .keys(deps.storage, None, None, Order::Descending)
+        .nth((n - 1) as usize)
+        .transpose()?;
+    // strictly above, so ties on weight cannot push the candidate out
+    let weight = match nth {
+        Some((weight, _)) => weight + 1,
+        None => 0,
+    };
+    Ok(WeightToEnterTopResponse { weight })
+}
+
+pub fn query_cumulative_weight(deps: Deps, top_n: u64) -> StdResult<CumulativeWeightResponse> {
+    let top_n = top_n.min(MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default());
+    let weight = MEMBERS_BY_WEIGHT
+        .keys(deps.storage, None, None, Order::Descending)
+        .take(top_n as usize)
+        .map(|item| item.map(|(weight, _)| weight))
+        .sum::<StdResult<u64>>()?;
+    Ok(CumulativeWeightResponse { top_n, weight })
+}
+
+pub fn query_member_rank(deps: Deps, addr: String) -> StdResult<MemberRankResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = MEMBERS
+        .may_load(deps.storage, &addr)?
+        .ok_or_else(|| StdError::generic_err(format!("{} is not a member", addr)))?;
+
+    let heavier = MEMBERS_BY_WEIGHT
+        .prefix_range(
+            deps.storage,
+            Some(PrefixBound::exclusive(weight)),
+            None,
+            Order::Ascending,
+        )
+        .count();
+    let tied_before = MEMBERS_BY_WEIGHT
+        .prefix(weight)
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive(&addr)),
+            Order::Ascending,
+        )
+        .count();
+    Ok(MemberRankResponse {
+        rank: (heavier + tied_before) as u64 + 1,
+        out_of: MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+/// Picks members from the heaviest down until the threshold is reached. Taking the
+/// heaviest members first gives the largest possible weight for any number of members,
+/// so the first such prefix to reach the threshold is also the smallest coalition that can.
+pub fn query_minimal_coalition(
+    deps: Deps,
+    threshold: Decimal,
+) -> StdResult<MinimalCoalitionResponse> {
+    if threshold > Decimal::one() {
+        return Err(StdError::generic_err("Threshold must not exceed 1"));
+    }
+    let total = TOTAL.load(deps.storage)?;
+    let one = Decimal::one().atomics().u128();
+    let needed = total as u128 * threshold.atomics().u128();
+
+    let mut members = vec![];
+    let mut weight = 0u64;
+    for item in MEMBERS_BY_WEIGHT.keys(deps.storage, None, None, Order::Descending) {
+        if weight as u128 * one >= needed {
+            break;
+        }
+        let (member_weight, addr) = item?;
+        weight += member_weight;
+        members.push(Member {
+            identity: load_identity(deps.storage, &addr)?,
+            addr: addr.into_string(),
+            weight: member_weight,
+        });
+    }
+    Ok(MinimalCoalitionResponse { members, weight })
+}
+
+pub fn query_average_join_height(deps: Deps) -> StdResult<AverageJoinHeightResponse> {
+    let mut weighted_sum = 0u128;
+    let mut total = 0u128;
+    let mut untracked_weight = 0u64;
+    for item in MEMBERS.range(deps.storage, None, None, Order::Ascending) {
+        let (addr, weight) = item?;
+        match JOINED_AT.may_load(deps.storage, &addr)? {
+            Some(joined) => {
+                weighted_sum += weight as u128 * joined as u128;
+                total += weight as u128;
+            }
+            None => untracked_weight += weight,
+        }
+    }
+    Ok(AverageJoinHeightResponse {
+        height: weighted_sum.checked_div(total).map(|height| height as u64),
+        untracked_weight,
+    })
+}
+
+pub fn query_nakamoto_coefficient(
+    deps: Deps,
+    threshold: Option<Decimal>,
+) -> StdResult<NakamotoCoefficientResponse> {
+    let threshold = threshold.unwrap_or_else(|| Decimal::from_ratio(1u128, 3u128));
+    if threshold >= Decimal::one() {
+        return Err(StdError::generic_err("Threshold must be below 1"));
+    }
+    let total = TOTAL.load(deps.storage)?;
+    let one = Decimal::one().atomics().u128();
+    let limit = total as u128 * threshold.atomics().u128();
+
+    let mut coefficient = 0;
+    let mut weight = 0u64;
+    // an empty group has nothing to exceed
+    if total > 0 {
+        for item in MEMBERS_BY_WEIGHT.keys(deps.storage, None, None, Order::Descending) {
+            let (member_weight, _) = item?;
+            weight += member_weight;
+            coefficient += 1;
+            if weight as u128 * one > limit {
+                break;
+            }
+        }
+    }
+    Ok(NakamotoCoefficientResponse {
+        coefficient,
+        threshold,
+    })
+}
+
+pub fn query_members_to_reach(
+    deps: Deps,
+    threshold: Decimal,
+    from_bottom: bool,
+) -> StdResult<MembersToReachResponse> {
+    if threshold > Decimal::one() {
+        return Err(StdError::generic_err("Threshold must not exceed 1"));
+    }
+    let total = TOTAL.load(deps.storage)?;
+    let one = Decimal::one().atomics().u128();
+    let needed = total as u128 * threshold.atomics().u128();
+    let order = if from_bottom {
+        Order::Ascending
+    } else {
+        Order::Descending
+    };
+
+    let mut count = 0;
+    let mut weight = 0u64;
+    for item in MEMBERS_BY_WEIGHT.keys(deps.storage, None, None, order) {
+        if weight as u128 * one >= needed {
+            break;
+        }
+        let (member_weight, _) = item?;
+        weight += member_weight;
+        count += 1;
+    }
+    Ok(MembersToReachResponse { count, weight })
+}
+
+pub fn query_quantiles(deps: Deps, n: u32) -> StdResult<QuantilesResponse> {
+    if !(1..=MAX_QUANTILES).contains(&n) {
+        return Err(StdError::generic_err(format!(
+            "Number of tiers must be between 1 and {}",
+            MAX_QUANTILES
+        )));
+    }
+    let n = n as u64;
+    let count = MEMBER_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    let mut tiers = vec![
+        QuantileTier {
+            count: 0,
+            weight: 0
+        };
+        n as usize
+    ];
+    // tier i holds the positions from i * count / n up to (i + 1) * count / n
+    let mut tier = 0;
+    for (position, item) in MEMBERS_BY_WEIGHT
+        .keys(deps.storage, None, None, Order::Ascending)
+        .enumerate()
+    {
+        let (weight, _) = item?;
+        while (position as u64) >= (tier + 1) * count / n {
+            tier += 1;
+        }
+        tiers[tier as usize].count += 1;
+        tiers[tier as usize].weight += weight;
+    }
+    Ok(QuantilesResponse { tiers })
+}
+
+pub fn query_can_add(deps: Deps, member: Member) -> StdResult<CanAddResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut violations = vec![];
+
+    if let Some(height) = DISSOLVED_AT.may_load(deps.storage)? {
+        violations.push(ContractError::Dissolved { height }.to_string());
+    }
+    if let Err(err) = assert_not_frozen(deps.storage) {
+        violations.push(err.to_string());
+    }
+    let addr = match deps.api.addr_validate(&member.addr) {
+        Ok(addr) => addr,
+        Err(err) => {
+            // nothing else can be checked without a valid address
+            violations.push(err.to_string());
+            return Ok(CanAddResponse {
+                ok: false,
+                violations,
+            });
+        }
+    };
+    let old = MEMBERS.may_load(deps.storage, &addr)?;
+    if old.is_some() {
+        violations.push(
+            ContractError::AlreadyAMember {
+                addr: addr.to_string(),
+            }
+            .to_string(),
+        );
+    }
+    for check in add_checks(deps.storage, &config, &addr, &member.identity) {
+        if let Err(err) = check {
+            violations.push(err.to_string());
+        }
+    }
+
+    let projected =
+        (TOTAL.load(deps.storage)? - old.unwrap_or_default()) as u128 + member.weight as u128;
+    if projected > config.max_weight as u128 {
+        violations.push(ContractError::MaxWeightExceeded {}.to_string());
+    }
+    if projected < config.min_weight as u128 {
+        violations.push(ContractError::MinWeightNotMet {}.to_string());
+    }
+    let projected_total = u64::try_from(projected).unwrap_or(u64::MAX);
+    if let Err(err) =
+        assert_min_member_percent(&config, addr.as_str(), member.weight, projected_total)
+    {
+        violations.push(err.to_string());
+    }
+
+    Ok(CanAddResponse {
+        ok: violations.is_empty(),
+        violations,
+    })
+}
+
+pub fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<MemberResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let addr = ALIASES.may_load(deps.storage, &addr)?.unwrap_or(addr);
+    let res = match height {
+        Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h),
+        None => MEMBERS.may_load(deps.storage, &addr),
+    }?;
+    match res {
+        Some(weight) => Ok(MemberResponse {
+            weight: Some(weight),
+            identity: load_identity(deps.storage, &addr).ok(),
+        }),
+        None => Ok(MemberResponse {
+            weight: None,
+            identity: None,
+        }),
+    }
+}
+
+pub fn query_admin_member(deps: Deps) -> StdResult<AdminMemberResponse> {
+    let admin = ADMIN.get(deps)?;
+    let member = match &admin {
+        Some(admin) => Some(query_member(deps, admin.to_string(), None)?)
+            .filter(|member| member.weight.is_some()),
+        None => None,
+    };
+    Ok(AdminMemberResponse {
+        admin: admin.map(Addr::into_string),
+        member,
+    })
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+// members RewriteIdentities updates at most
+const MAX_REWRITES: usize = 100;
+// members scanned by StorageStats at most
+const STORAGE_STATS_LIMIT: usize = 500;
+// longest identity prefix accepted by SearchByIdentityPrefix
+const MAX_PREFIX_LEN: usize = 64;
+// most tiers Quantiles splits into
+const MAX_QUANTILES: u32 = 100;
+const MAX_NAME_LEN: usize = 64;
+const MAX_DESCRIPTION_LEN: usize = 1024;
+const MAX_URL_LEN: usize = 256;
+// hooks HooksInfo queries at most
+const MAX_HOOKS_INFO: usize = 20;
+
+pub fn query_list_members(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let mut members = MEMBERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .map(|item| {
+            item.map(|(addr, weight)| Member {
+                addr: addr.to_string(),
+                weight,
+                // This should always have been set
+                identity: load_identity(deps.storage, &addr).unwrap(),
+            })
+        })
+        .collect::<StdResult<Vec<Member>>>()?;
+    let has_more = members.len() > limit;
+    members.truncate(limit);
+
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_list_shares(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    height: Option<u64>,
+) -> StdResult<ListSharesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let total_weight = query_total_weight(deps, height)?.weight;
+
+    let mut shares = vec![];
+    let mut cursor = addr;
+    // a member at a past height either changed since, and so is in the changelog,
+    // or is still a member with the same weight
+    let next = |cursor: &Option<Addr>| -> StdResult<Option<Addr>> {
+        let current = MEMBERS
+            .keys(
+                deps.storage,
+                cursor.as_ref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?;
+        if height.is_none() {
+            return Ok(current);
+        }
+        let changed = MEMBERS
+            .changelog()
+            .prefix_range(
+                deps.storage,
+                cursor.as_ref().map(PrefixBound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?
+            .map(|((addr, _), _)| addr);
+        Ok(match (current, changed) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        })
+    };
+    while let Some(addr) = next(&cursor)? {
+        cursor = Some(addr.clone());
+        let weight = match height {
+            Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h)?,
+            None => MEMBERS.may_load(deps.storage, &addr)?,
+        };
+        // not a member at that height
+        let weight = match weight {
+            Some(weight) => weight,
+            None => continue,
+        };
+        if shares.len() == limit {
+            return Ok(ListSharesResponse {
+                shares,
+                total_weight,
+                has_more: true,
+            });
+        }
+        let share = if total_weight == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(weight, total_weight)
+        };
+        shares.push(MemberShare {
+            addr: addr.to_string(),
+            weight,
+            share,
+        });
+    }
+    Ok(ListSharesResponse {
+        shares,
+        total_weight,
+        has_more: false,
+    })
+}
+
+pub fn query_next_member(deps: Deps, after: String) -> StdResult<NextMemberResponse> {
+    let after = deps.api.addr_validate(&after)?;
+    let member = MEMBERS
+        .range(
+            deps.storage,
+            Some(Bound::exclusive(&after)),
+            None,
+            Order::Ascending,
+        )
+        .next()
+        .transpose()?
+        .map(|(addr, weight)| -> StdResult<_> {
+            Ok(Member {
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+                weight,
+            })
+        })
+        .transpose()?;
+    Ok(NextMemberResponse { member })
+}
+
+pub fn query_resolved_weight(
+    deps: Deps,
+    env: &Env,
+    addr: String,
+    height: Option<u64>,
+) -> StdResult<ResolvedWeightResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = match height {
+        Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h),
+        None => MEMBERS.may_load(deps.storage, &addr),
+    }?
+    .unwrap_or_default();
+    let delegated_out = sum_delegations(deps.storage, PARTIAL_DELEGATIONS, &addr)?;
+    let mut delegated_in = 0u64;
+    for item in INCOMING_DELEGATIONS
+        .prefix(&addr)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (from, amount) = item?;
+        if !is_inactive(deps.storage, &env.block, &from)? {
+            // never pass on more than the delegator holds, whatever was stored
+            let held = MEMBERS.may_load(deps.storage, &from)?.unwrap_or_default();
+            delegated_in += amount.min(held);
+        }
+    }
+    let frozen = is_frozen_member(deps.storage, &addr)?;
+    let expired = is_expired_member(deps.storage, &env.block, &addr)?;
+
+    let resolved = if frozen || expired {
+        0
+    } else {
+        weight.saturating_sub(delegated_out) + delegated_in
+    };
+    Ok(ResolvedWeightResponse {
+        weight,
+        delegated_out,
+        delegated_in,
+        frozen,
+        expired,
+        resolved,
+    })
+}
+
+pub fn query_effective_weight(deps: Deps, addr: String) -> StdResult<EffectiveWeightResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = MEMBERS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let delegated_out = sum_delegations(deps.storage, PARTIAL_DELEGATIONS, &addr)?;
+    let delegated_in = sum_delegations(deps.storage, INCOMING_DELEGATIONS, &addr)?;
+    // a weight reduction may leave a member delegating more than it holds
+    let effective = weight.saturating_sub(delegated_out) + delegated_in;
+    Ok(EffectiveWeightResponse {
+        weight,
+        delegated_out,
+        delegated_in,
+        effective,
+    })
+}
+
+pub fn query_delegations(deps: Deps, addr: String) -> StdResult<DelegationsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let list = |map: Map<(&Addr, &Addr), u64>| {
+        map.prefix(&addr)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(addr, amount)| Delegation {
+                    addr: addr.into_string(),
+                    amount,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()
+    };
+    Ok(DelegationsResponse {
+        outgoing: list(PARTIAL_DELEGATIONS)?,
+        incoming: list(INCOMING_DELEGATIONS)?,
+    })
+}
+
+pub fn query_state_hash(deps: Deps, height: Option<u64>) -> StdResult<StateHashResponse> {
+    // roots are committed with every change, so the latest one before `height` is current
+    let hash = ROOTS
+        .range(
+            deps.storage,
+            None,
+            height.map(Bound::exclusive),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, root)| root);
+    Ok(StateHashResponse { hash })
+}
+
+pub fn query_verify_proof(
+    deps: Deps,
+    addr: String,
+    weight: u64,
+    height: u64,
+    proof: Vec<Binary>,
+) -> StdResult<VerifyProofResponse> {
+    // the last root committed strictly before `height`, matching snapshot queries
+    let root = ROOTS
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::exclusive(height)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, root)| root);
+    let root_hash = root
+        .as_ref()
+        .and_then(|r| merkle::Hash::try_from(r.as_slice()).ok());
+    let proof = proof
+        .iter()
+        .map(|p| merkle::Hash::try_from(p.as_slice()).ok())
+        .collect::<Option<Vec<_>>>();
+    let valid = match (root_hash, proof) {
+        (Some(root), Some(proof)) => merkle::verify(&root, merkle::leaf(&addr, weight), &proof),
+        _ => false,
+    };
+    Ok(VerifyProofResponse { valid, root })
+}
+
+/// Every address that ever had a weight has an entry in the changelog, so walking
+/// its distinct addresses covers members that have since been removed as well
+pub fn query_changed_members(
+    deps: Deps,
+    from_height: u64,
+    to_height: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ChangedMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = maybe_addr(deps.api, start_after)?;
+    let (addrs, last) = changelog_addrs(deps.storage, start_after, limit)?;
+
+    let mut changes = vec![];
+    for addr in addrs {
+        let old = MEMBERS.may_load_at_height(deps.storage, &addr, from_height)?;
+        let new = MEMBERS.may_load_at_height(deps.storage, &addr, to_height)?;
+        if old != new {
+            changes.push(MemberDiff::new(addr.as_str(), old, new));
+        }
+    }
+    Ok(ChangedMembersResponse {
+        changes,
+        last: last.map(Addr::into_string),
+    })
+}
+
+pub fn query_diff_from_checkpoint(
+    deps: Deps,
+    env: &Env,
+    name: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ChangedMembersResponse> {
+    let height = CHECKPOINTS
+        .may_load(deps.storage, &name)?
+        .ok_or_else(|| StdError::not_found(format!("checkpoint {}", name)))?;
+    // the next height also covers changes made earlier in the current block
+    query_changed_members(deps, height, env.block.height + 1, start_after, limit)
+}
+
+pub fn query_member_snapshot_bounds(
+    deps: Deps,
+    addr: String,
+) -> StdResult<MemberSnapshotBoundsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let bound = |order| -> StdResult<Option<u64>> {
+        MEMBERS
+            .changelog()
+            .prefix(&addr)
+            .keys(deps.storage, None, None, order)
+            .next()
+            .transpose()
+    };
+    Ok(MemberSnapshotBoundsResponse {
+        first_height: bound(Order::Ascending)?,
+        last_height: bound(Order::Descending)?,
+    })
+}
+
+/// Up to `limit` addresses with recorded weight changes after `start_after`, and the
+/// last of them if more may follow
+fn changelog_addrs(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: usize,
+) -> StdResult<(Vec<Addr>, Option<Addr>)> {
+    let mut addrs = vec![];
+    let mut cursor = start_after;
+    loop {
+        // jump straight to the next address instead of walking all its entries
+        let next = MEMBERS
+            .changelog()
+            .prefix_range(
+                storage,
+                cursor.as_ref().map(PrefixBound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?;
+        let addr = match next {
+            Some(((addr, _), _)) => addr,
+            None => return Ok((addrs, None)),
+        };
+        if addrs.len() == limit {
+            return Ok((addrs, cursor));
+        }
+        addrs.push(addr.clone());
+        cursor = Some(addr);
+    }
+}
+
+pub fn query_churn(
+    deps: Deps,
+    from_height: u64,
+    to_height: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ChurnResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = maybe_addr(deps.api, start_after)?;
+    let (addrs, last) = changelog_addrs(deps.storage, start_after, limit)?;
+    let mut added = 0;
+    let mut removed = 0;
+    let mut net_weight_change: i128 = 0;
+    for addr in addrs {
+        let changes = MEMBERS
+            .changelog()
+            .prefix(&addr)
+            .range(
+                deps.storage,
+                Some(Bound::inclusive(from_height)),
+                Some(Bound::inclusive(to_height)),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+        for (height, change) in changes {
+            let new = MEMBERS.may_load_at_height(deps.storage, &addr, height + 1)?;
+            match (change.old, new) {
+                (None, Some(_)) => added += 1,
+                (Some(_), None) => removed += 1,
+                _ => {}
+            }
+            net_weight_change += new.unwrap_or_default() as i128;
+            net_weight_change -= change.old.unwrap_or_default() as i128;
+        }
+    }
+    Ok(ChurnResponse {
+        added,
+        removed,
+        net_weight_change: i64::try_from(net_weight_change)
+            .map_err(|_| StdError::generic_err("Net weight change out of range"))?,
+        last: last.map(Addr::into_string),
+    })
+}
+
+pub fn query_member_history(
+    deps: Deps,
+    addr: String,
+    from_height: u64,
+    to_height: u64,
+    limit: Option<u32>,
+) -> StdResult<MemberHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = deps.api.addr_validate(&addr)?;
+
+    // the changelog keeps the value from before each change, so the value after
+    // a change is the one stored by the next entry, or the current one for the last
+    let entries = MEMBERS
+        .changelog()
+        .prefix(&addr)
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(from_height)),
+            Some(Bound::inclusive(to_height)),
+            Order::Ascending,
+        )
+        .take(limit + 1)
+        .map(|item| item.map(|(height, _)| height))
+        .collect::<StdResult<Vec<u64>>>()?;
+    let has_more = entries.len() > limit;
+
+    let changes = entries
+        .into_iter()
+        .take(limit)
+        .map(|height| -> StdResult<_> {
+            let next = MEMBERS
+                .changelog()
+                .prefix(&addr)
+                .range(
+                    deps.storage,
+                    Some(Bound::exclusive(height)),
+                    None,
+                    Order::Ascending,
+                )
+                .next()
+                .transpose()?;
+            let weight = match next {
+                Some((_, change)) => change.old,
+                None => MEMBERS.may_load(deps.storage, &addr)?,
+            };
+            Ok(WeightChange { height, weight })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(MemberHistoryResponse { changes, has_more })
+}
+
+pub fn query_list_by_identity(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let cursor = match maybe_addr(deps.api, start_after)? {
+        Some(addr) => Some((load_identity(deps.storage, &addr)?, addr)),
+        None => None,
+    };
+
+    // members with an identity come first, followed by the ones without
+    let first = match &cursor {
+        Some((identity, _)) => Bound::inclusive(identity.as_str()),
+        None => Bound::exclusive(""),
+    };
+    let named = IDENTITY_INDEX.range(deps.storage, Some(first), None, Order::Ascending);
+    let anonymous = IDENTITY_INDEX
+        .may_load(deps.storage, "")?
+        .map(|addrs| Ok((String::new(), addrs)));
+    let anonymous_first = matches!(&cursor, Some((identity, _)) if identity.is_empty());
+    let buckets: Box<dyn Iterator<Item = StdResult<(String, Vec<Addr>)>>> = if anonymous_first {
+        Box::new(anonymous.into_iter())
+    } else {
+        Box::new(named.chain(anonymous))
+    };
+
+    let mut keys = vec![];
+    for bucket in buckets {
+        let (identity, addrs) = bucket?;
+        let skip = match &cursor {
+            // resume right after the cursor within its own identity
+            Some((id, addr)) if *id == identity => addrs.iter().take_while(|a| *a <= addr).count(),
+            _ => 0,
+        };
+        // one extra to tell whether there is more
+        keys.extend(addrs.into_iter().skip(skip).take(limit + 1 - keys.len()));
+        if keys.len() > limit {
+            break;
+        }
+    }
+    let has_more = keys.len() > limit;
+    keys.truncate(limit);
+
+    let members = keys
+        .into_iter()
+        .map(|addr| {
+            Ok(Member {
+                weight: MEMBERS.load(deps.storage, &addr)?,
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_search_by_identity_prefix(
+    deps: Deps,
+    prefix: String,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    if prefix.is_empty() || prefix.len() > MAX_PREFIX_LEN {
+        return Err(StdError::generic_err(format!(
+            "Prefix must be between 1 and {} bytes",
+            MAX_PREFIX_LEN
+        )));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let mut keys = vec![];
+    for bucket in IDENTITY_INDEX.range(
+        deps.storage,
+        Some(Bound::inclusive(prefix.as_str())),
+        None,
+        Order::Ascending,
+    ) {
+        let (identity, addrs) = bucket?;
+        if !identity.starts_with(&prefix) || keys.len() > limit {
+            break;
+        }
+        keys.extend(addrs.into_iter().take(limit + 1 - keys.len()));
+    }
+    let has_more = keys.len() > limit;
+    keys.truncate(limit);
+
+    let members = keys
+        .into_iter()
+        .map(|addr| {
+            Ok(Member {
+                weight: MEMBERS.load(deps.storage, &addr)?,
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_member_by_external_id(
+    deps: Deps,
+    external_id: String,
+) -> StdResult<MemberByExternalIdResponse> {
+    let member = EXTERNAL_ID_OWNERS
+        .may_load(deps.storage, &external_id)?
+        .map(|addr| -> StdResult<_> {
+            Ok(Member {
+                weight: MEMBERS.load(deps.storage, &addr)?,
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+            })
+        })
+        .transpose()?;
+    Ok(MemberByExternalIdResponse { member })
+}
+
+pub fn query_list_by_join_height(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    reverse: bool,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let cursor = match maybe_addr(deps.api, start_after)? {
+        Some(addr) => {
+            let joined = JOINED_AT
+                .may_load(deps.storage, &addr)?
+                .ok_or_else(|| StdError::generic_err(format!("{} has no join height", addr)))?;
+            Some((joined, addr))
+        }
+        None => None,
+    };
+    let bound = cursor
+        .as_ref()
+        .map(|(joined, addr)| Bound::exclusive((*joined, addr)));
+    let (min, max, order) = if reverse {
+        (None, bound, Order::Descending)
+    } else {
+        (bound, None, Order::Ascending)
+    };
+
+    let mut members = MEMBERS_BY_JOIN
+        .keys(deps.storage, min, max, order)
+        .take(limit + 1)
+        .map(|item| {
+            let (_, addr) = item?;
+            Ok(Member {
+                weight: MEMBERS.load(deps.storage, &addr)?,
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let has_more = members.len() > limit;
+    members.truncate(limit);
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_list_by_tag(
+    deps: Deps,
+    tag: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let mut members = TAGS
+        .prefix(&tag)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .map(|item| {
+            let addr = item?;
+            Ok(Member {
+                weight: MEMBERS.load(deps.storage, &addr)?,
+                identity: load_identity(deps.storage, &addr)?,
+                addr: addr.into_string(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let has_more = members.len() > limit;
+    members.truncate(limit);
+    Ok(MemberListResponse { members, has_more })
+}
+
+pub fn query_weight_by_tag(deps: Deps, tags: Vec<String>) -> StdResult<WeightByTagResponse> {
+    if tags.len() > MAX_LIMIT as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot sum more than {} tags at once",
+            MAX_LIMIT
+        )));
+    }
+    let mut weights: Vec<TagWeightResponse> = vec![];
+    for tag in tags {
+        if weights.iter().any(|w| w.tag == tag) {
+            continue;
+        }
+        weights.push(query_tag_weight(deps, tag)?);
+    }
+    Ok(WeightByTagResponse { tags: weights })
+}
+
+pub fn query_tag_weight(deps: Deps, tag: String) -> StdResult<TagWeightResponse> {
+    let mut weight = 0u64;
+    for addr in TAGS
+        .prefix(&tag)
+        .keys(deps.storage, None, None, Order::Ascending)
+    {
+        weight += MEMBERS.load(deps.storage, &addr?)?;
+    }
+    Ok(TagWeightResponse { tag, weight })
+}
+
+/// Accepts the admin, or the temporary admin while their delegation lasts.
+/// Lapsed delegations are cleared before dispatch, so no block info is needed here
+fn assert_admin(deps: Deps, sender: &Addr) -> Result<(), AdminError> {
+    match TEMP_ADMIN.may_load(deps.storage)? {
+        Some((temp_admin, _)) if &temp_admin == sender => Ok(()),
+        _ => ADMIN.assert_admin(deps, sender),
+    }
+}
+
+fn assert_admin_or_operator(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if OPERATORS.has(deps.storage, sender) {
+        return Ok(());
+    }
+    match assert_admin(deps, sender) {
+        Err(AdminError::NotAdmin {}) => Err(ContractError::Unauthorized {}),
+        res => Ok(res?),
+    }
+}
+
+fn assert_hook_contract(deps: Deps, hook: &Addr) -> Result<(), ContractError> {
+    if CONFIG.load(deps.storage)?.validate_hook_contracts
+        && deps.querier.query_wasm_contract_info(hook).is_err()
+    {
+        return Err(ContractError::HookNotAContract {
+            addr: hook.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn assert_not_frozen(storage: &dyn Storage) -> Result<(), ContractError> {
+    if FROZEN.may_load(storage)?.unwrap_or_default() {
+        return Err(ContractError::Frozen {});
+    }
+    Ok(())
+}
+
+fn assert_length(field: &str, value: &str, min: usize, max: usize) -> Result<(), ContractError> {
+    if !(min..=max).contains(&value.len()) {
+        return Err(ContractError::InvalidLength {
+            field: field.to_string(),
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+fn assert_unlocked(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    if LOCKED_MEMBERS.has(storage, addr) {
+        return Err(ContractError::MemberLocked {
+            addr: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// the per-address guards on adding a member, shared with the CanAdd dry run
+fn add_checks(
+    storage: &dyn Storage,
+    config: &Config,
+    addr: &Addr,
+    identity: &str,
+) -> Vec<Result<(), ContractError>> {
+    vec![
+        assert_unlocked(storage, addr),
+        assert_not_alias(storage, addr),
+        assert_identity(config, addr, identity),
+    ]
+}
+
+fn assert_not_alias(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    if ALIASES.has(storage, addr) {
+        return Err(ContractError::AddressIsAlias {
+            addr: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn assert_identity(config: &Config, addr: &Addr, identity: &str) -> Result<(), ContractError> {
+    if config.require_identity && identity.trim().is_empty() {
+        return Err(ContractError::IdentityRequired {
+            addr: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn assert_min_member_percent(
+    config: &Config,
+    addr: &str,
+    weight: u64,
+    projected_total: u64,
+) -> Result<(), ContractError> {
+    match config.min_member_percent {
+        Some(min) if Decimal::from_ratio(weight, projected_total.max(1)) < min => {
+            Err(ContractError::BelowMinMemberPercent {
+                addr: addr.to_string(),
+                min,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rejects a batch of diffs moving the total, or any single member, by more than the limit
+fn assert_weight_delta(config: &Config, diffs: &[MemberDiff]) -> Result<(), ContractError> {
+    let max = match config.max_weight_delta_per_op {
+        Some(max) => max,
+        None => return Ok(()),
+    };
+    let too_large = |change: i128| -> Result<(), ContractError> {
+        let delta = change.unsigned_abs();
+        if delta > max as u128 {
+            return Err(ContractError::WeightDeltaTooLarge {
+                delta: delta as u64,
+                max,
+            });
+        }
+        Ok(())
+    };
+    let mut net = 0i128;
+    for diff in diffs {
+        let change = diff.new.unwrap_or_default() as i128 - diff.old.unwrap_or_default() as i128;
+        too_large(change)?;
+        net += change;
+    }
+    too_large(net)
+}
+
+// rejects removals that would take the total below the admin-set floor
+fn assert_min_active_total(config: &Config, projected_total: u64) -> Result<(), ContractError> {
+    match config.min_active_total {
+        Some(min) if projected_total < min => Err(ContractError::WouldBreakQuorum {
+            projected_total,
+            min,
+        }),
+        _ => Ok(()),
+    }
 }
 
 fn assert_weights(deps: Deps) -> Result<(), ContractError> {
-    let min = MIN_WEIGHT.load(deps.storage)?;
-    let max = MAX_WEIGHT.load(deps.storage)?;
+    let Config {
+        min_weight: min,
+        max_weight: max,
+        ..
+    } = CONFIG.load(deps.storage)?;
     let total = MEMBERS
         .range(deps.storage, None, None, Order::Ascending)
         .fold(0u64, |t, m| match m {
@@ -1,8 +1,10 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{Decimal, OverflowError, StdError, Timestamp};
 use thiserror::Error;
 
 use cw_controllers::{AdminError, HookError};
 
+use crate::state::OpKind;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -25,4 +27,145 @@ pub enum ContractError {
 
     #[error("MinWeightNotMet")]
     MinWeightNotMet {},
+
+    #[error(
+        "Removal would bring total weight to {projected_total}, below the active minimum of {min}"
+    )]
+    WouldBreakQuorum { projected_total: u64, min: u64 },
+
+    #[error("No weight source configured")]
+    NoWeightSource {},
+
+    #[error("Cannot process more than {max} addresses at once")]
+    TooManyAddresses { max: u32 },
+
+    #[error("Denominator must not be zero")]
+    ZeroDenominator {},
+
+    #[error("{addr} is not a member")]
+    NotAMember { addr: String },
+
+    #[error("Cannot delegate more than own weight, {available} available")]
+    OverDelegated { available: u64 },
+
+    #[error("Cannot delegate to self")]
+    SelfDelegation {},
+
+    #[error("No removal pending for {addr}")]
+    NoPendingRemoval { addr: String },
+
+    #[error("{addr} must have an identity")]
+    IdentityRequired { addr: String },
+
+    #[error("External id {external_id} is already assigned to {owner}")]
+    ExternalIdTaken { external_id: String, owner: String },
+
+    #[error("Hook {addr} is not a contract")]
+    HookNotAContract { addr: String },
+
+    #[error("Emergency admin recovery is not enabled")]
+    RecoveryDisabled {},
+
+    #[error("An emergency admin proposal is already open")]
+    EmergencyProposalOpen {},
+
+    #[error("No open emergency admin proposal")]
+    NoEmergencyProposal {},
+
+    #[error("Expected total weight {expected}, but it is {actual}")]
+    TotalMismatch { expected: u64, actual: u64 },
+
+    #[error("{field} must be between {min} and {max} bytes long")]
+    InvalidLength {
+        field: String,
+        min: usize,
+        max: usize,
+    },
+
+    #[error("Vesting must end after it starts")]
+    InvalidVestingSchedule {},
+
+    #[error("No membership batch to revert")]
+    NoBatchToRevert {},
+
+    #[error("Members changed since the last batch, it can no longer be reverted")]
+    StaleBatch {},
+
+    #[error("{addr} would hold less than {min} of the total weight")]
+    BelowMinMemberPercent { addr: String, min: Decimal },
+
+    #[error("{addr} is already a member")]
+    AlreadyAMember { addr: String },
+
+    #[error("{addr} cannot be both an alias and a canonical address")]
+    AliasChain { addr: String },
+
+    #[error("{addr} is registered as an alias and cannot become a member")]
+    AddressIsAlias { addr: String },
+
+    #[error("Member {addr} is locked")]
+    MemberLocked { addr: String },
+
+    #[error("Leaving would bring total weight to {projected_total}, below the min of {min}")]
+    WouldViolateMinWeight { projected_total: u64, min: u64 },
+
+    #[error("No attester configured")]
+    NoAttester {},
+
+    #[error("Invalid attestation signature")]
+    InvalidSignature {},
+
+    #[error("Stale attestation, expected nonce {expected}")]
+    StaleAttestation { expected: u64 },
+
+    #[error("Weight change of {delta} exceeds the limit of {max} per operation")]
+    WeightDeltaTooLarge { delta: u64, max: u64 },
+
+    #[error("Budget has only {remaining} weight left")]
+    InsufficientBudget { remaining: u64 },
+
+    #[error("Weight decay is not configured")]
+    DecayNotConfigured {},
+
+    #[error("No decay due before height {next_height}")]
+    DecayNotDue { next_height: u64 },
+
+    #[error("Bad admin nonce, expected {expected}")]
+    BadNonce { expected: u64 },
+
+    #[error("Admin can only change at height {ready_at}")]
+    NoticePeriodNotMet { ready_at: u64 },
+
+    #[error("Admin changes must be proposed while a notice period is set")]
+    AdminChangeNeedsNotice {},
+
+    #[error("No admin change proposed")]
+    NoAdminProposal {},
+
+    #[error("Temporary admin rights must expire in the future")]
+    InvalidTempAdminExpiry {},
+
+    #[error("Cannot backfill the weight of {addr} at height {height}")]
+    InvalidBackfill { addr: String, height: u64 },
+
+    #[error("{op:?} operations are paused")]
+    OperationPaused { op: OpKind },
+
+    #[error("Freeze window must end after it starts")]
+    InvalidFreezeWindow {},
+
+    #[error("Group is frozen until {end}")]
+    InFreezeWindow { end: Timestamp },
+
+    #[error("Group has expired")]
+    ContractExpired {},
+
+    #[error("Group is sealed")]
+    Sealed {},
+
+    #[error("Group is frozen")]
+    Frozen {},
+
+    #[error("Group was dissolved at height {height}")]
+    Dissolved { height: u64 },
 }
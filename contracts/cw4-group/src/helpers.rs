@@ -36,7 +36,11 @@ impl Cw4GroupContract {
     }
 
     pub fn update_members(&self, remove: Vec<String>, add: Vec<Member>) -> StdResult<CosmosMsg> {
-        let msg = ExecuteMsg::UpdateMembers { remove, add };
+        let msg = ExecuteMsg::UpdateMembers {
+            remove,
+            add,
+            expected_total: None,
+        };
         self.encode_msg(msg)
     }
 }
@@ -17,10 +17,14 @@ For more information on this contract, please check out the
 pub mod contract;
 pub mod error;
 pub mod helpers;
+pub mod merkle;
 pub mod msg;
+pub mod packed;
 pub mod state;
 
 pub use crate::error::ContractError;
 
+#[cfg(test)]
+mod multitest;
 #[cfg(test)]
 mod tests;
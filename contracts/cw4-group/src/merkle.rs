@@ -0,0 +1,72 @@
+//! Merkle commitments over the member set, so light clients can check a
+//! member's weight against a root instead of trusting a full node.
+//!
+//! Leaves are `sha256(0x00 || addr || weight as big endian u64)`, ordered by address.
+//! Inner nodes are `sha256(0x01 || min(a, b) || max(a, b))`, so a proof is just the
+//! list of sibling hashes from leaf to root. A node without a sibling is carried
+//! up unchanged. The root of an empty set is `sha256("")`.
+
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+pub fn leaf(addr: &str, weight: u64) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(addr.as_bytes());
+    hasher.update(weight.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn node(a: &Hash, b: &Hash) -> Hash {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(lo);
+    hasher.update(hi);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => node(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return Sha256::digest([]).into();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes proving the leaf at `index`, or `None` if out of range
+pub fn proof(leaves: &[Hash], mut index: usize) -> Option<Vec<Hash>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut siblings = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            siblings.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(siblings)
+}
+
+pub fn verify(root: &Hash, leaf: Hash, proof: &[Hash]) -> bool {
+    let computed = proof.iter().fold(leaf, |acc, sibling| node(&acc, sibling));
+    &computed == root
+}
@@ -1,5 +1,9 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cw4::{Member, MemberListResponse, MemberResponse};
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, Decimal, StdResult, Timestamp, Uint128, WasmMsg};
+use cw4::{Member, MemberDiff, MemberListResponse, MemberResponse};
+use cw_utils::Expiration;
+
+use crate::state::{EmergencyRecovery, OpKind, VestingSchedule, WeightDecay};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -11,22 +15,333 @@ pub struct InstantiateMsg {
     pub max_weight: u64,
     /// The minimum total member weight required for a proposal to be created
     pub min_weight: u64,
+    /// Informational label for what weights represent (eg. "KUJI")
+    pub weight_unit: Option<String>,
+    /// If set, removed members keep their weight for this many blocks
+    /// until `FinalizeRemovals` is called
+    pub removal_grace_blocks: Option<u64>,
+    /// Token or staking contract answering cw20-style `Balance` queries.
+    /// When set, `SyncWeights` copies member balances into their weights
+    pub weight_source: Option<String>,
+    /// Make removing an address that is not a member an error.
+    /// Defaults to false, silently ignoring such removals
+    #[serde(default)]
+    pub strict_removals: bool,
+    /// Reject members added with an empty identity. Defaults to false
+    #[serde(default)]
+    pub require_identity: bool,
+    /// Reject hooks that are not deployed contracts. Defaults to false
+    #[serde(default)]
+    pub validate_hook_contracts: bool,
+    /// Let members appoint a new admin by a weighted supermajority, in case the admin key is lost
+    pub emergency_recovery: Option<EmergencyRecovery>,
+    /// Start with the member set frozen until the admin unfreezes it
+    pub frozen: Option<bool>,
+    /// Contract to notify with a `GroupInstantiated` message once the group is set up
+    pub init_callback: Option<InitCallback>,
+    /// Time-box the group. Once expired, all changes are rejected and
+    /// `CheckExpiry` dissolves it
+    pub expires_at: Option<Expiration>,
+    /// After every `UpdateMembers`, scale all weights so they sum to `rebalance_target`.
+    /// Each weight is rounded down and the remainder goes to `rebalance_remainder_to`,
+    /// or to the heaviest member (lowest address on ties) if that is unset or not a member.
+    /// Defaults to false
+    #[serde(default)]
+    pub auto_rebalance: bool,
+    #[serde(default)]
+    pub rebalance_target: u64,
+    pub rebalance_remainder_to: Option<String>,
+    /// Protect against replays by requiring every message the admin sends to be
+    /// wrapped in `WithNonce`. Defaults to false
+    #[serde(default)]
+    pub require_admin_nonce: bool,
+    /// Let members remove themselves with `Leave`. Defaults to false
+    #[serde(default)]
+    pub allow_self_leave: bool,
+    /// Add a `member_update` event to `UpdateMembers` responses, whose `payload`
+    /// attribute is a base64 encoded JSON `MemberUpdateSummary`. Defaults to false
+    #[serde(default)]
+    pub emit_payload_event: bool,
+}
+
+/// Payload of the `member_update` event, for indexers that prefer a single structured value
+#[cw_serde]
+pub struct MemberUpdateSummary {
+    pub diffs: Vec<MemberDiff>,
+    /// Total weight after the update
+    pub total_weight: u64,
+    pub height: u64,
+}
+
+/// Membership update signed off-chain by the attester. Each attestation must carry
+/// the next nonce, so it can only be applied once
+#[cw_serde]
+pub struct Attestation {
+    pub nonce: u64,
+    pub add: Vec<Member>,
+    pub remove: Vec<String>,
+}
+
+#[cw_serde]
+pub struct InitCallback {
+    pub contract: String,
+    /// Passed back unchanged in the `GroupInstantiated` message
+    pub msg: Binary,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Change the admin
     UpdateAdmin { admin: Option<String> },
     /// apply a diff to the existing members.
     /// remove is applied after add, so if an address is in both, it is removed
+    /// If `expected_total` is given, the update only applies while the total weight still
+    /// matches it, so concurrent updates are not silently overwritten
     UpdateMembers {
         remove: Vec<String>,
         add: Vec<Member>,
+        #[serde(default)]
+        expected_total: Option<u64>,
+    },
+    /// Change the weight and/or identity of an existing member, leaving omitted
+    /// fields as they are. Must be called by Admin
+    UpdateMember {
+        addr: String,
+        weight: Option<u64>,
+        identity: Option<String>,
+    },
+    /// Add `member`, or update it if its weight or identity differ. Nothing happens,
+    /// and no hooks are called, if it is already present as given. Must be called by Admin
+    EnsureMember { member: Member },
+    /// Restore the weights from before the most recent `UpdateMembers` batch.
+    /// Identities keep their current value. Fails if members changed since.
+    /// Must be called by Admin
+    RevertLastBatch {},
+    /// Correct the recorded weight of `addr` from `at_height` up to its next recorded
+    /// change, or up to now if there is none, in which case the current weight changes
+    /// too. Historical totals and merkle roots over the same range are rewritten to
+    /// match. This rewrites history that others may already have relied on, so use it
+    /// with care. `addr` must have been a member at `at_height`. Must be called by Admin
+    BackfillWeight {
+        addr: String,
+        weight: u64,
+        at_height: u64,
+    },
+    /// Add or update members from `data` in the format described in the `packed` module.
+    /// Subject to the same checks as `UpdateMembers`. Must be called by Admin
+    ImportPacked { data: Binary },
+    /// Add a page of another cw4 group's members, summing the weights of addresses
+    /// present in both. Repeat with the last merged address as `start_after` until
+    /// `has_more` is false. Must be called by Admin
+    MergeFrom {
+        source: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Add or update `members`, combining weights of addresses already present as
+    /// `strategy` says. Repeated addresses are combined the same way. Must be called by Admin
+    ImportMerge {
+        members: Vec<Member>,
+        strategy: MergeStrategy,
     },
-    /// Add a new hook to be informed of all membership changes. Must be called by Admin
-    AddHook { addr: String },
+    /// Add a new hook to be informed of all membership changes. Must be called by Admin.
+    /// Hooks with a higher `priority` are called first, ties keep registration order
+    AddHook {
+        addr: String,
+        #[serde(default)]
+        priority: Option<i32>,
+    },
+    /// Add or update a member and, if `as_hook`, also register it as a hook.
+    /// Must be called by the admin
+    AddMemberWithHook { member: Member, as_hook: bool },
+    /// Add a new hook that is only informed of changes touching one of `addrs_of_interest`.
+    /// An empty list behaves like `AddHook`. Must be called by Admin
+    AddHookFiltered {
+        addr: String,
+        addrs_of_interest: Vec<String>,
+    },
+    /// Execute `msg` if `nonce` matches the admin nonce, which is then incremented.
+    /// Must be called by the admin, and is how they send messages at all when
+    /// `require_admin_nonce` is set
+    WithNonce { nonce: u64, msg: Box<ExecuteMsg> },
     /// Remove a hook. Must be called by Admin
     RemoveHook { addr: String },
+    /// Replace the registered hooks with `addrs`, ignoring duplicates. Hooks kept
+    /// from before retain their priority and filter. Must be called by Admin
+    SetHooks { addrs: Vec<String> },
+    /// Apply all scheduled removals whose grace period is over. Locked members stay
+    /// scheduled until unlocked. Can be called by anyone
+    FinalizeRemovals {},
+    /// Remove members whose seat has expired, up to a page at a time. Locked members
+    /// are skipped. Must be called by the admin or an operator
+    PruneExpired {},
+    /// Allow an account to run maintenance. Must be called by Admin
+    AddOperator { addr: String },
+    /// Must be called by Admin
+    RemoveOperator { addr: String },
+    /// Hand the admin role to `new_admin` and replace all operators with
+    /// `new_operators` at once. Must be called by Admin
+    RotateControl {
+        new_admin: String,
+        new_operators: Vec<String>,
+    },
+    /// Drop a scheduled removal, keeping the member. Must be called by Admin
+    CancelRemoval { addr: String },
+    /// Multiply every member's weight by `numerator / denominator`, rounding down.
+    /// Fails if a locked member's weight would change. Must be called by Admin
+    ScaleWeights { numerator: u64, denominator: u64 },
+    /// Adjust the weight of existing members by signed amounts, applied in order.
+    /// Fails if any member would drop below zero or under `min_member_percent`.
+    /// Must be called by Admin
+    ApplyDeltas { deltas: Vec<(String, i64)> },
+    /// Exchange the weights of two members, leaving the total unchanged.
+    /// Must be called by Admin
+    SwapWeights { a: String, b: String },
+    /// Set the weight of the given members to their balance in the weight source.
+    /// Addresses that are not members, or are locked, are skipped. Can be called by anyone
+    SyncWeights { addrs: Vec<String> },
+    /// Set or clear the vesting schedule of a member. Must be called by Admin
+    SetVesting {
+        addr: String,
+        schedule: Option<VestingSchedule>,
+    },
+    /// Set the weight of the given members to their vested weight at the current height.
+    /// Addresses without a schedule are skipped. Can be called by anyone
+    CrankVesting { addrs: Vec<String> },
+    /// Block removals that would bring the total weight below `min`.
+    /// `None` lifts the floor. Must be called by Admin
+    SetMinActiveTotal { min: Option<u64> },
+    /// Reject adding or updating a member with less than `percent` of the total weight
+    /// after the update. Removals are unaffected. `None` lifts the floor. Must be called by Admin
+    SetMinMemberPercent { percent: Option<Decimal> },
+    /// Configure how `DecayWeights` shrinks all weights, counting intervals from now.
+    /// `None` turns decay off. Must be called by Admin
+    SetDecay { decay: Option<WeightDecay> },
+    /// Apply the configured decay once for every interval elapsed since the last one,
+    /// removing members whose weight reaches zero. Locked members keep their weight.
+    /// Fails if the result breaks the `min_active_total` or `min_member_percent` floors.
+    /// Can be called by anyone
+    DecayWeights {},
+    /// Limit how far a single operation may move the total weight, or the weight of
+    /// any one member. `None` lifts the limit. Must be called by Admin
+    SetMaxWeightDelta { max: Option<u64> },
+    /// Set the weight left to hand out with `AllocateFromBudget`, replacing any
+    /// remainder. Must be called by Admin
+    SetBudget { amount: u64 },
+    /// Move `amount` from the budget into the weight of `addr`, adding them as a member
+    /// if needed. Must be called by Admin
+    AllocateFromBudget { addr: String, amount: u64 },
+    /// Describe what the group represents. Must be called by Admin
+    SetGroupInfo {
+        name: String,
+        description: String,
+        url: Option<String>,
+    },
+    /// Replace the tags (eg. committees) of a member. Must be called by Admin
+    SetMemberTags { addr: String, tags: Vec<String> },
+    /// Delegate `amount` of the sender's weight to another member, replacing any
+    /// previous delegation to them. An amount of 0 revokes it. If the sender's weight
+    /// later drops below what it delegates, its delegations are trimmed in address
+    /// order to fit. Must be called by a member
+    DelegatePartial { to: String, amount: u64 },
+    /// Move the seat of `old` to `new`, which must not be a member yet, along with its
+    /// weight, identity, join height, tags, delegations made and received, external
+    /// id, expiry, vesting schedule and frozen status. Must be called by Admin
+    Supersede { old: String, new: String },
+    /// Hand the admin role and the admin's seat, with its weight, over to `new_addr`.
+    /// Must be called by the admin, who must be a member
+    TransferAdminAndSeat { new_addr: String, identity: String },
+    /// Share the admin's powers with `addr` until `expires`, replacing any earlier
+    /// delegation. Changing the admin itself stays reserved to the admin.
+    /// Must be called by the admin
+    DelegateAdmin { addr: String, expires: Expiration },
+    /// End a delegation made with `DelegateAdmin` early. Must be called by the admin
+    RevokeTempAdmin {},
+    /// Propose `addr` as the next admin, replacing any earlier proposal.
+    /// Must be called by the admin
+    ProposeAdmin { addr: String },
+    /// Take over the admin role once the notice period since `ProposeAdmin` has
+    /// passed. Must be called by the proposed address
+    AcceptAdmin {},
+    /// Make admin changes wait `blocks` after `ProposeAdmin`, and only go through it.
+    /// `None` lifts the requirement. Must be called by the admin
+    SetAdminChangeNotice { blocks: Option<u64> },
+    /// Freeze or unfreeze a member's weight. Must be called by the admin
+    SetMemberFrozen { addr: String, frozen: bool },
+    /// Protect a member's seat against updates, removal and transfer.
+    /// Must be called by the admin
+    LockMember { addr: String },
+    /// Lift a lock placed by `LockMember`. Must be called by the admin
+    UnlockMember { addr: String },
+    /// Make `alias` answer member queries with the weight and identity of `canonical`.
+    /// Must be called by the admin
+    SetAlias { alias: String, canonical: String },
+    /// Drop an alias registered with `SetAlias`. Must be called by the admin
+    RemoveAlias { alias: String },
+    /// Set or clear the expiration of a member's seat. Must be called by the admin
+    SetMemberExpiry {
+        addr: String,
+        expires: Option<Expiration>,
+    },
+    /// Notify hooks with a `QuorumCrossedHookMsg` whenever the total weight reaches
+    /// `threshold` or falls back below it. `None` stops watching. Must be called by the admin
+    SetQuorumWatch { threshold: Option<u64> },
+    /// Replace `from_prefix` with `to_prefix` in the identity of every member whose
+    /// identity starts with it. Must be called by the admin
+    RewriteIdentities {
+        from_prefix: String,
+        to_prefix: String,
+    },
+    /// Assign a member's off-chain reference, replacing any previous one.
+    /// External ids are unique. Must be called by the admin
+    SetExternalId { addr: String, external_id: String },
+    /// Propose a new admin, voting for it. Must be called by a member, and replaces
+    /// no open proposal. Requires emergency recovery to be enabled
+    ProposeEmergencyAdmin { addr: String },
+    /// Vote for the open emergency admin proposal. Must be called by a member.
+    /// The proposed address becomes admin once the votes exceed the supermajority
+    VoteEmergencyAdmin {},
+    /// Block any change to the member set until `Unfreeze`. Must be called by the admin
+    Freeze {},
+    /// Lift a previous `Freeze`. Must be called by the admin
+    Unfreeze {},
+    /// Reject every message, other than those managing the window, while the block
+    /// time is from `start` up to but excluding `end`. Replaces any earlier window.
+    /// Must be called by the admin
+    SetFreezeWindow { start: Timestamp, end: Timestamp },
+    /// Remove the freeze window. Must be called by the admin
+    ClearFreezeWindow {},
+    /// Reject just the given kinds of operation, replacing the previously paused ones.
+    /// Member changes are judged by their effect, whatever message causes them, and
+    /// `Dissolve` is not affected. Must be called by the admin
+    SetPausedOps { ops: Vec<OpKind> },
+    /// Remove every member and permanently block further changes.
+    /// Must be called by the admin
+    Dissolve {},
+    /// Dissolve the group if it has expired. Can be called by anyone
+    CheckExpiry {},
+    /// Set or clear the secp256k1 public key whose attestations `ApplyAttestation`
+    /// accepts. Must be called by the admin
+    SetAttester { pubkey: Option<Binary> },
+    /// Apply the JSON encoded `Attestation`, if `signature` is the attester's signature
+    /// over its sha256 hash. Can be called by anyone
+    ApplyAttestation {
+        attestation: Binary,
+        signature: Binary,
+    },
+    /// Give up the sender's own seat. Only available if `allow_self_leave` is set, and
+    /// subject to the same min weight, min active total and max weight delta checks as
+    /// an admin removal. Must be called by a member
+    Leave {},
+    /// Remember the current height under `name`, replacing any earlier checkpoint
+    /// of that name. Must be called by the admin
+    SaveCheckpoint { name: String },
+    /// Keep the current member set forever, rejecting every further message,
+    /// admin changes included. Must be called by the admin
+    Seal {},
 }
 
 #[cw_serde]
@@ -36,11 +351,49 @@ pub enum QueryMsg {
     Admin {},
     #[returns(cw4::TotalWeightResponse)]
     TotalWeight { at_height: Option<u64> },
+    /// Total weight minus the weight of `addrs`, counting only those that were
+    /// members at the height. Repeated addresses are subtracted once.
+    /// At most 30 addresses can be given at once
+    #[returns(cw4::TotalWeightResponse)]
+    TotalWeightExcluding {
+        addrs: Vec<String>,
+        at_height: Option<u64>,
+    },
+    /// Whether `addrs` together hold more than `fraction` of the total weight at the
+    /// height. Reaching the fraction exactly is not a majority. Repeated addresses count once.
+    /// At most 30 addresses can be given at once
+    #[returns(IsMajorityResponse)]
+    IsMajority {
+        addrs: Vec<String>,
+        fraction: Decimal,
+        at_height: Option<u64>,
+    },
+    /// Those of `addrs` that are current members, in the order given. Repeated
+    /// addresses are listed once. At most 30 addresses can be checked at once
+    #[returns(MemberListResponse)]
+    IntersectMembers { addrs: Vec<String> },
+    /// Addresses that are members here but not of the cw4 group `other`, and the
+    /// reverse, walking both groups by address. Repeat with `last` as `start_after`
+    /// until it is `None`. Pages may be empty while `last` is set
+    #[returns(CompareToGroupResponse)]
+    CompareToGroup {
+        other: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(MemberListResponse)]
     ListMembers {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Lists members with their share of the total weight, optionally at a past height.
+    /// Historical pages include members removed since, so they sum to the total at that height
+    #[returns(ListSharesResponse)]
+    ListShares {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        at_height: Option<u64>,
+    },
     #[returns(MemberResponse)]
     Member {
         addr: String,
@@ -49,4 +402,814 @@ pub enum QueryMsg {
     /// Shows all registered hooks.
     #[returns(cw_controllers::HooksResponse)]
     Hooks {},
+    /// The cw2 contract name and version of each registered hook, in registration
+    /// order. Only the first 20 hooks are queried
+    #[returns(HooksInfoResponse)]
+    HooksInfo {},
+    /// Returns the group configuration
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Returns the member right after `after` in address order, if any
+    #[returns(NextMemberResponse)]
+    NextMember { after: String },
+    /// Weight of a member after applying incoming and outgoing delegations
+    #[returns(EffectiveWeightResponse)]
+    EffectiveWeight { addr: String },
+    /// The weight `addr` controls for voting, combining its own weight at the height
+    /// with delegations, freezes and expiry. Only weights are kept per height, the
+    /// other parts (and the delegator weights that cap what is received) are applied
+    /// as they stand now, even with `at_height`. This is the query voting modules
+    /// should use
+    #[returns(ResolvedWeightResponse)]
+    ResolvedWeight {
+        addr: String,
+        at_height: Option<u64>,
+    },
+    /// Delegations made and received by a member
+    #[returns(DelegationsResponse)]
+    Delegations { addr: String },
+    /// Checks a merkle proof (see `merkle`) that `addr` had `weight` at the start
+    /// of block `height`, as `Member { at_height }` would report
+    #[returns(VerifyProofResponse)]
+    VerifyProof {
+        addr: String,
+        weight: u64,
+        height: u64,
+        proof: Vec<Binary>,
+    },
+    /// Hash of the member set at the start of block `at_height`, or now if omitted.
+    /// It is the merkle root (see `merkle`) over all (address, weight) pairs, so it is
+    /// independent of the order of updates. Identities are not covered
+    #[returns(StateHashResponse)]
+    StateHash { at_height: Option<u64> },
+    /// Addresses whose weight at `to_height` differs from their weight at `from_height`,
+    /// including members added or removed in between, ordered by address. Each page
+    /// checks up to `limit` addresses, so repeat with `last` as `start_after` until it
+    /// is `None`. Pages may be empty while `last` is set
+    #[returns(ChangedMembersResponse)]
+    ChangedMembers {
+        from_height: u64,
+        to_height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Addresses whose weight now differs from their weight at the checkpoint `name`,
+    /// ordered by address. Paginated like `ChangedMembers`
+    #[returns(ChangedMembersResponse)]
+    DiffFromCheckpoint {
+        name: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Members added and removed by changes recorded between `from_height` and
+    /// `to_height` inclusive, and the net effect of those changes on the total weight.
+    /// Several changes to one address within a block count as one. Each page covers
+    /// up to `limit` addresses with recorded changes, so repeat with `last` as
+    /// `start_after` until it is `None` and sum the pages
+    #[returns(ChurnResponse)]
+    Churn {
+        from_height: u64,
+        to_height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Heights of the first and last recorded weight change of an address
+    #[returns(MemberSnapshotBoundsResponse)]
+    MemberSnapshotBounds { addr: String },
+    /// Weight changes of a member recorded between `from_height` and `to_height`
+    /// inclusive, oldest first
+    #[returns(MemberHistoryResponse)]
+    MemberHistory {
+        addr: String,
+        from_height: u64,
+        to_height: u64,
+        limit: Option<u32>,
+    },
+    /// Weight needed to meet both an absolute threshold and a fraction of the total
+    /// weight (at `at_height` if given), whichever is higher. At least one is required
+    #[returns(EffectiveThresholdResponse)]
+    EffectiveThreshold {
+        absolute: Option<u64>,
+        percent: Option<Decimal>,
+        at_height: Option<u64>,
+    },
+    /// Additional weight `addr` would need to hold at least `target_percent` of the
+    /// total, counting the added weight in the total as well
+    #[returns(WeightForShareResponse)]
+    WeightForShare {
+        addr: String,
+        target_percent: Decimal,
+    },
+    /// Weight of a member according to its vesting schedule, at the current height
+    /// if none is given. This may differ from `Member` until `CrankVesting` is called
+    #[returns(VestedWeightResponse)]
+    VestedWeight {
+        addr: String,
+        at_height: Option<u64>,
+    },
+    /// Members whose weight is exactly `weight`, ordered by address
+    #[returns(MemberListResponse)]
+    MembersWithWeight {
+        weight: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Weight a new member needs to rank among the `n` heaviest, regardless of address
+    #[returns(WeightToEnterTopResponse)]
+    WeightToEnterTop { n: u64 },
+    /// Combined weight of the `top_n` heaviest members
+    #[returns(CumulativeWeightResponse)]
+    CumulativeWeight { top_n: u64 },
+    /// Fraction of current members with a weight of at most `weight`, zero if
+    /// there are no members
+    #[returns(WeightPercentileResponse)]
+    WeightPercentile { weight: u64 },
+    /// 1-based position of a member by descending weight, ties broken by address
+    #[returns(MemberRankResponse)]
+    MemberRank { addr: String },
+    /// Smallest set of members whose combined weight reaches `threshold_percent` of the total
+    #[returns(MinimalCoalitionResponse)]
+    MinimalCoalition { threshold_percent: Decimal },
+    /// When and by whom the contract was instantiated
+    #[returns(ProvenanceResponse)]
+    Provenance {},
+    /// Average height current members joined at, weighted by their weight.
+    /// Members from before join heights were tracked are left out of the average,
+    /// and their combined weight is reported separately
+    #[returns(AverageJoinHeightResponse)]
+    AverageJoinHeight {},
+    /// How many members, counted from the lightest if `from_bottom` or else from the
+    /// heaviest, it takes to reach `threshold_percent` of the total
+    #[returns(MembersToReachResponse)]
+    MembersToReach {
+        threshold_percent: Decimal,
+        from_bottom: bool,
+    },
+    /// Fewest members, heaviest first, that together hold more than `threshold` of
+    /// the total weight. `threshold` defaults to 1/3 and must be below 1
+    #[returns(NakamotoCoefficientResponse)]
+    NakamotoCoefficient { threshold: Option<Decimal> },
+    /// Splits the members, ordered by weight, into `n` tiers of (near) equal size,
+    /// lightest first. Earlier tiers hold one member less when they cannot be equal
+    #[returns(QuantilesResponse)]
+    Quantiles { n: u32 },
+    /// Checks `member` against every constraint an add would be subject to,
+    /// reporting all that fail
+    #[returns(CanAddResponse)]
+    CanAdd { member: Member },
+    /// Lists members ordered by identity, then address. Members without an
+    /// identity come last. `start_after` is the address of the last member returned
+    #[returns(MemberListResponse)]
+    ListByIdentity {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists members whose identity starts with `prefix`, ordered by identity, then address
+    #[returns(MemberListResponse)]
+    SearchByIdentityPrefix { prefix: String, limit: Option<u32> },
+    /// Finds the member assigned an off-chain reference with `SetExternalId`
+    #[returns(MemberByExternalIdResponse)]
+    MemberByExternalId { external_id: String },
+    /// Lists members carrying the given tag, ordered by address
+    #[returns(MemberListResponse)]
+    ListByTag {
+        tag: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists members ordered by the height they joined at, oldest first unless `reverse`.
+    /// `start_after` is the last address of the previous page. Members from before join
+    /// heights were tracked are not listed
+    #[returns(MemberListResponse)]
+    ListByJoinHeight {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        reverse: Option<bool>,
+    },
+    /// Sum of the weights of members carrying the given tag
+    #[returns(TagWeightResponse)]
+    TagWeight { tag: String },
+    /// Sum of the weights of members carrying each of the given tags, in the order
+    /// given. Members with several of the tags count toward each. Repeated tags are
+    /// listed once. At most 30 tags can be summed at once
+    #[returns(WeightByTagResponse)]
+    WeightByTag { tags: Vec<String> },
+    /// Accounts allowed to run maintenance besides the admin
+    #[returns(OperatorsResponse)]
+    ListOperators {},
+    /// Members scheduled for removal once their grace period ends
+    #[returns(PendingRemovalsResponse)]
+    PendingRemovals {},
+    /// The earliest scheduled change to a member's weight. Removals pending
+    /// finalization are the only changes that are scheduled
+    #[returns(NextScheduledResponse)]
+    NextScheduledFor { addr: String },
+    /// The admin, along with their seat if they are also a member
+    #[returns(AdminMemberResponse)]
+    AdminMember {},
+    /// Weight held by frozen or expired members, next to the remaining active weight
+    #[returns(InactiveWeightResponse)]
+    InactiveWeight {},
+    /// Rough estimate of the storage used by members and their identities
+    #[returns(StorageStatsResponse)]
+    StorageStats {},
+    /// Cheap liveness check for monitoring. Never fails, even before instantiation
+    #[returns(HealthResponse)]
+    Health {},
+    /// How far the total weight is from the configured bounds
+    #[returns(HeadroomReportResponse)]
+    HeadroomReport {},
+    /// Admin, weights, member count and frozen state in a single query
+    #[returns(OverviewResponse)]
+    Overview {},
+    /// The open emergency admin proposal and the weight voting for it
+    #[returns(EmergencyProposalResponse)]
+    EmergencyProposal {},
+    /// Whether the member set is currently frozen
+    #[returns(FrozenResponse)]
+    Frozen {},
+    /// Kinds of operation currently paused
+    #[returns(PausedOpsResponse)]
+    PausedOps {},
+    /// The scheduled freeze window, and whether the current block falls in it
+    #[returns(FreezeWindowResponse)]
+    FreezeWindow {},
+    /// Whether the group has been sealed
+    #[returns(SealedResponse)]
+    IsSealed {},
+    /// Whether `addr` is locked against changes
+    #[returns(IsLockedResponse)]
+    IsLocked { addr: String },
+    /// When the group expires, and whether it already has
+    #[returns(ExpirationResponse)]
+    Expiration {},
+    /// Weight still available to `AllocateFromBudget`
+    #[returns(RemainingBudgetResponse)]
+    RemainingBudget {},
+    /// The nonce the next `WithNonce` message must carry
+    #[returns(AdminNonceResponse)]
+    AdminNonce {},
+    /// The proposed next admin and the height from which it can accept
+    #[returns(AdminChangeReadyAtResponse)]
+    AdminChangeReadyAt {},
+    /// The address currently holding delegated admin rights, if any
+    #[returns(TempAdminResponse)]
+    TempAdmin {},
+    /// Height at which the group was dissolved, if it was
+    #[returns(DissolvedResponse)]
+    Dissolved {},
+    /// Lifetime activity counters
+    #[returns(crate::state::Stats)]
+    Stats {},
+    /// Name and description of the group, empty until set
+    #[returns(crate::state::GroupInfo)]
+    GroupInfo {},
+    /// Diffs of the most recent `UpdateMembers` batch
+    #[returns(LastBatchDiffResponse)]
+    LastBatchDiff {},
+    /// Block height and time of the last membership change, empty on groups migrated
+    /// from a version that did not record it and unchanged since
+    #[returns(LastChangeResponse)]
+    LastChange {},
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub min_weight: u64,
+    pub max_weight: u64,
+    pub weight_unit: Option<String>,
+    pub removal_grace_blocks: Option<u64>,
+    pub weight_source: Option<String>,
+    pub min_active_total: Option<u64>,
+    pub strict_removals: bool,
+    pub require_identity: bool,
+    pub quorum_watch: Option<u64>,
+    pub validate_hook_contracts: bool,
+    pub emergency_recovery: Option<EmergencyRecovery>,
+    pub expires_at: Option<Expiration>,
+    pub auto_rebalance: bool,
+    pub rebalance_target: u64,
+    pub rebalance_remainder_to: Option<String>,
+    pub require_admin_nonce: bool,
+    pub allow_self_leave: bool,
+    pub emit_payload_event: bool,
+    pub min_member_percent: Option<Decimal>,
+    pub max_weight_delta_per_op: Option<u64>,
+    pub decay: Option<WeightDecay>,
+    pub admin_change_notice_blocks: Option<u64>,
+    /// Page size used by list queries when no limit is given
+    pub default_limit: u32,
+    /// Largest page size list queries will return
+    pub max_limit: u32,
+}
+
+/// Query sent to the weight source, compatible with cw20 `Balance`
+#[cw_serde]
+pub enum WeightSourceQueryMsg {
+    Balance { address: String },
+}
+
+#[cw_serde]
+pub struct WeightSourceBalanceResponse {
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct NextMemberResponse {
+    pub member: Option<Member>,
+}
+
+#[cw_serde]
+pub struct EffectiveWeightResponse {
+    /// The member's own weight
+    pub weight: u64,
+    pub delegated_out: u64,
+    pub delegated_in: u64,
+    /// `weight - delegated_out + delegated_in`
+    pub effective: u64,
+}
+
+#[cw_serde]
+pub struct ResolvedWeightResponse {
+    /// The member's own weight at the height
+    pub weight: u64,
+    pub delegated_out: u64,
+    /// Delegated by members that are neither frozen nor expired
+    pub delegated_in: u64,
+    pub frozen: bool,
+    pub expired: bool,
+    /// Zero if frozen or expired, otherwise `weight - delegated_out + delegated_in`
+    pub resolved: u64,
+}
+
+#[cw_serde]
+pub struct Delegation {
+    pub addr: String,
+    pub amount: u64,
+}
+
+#[cw_serde]
+pub struct DelegationsResponse {
+    /// Delegations made by the member
+    pub outgoing: Vec<Delegation>,
+    /// Delegations received by the member
+    pub incoming: Vec<Delegation>,
+}
+
+#[cw_serde]
+pub struct VerifyProofResponse {
+    pub valid: bool,
+    /// The root the proof was checked against, if the group existed at that height
+    pub root: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct CompareToGroupResponse {
+    pub only_here: Vec<String>,
+    pub only_there: Vec<String>,
+    /// Last address covered, `None` once both groups have been compared in full
+    pub last: Option<String>,
+}
+
+#[cw_serde]
+pub struct IsMajorityResponse {
+    pub is_majority: bool,
+    /// Combined weight of the given addresses
+    pub their_weight: u64,
+    pub total_weight: u64,
+}
+
+#[cw_serde]
+pub struct AdminMemberResponse {
+    /// `None` if the group has no admin
+    pub admin: Option<String>,
+    /// `None` if the admin is not a member
+    pub member: Option<MemberResponse>,
+}
+
+#[cw_serde]
+pub struct StateHashResponse {
+    /// `None` if the group did not exist at that height
+    pub hash: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct EffectiveThresholdResponse {
+    pub threshold: u64,
+    /// Total weight the percentage was resolved against
+    pub total_weight: u64,
+}
+
+#[cw_serde]
+pub struct WeightForShareResponse {
+    /// Current weight of the address, 0 for non-members
+    pub weight: u64,
+    pub total_weight: u64,
+    /// Smallest additional weight reaching the target, `None` if it cannot be reached
+    pub additional: Option<u64>,
+}
+
+#[cw_serde]
+pub struct VestedWeightResponse {
+    /// `None` if the address has no vesting schedule
+    pub weight: Option<u64>,
+}
+
+#[cw_serde]
+pub struct WeightPercentileResponse {
+    pub percentile: Decimal,
+}
+
+#[cw_serde]
+pub struct WeightToEnterTopResponse {
+    /// One more than the weight of the `n`th member, or zero if there are fewer than `n`
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct CumulativeWeightResponse {
+    /// Number of members counted, `top_n` capped at the member count
+    pub top_n: u64,
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct MemberRankResponse {
+    pub rank: u64,
+    /// Number of members ranked
+    pub out_of: u64,
+}
+
+#[cw_serde]
+pub struct ProvenanceResponse {
+    /// Both are `None` for groups instantiated before provenance was recorded
+    pub instantiated_at: Option<u64>,
+    pub creator: Option<String>,
+}
+
+#[cw_serde]
+pub struct AverageJoinHeightResponse {
+    /// `None` if no member with a known join height has weight
+    pub height: Option<u64>,
+    /// Weight of members whose join height is unknown
+    pub untracked_weight: u64,
+}
+
+#[cw_serde]
+pub struct NakamotoCoefficientResponse {
+    /// Zero for an empty group
+    pub coefficient: u64,
+    pub threshold: Decimal,
+}
+
+#[cw_serde]
+pub struct MembersToReachResponse {
+    pub count: u64,
+    /// Combined weight of the counted members
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct QuantileTier {
+    pub count: u64,
+    /// Combined weight of the tier
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct QuantilesResponse {
+    pub tiers: Vec<QuantileTier>,
+}
+
+#[cw_serde]
+pub struct MinimalCoalitionResponse {
+    /// Members of the coalition, heaviest first
+    pub members: Vec<Member>,
+    /// Combined weight of the coalition
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct MemberShare {
+    pub addr: String,
+    pub weight: u64,
+    /// `weight / total_weight`, rounded down. Zero if the group has no weight
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct ListSharesResponse {
+    pub shares: Vec<MemberShare>,
+    pub total_weight: u64,
+    pub has_more: bool,
+}
+
+#[cw_serde]
+pub struct CanAddResponse {
+    pub ok: bool,
+    /// Description of each failed constraint
+    pub violations: Vec<String>,
+}
+
+#[cw_serde]
+pub struct WeightChange {
+    /// Height at which the change was made, effective from the next block
+    pub height: u64,
+    /// Weight after the change, `None` if the member was removed
+    pub weight: Option<u64>,
+}
+
+#[cw_serde]
+pub struct ChurnResponse {
+    pub added: u64,
+    pub removed: u64,
+    pub net_weight_change: i64,
+    /// Last address covered, `None` once the history has been covered in full
+    pub last: Option<String>,
+}
+
+#[cw_serde]
+pub struct ChangedMembersResponse {
+    /// Weight at `from_height` as `old` and at `to_height` as `new`
+    pub changes: Vec<MemberDiff>,
+    /// Last address checked, `None` once the history has been covered in full
+    pub last: Option<String>,
+}
+
+#[cw_serde]
+pub struct MemberSnapshotBoundsResponse {
+    /// `None` if the address never had a weight
+    pub first_height: Option<u64>,
+    pub last_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct MemberHistoryResponse {
+    pub changes: Vec<WeightChange>,
+    /// Whether more changes follow in the requested range
+    pub has_more: bool,
+}
+
+#[cw_serde]
+pub struct InactiveWeightResponse {
+    /// Weight of members that are frozen or past their expiration
+    pub inactive: u64,
+    /// Total weight minus `inactive`
+    pub active: u64,
+}
+
+/// Sizes of the stored keys and values, ignoring namespaces, snapshots and indexes
+#[cw_serde]
+pub struct StorageStatsResponse {
+    /// Members counted, at most the scan limit
+    pub member_count: u64,
+    /// Length of the counted members' identities
+    pub total_identity_bytes: u64,
+    /// Bytes used by the counted weights, identity ids and interned identities
+    pub estimated_bytes: u64,
+    /// Whether the scan stopped before reaching all members
+    pub truncated: bool,
+}
+
+#[cw_serde]
+pub struct OverviewResponse {
+    pub admin: Option<String>,
+    pub total_weight: u64,
+    pub member_count: u64,
+    pub min_weight: u64,
+    pub max_weight: u64,
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct HeadroomReportResponse {
+    /// Weight that can still be added before reaching the max
+    pub weight_to_max: u64,
+    /// Weight that can still be removed before falling below the min
+    pub weight_to_min: u64,
+    /// Members of the current average weight that still fit under the max,
+    /// `None` while there is no average to go by
+    pub members_to_max_count: Option<u64>,
+}
+
+#[cw_serde]
+pub struct HealthResponse {
+    /// Whether the contract has been instantiated
+    pub initialized: bool,
+    pub total_weight: u64,
+    pub member_count: u64,
+    /// Whether the total weight lies within the configured min and max
+    pub within_bounds: bool,
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct EmergencyProposalResponse {
+    pub addr: Option<String>,
+    pub expires_at: Option<u64>,
+    /// Current weight of the members who voted for it
+    pub votes: u64,
+}
+
+#[cw_serde]
+pub struct SealedResponse {
+    pub sealed: bool,
+}
+
+#[cw_serde]
+pub struct FrozenResponse {
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct PausedOpsResponse {
+    pub ops: Vec<OpKind>,
+}
+
+#[cw_serde]
+pub struct FreezeWindowResponse {
+    /// `None` if no window is set
+    pub start: Option<Timestamp>,
+    pub end: Option<Timestamp>,
+    pub active: bool,
+}
+
+#[cw_serde]
+pub struct ExpirationResponse {
+    pub expires_at: Option<Expiration>,
+    pub expired: bool,
+}
+
+#[cw_serde]
+pub struct HookInfo {
+    pub addr: String,
+    /// `None` if the hook does not expose cw2 contract info
+    pub name: Option<String>,
+    pub version: Option<String>,
+}
+
+#[cw_serde]
+pub struct HooksInfoResponse {
+    pub hooks: Vec<HookInfo>,
+}
+
+#[cw_serde]
+pub struct RemainingBudgetResponse {
+    pub remaining: u64,
+}
+
+#[cw_serde]
+pub struct AdminNonceResponse {
+    pub nonce: u64,
+}
+
+#[cw_serde]
+pub struct AdminChangeReadyAtResponse {
+    /// `None` if no admin change is proposed
+    pub proposed: Option<String>,
+    pub ready_at: Option<u64>,
+}
+
+#[cw_serde]
+pub struct TempAdminResponse {
+    /// `None` if there is no delegation, or it has lapsed
+    pub addr: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct IsLockedResponse {
+    pub locked: bool,
+}
+
+#[cw_serde]
+pub struct DissolvedResponse {
+    pub dissolved_at: Option<u64>,
+}
+
+#[cw_serde]
+pub struct MemberByExternalIdResponse {
+    pub member: Option<Member>,
+}
+
+#[cw_serde]
+pub struct TagWeightResponse {
+    pub tag: String,
+    pub weight: u64,
+}
+
+#[cw_serde]
+pub struct WeightByTagResponse {
+    pub tags: Vec<TagWeightResponse>,
+}
+
+#[cw_serde]
+pub struct OperatorsResponse {
+    pub operators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct PendingRemoval {
+    pub addr: String,
+    /// Height from which the removal can be finalized
+    pub remove_at: u64,
+}
+
+#[cw_serde]
+pub struct PendingRemovalsResponse {
+    pub removals: Vec<PendingRemoval>,
+}
+
+#[cw_serde]
+pub struct NextScheduledResponse {
+    /// Height from which the change can apply, `None` if nothing is scheduled
+    pub at_height: Option<u64>,
+    pub new_weight: Option<u64>,
+}
+
+#[cw_serde]
+pub struct LastChangeResponse {
+    pub height: Option<u64>,
+    pub time: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub enum MergeStrategy {
+    /// Add the imported weight to the present one, keeping the present identity
+    Sum,
+    /// Keep the larger of the two weights and the present identity
+    Max,
+    /// Overwrite the present member with the imported one
+    Replace,
+}
+
+#[cw_serde]
+pub enum CrossingDirection {
+    /// The total reached the threshold from below
+    Up,
+    /// The total fell below the threshold
+    Down,
+}
+
+/// Sent to all hooks, under a `QuorumCrossedHook` variant, when the total weight
+/// crosses the watched threshold
+#[cw_serde]
+pub struct QuorumCrossedHookMsg {
+    pub threshold: u64,
+    pub new_total: u64,
+    pub direction: CrossingDirection,
+}
+
+impl QuorumCrossedHookMsg {
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&QuorumCrossedExecuteMsg::QuorumCrossedHook(self))?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[cw_serde]
+enum QuorumCrossedExecuteMsg {
+    QuorumCrossedHook(QuorumCrossedHookMsg),
+}
+
+/// Sent to the `init_callback` contract, under a `GroupInstantiated` variant,
+/// once the group is instantiated
+#[cw_serde]
+pub struct GroupInstantiatedMsg {
+    /// The message given in the `InitCallback`
+    pub msg: Binary,
+    pub total_weight: u64,
+}
+
+impl GroupInstantiatedMsg {
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&GroupInstantiatedExecuteMsg::GroupInstantiated(self))?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[cw_serde]
+enum GroupInstantiatedExecuteMsg {
+    GroupInstantiated(GroupInstantiatedMsg),
+}
+
+#[cw_serde]
+pub struct LastBatchDiffResponse {
+    /// Height of the batch, `None` if there was none yet
+    pub height: Option<u64>,
+    pub diffs: Vec<MemberDiff>,
 }
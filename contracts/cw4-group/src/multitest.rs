@@ -0,0 +1,754 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    from_slice, to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, Uint128,
+};
+use cw4::{Member, MemberChangedHookMsg, MemberListResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::{
+    CompareToGroupResponse, CrossingDirection, ExecuteMsg, GroupInstantiatedMsg, HookInfo,
+    HooksInfoResponse, InitCallback, InstantiateMsg, QueryMsg, QuorumCrossedHookMsg,
+    WeightSourceBalanceResponse, WeightSourceQueryMsg,
+};
+
+const OWNER: &str = "admin0001";
+const MEMBER1: &str = "member0001";
+const MEMBER2: &str = "member0002";
+const OUTSIDER: &str = "outsider";
+
+fn member<T: Into<String>>(addr: T, weight: u64) -> Member {
+    Member {
+        addr: addr.into(),
+        weight,
+        identity: "identity".to_string(),
+    }
+}
+
+fn contract_group() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+// a minimal token contract answering cw20 balance queries
+mod mock_token {
+    use super::*;
+
+    const BALANCES: Map<&Addr, Uint128> = Map::new("balances");
+
+    #[cw_serde]
+    pub struct InstantiateMsg {
+        pub balances: Vec<(String, Uint128)>,
+    }
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for (addr, balance) in msg.balances {
+            BALANCES.save(deps.storage, &Addr::unchecked(addr), &balance)?;
+        }
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: WeightSourceQueryMsg) -> StdResult<Binary> {
+        match msg {
+            WeightSourceQueryMsg::Balance { address } => {
+                let balance = BALANCES
+                    .may_load(deps.storage, &Addr::unchecked(address))?
+                    .unwrap_or_default();
+                to_binary(&WeightSourceBalanceResponse { balance })
+            }
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+// a hook receiver recording the quorum crossings it is told about
+mod mock_hook {
+    use super::*;
+
+    const CROSSINGS: Item<Vec<QuorumCrossedHookMsg>> = Item::new("crossings");
+    pub const CHANGES: Item<Vec<MemberChangedHookMsg>> = Item::new("changes");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        MemberChangedHook(MemberChangedHookMsg),
+        QuorumCrossedHook(QuorumCrossedHookMsg),
+    }
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, "mock-hook", "0.1.0")?;
+        CROSSINGS.save(deps.storage, &vec![])?;
+        CHANGES.save(deps.storage, &vec![])?;
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ExecuteMsg::QuorumCrossedHook(crossing) => {
+                CROSSINGS.update(deps.storage, |mut crossings| -> StdResult<_> {
+                    crossings.push(crossing);
+                    Ok(crossings)
+                })?;
+            }
+            ExecuteMsg::MemberChangedHook(change) => {
+                CHANGES.update(deps.storage, |mut changes| -> StdResult<_> {
+                    changes.push(change);
+                    Ok(changes)
+                })?;
+            }
+        }
+        Ok(Response::default())
+    }
+
+    pub fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&CROSSINGS.load(deps.storage)?)
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+mod mock_parent {
+    use super::*;
+
+    const NOTICE: Item<GroupInstantiatedMsg> = Item::new("notice");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        GroupInstantiated(GroupInstantiatedMsg),
+    }
+
+    pub fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        let ExecuteMsg::GroupInstantiated(notice) = msg;
+        NOTICE.save(deps.storage, &notice)?;
+        Ok(Response::default())
+    }
+
+    pub fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&NOTICE.may_load(deps.storage)?)
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+fn group_instantiate_msg(members: Vec<Member>) -> InstantiateMsg {
+    InstantiateMsg {
+        admin: Some(OWNER.into()),
+        members,
+        min_weight: 0,
+        max_weight: 1000,
+        weight_unit: None,
+        removal_grace_blocks: None,
+        weight_source: None,
+        strict_removals: false,
+        require_identity: false,
+        validate_hook_contracts: false,
+        emergency_recovery: None,
+        frozen: None,
+        init_callback: None,
+        expires_at: None,
+        auto_rebalance: false,
+        rebalance_target: 0,
+        rebalance_remainder_to: None,
+        require_admin_nonce: false,
+        allow_self_leave: false,
+        emit_payload_event: false,
+    }
+}
+
+fn instantiate_group(app: &mut App, msg: InstantiateMsg) -> Addr {
+    let group_id = app.store_code(contract_group());
+    app.instantiate_contract(group_id, Addr::unchecked(OWNER), &msg, &[], "group", None)
+        .unwrap()
+}
+
+fn list_members(app: &App, group: &Addr) -> Vec<Member> {
+    let res: MemberListResponse = app
+        .wrap()
+        .query_wasm_smart(
+            group,
+            &QueryMsg::ListMembers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    res.members
+}
+
+#[test]
+fn sync_weights_from_token_balances() {
+    let mut app = App::default();
+
+    let token_id = app.store_code(mock_token::contract());
+    let balances = vec![
+        (MEMBER1.to_string(), Uint128::new(40)),
+        (MEMBER2.to_string(), Uint128::new(7)),
+        (OUTSIDER.to_string(), Uint128::new(500)),
+    ];
+    let token = app
+        .instantiate_contract(
+            token_id,
+            Addr::unchecked(OWNER),
+            &mock_token::InstantiateMsg { balances },
+            &[],
+            "token",
+            None,
+        )
+        .unwrap();
+
+    let msg = InstantiateMsg {
+        weight_source: Some(token.to_string()),
+        ..group_instantiate_msg(vec![member(MEMBER1, 1), member(MEMBER2, 1)])
+    };
+    let group = instantiate_group(&mut app, msg);
+
+    // anyone can sync, non-members are ignored
+    let sync = ExecuteMsg::SyncWeights {
+        addrs: vec![MEMBER1.into(), MEMBER2.into(), OUTSIDER.into()],
+    };
+    app.execute_contract(Addr::unchecked(OUTSIDER), group.clone(), &sync, &[])
+        .unwrap();
+
+    assert_eq!(
+        list_members(&app, &group),
+        vec![member(MEMBER1, 40), member(MEMBER2, 7)]
+    );
+    let total: cw4::TotalWeightResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &QueryMsg::TotalWeight { at_height: None })
+        .unwrap();
+    assert_eq!(total.weight, 47);
+}
+
+#[test]
+fn sync_weights_skips_locked_members() {
+    let mut app = App::default();
+    let token_id = app.store_code(mock_token::contract());
+    let balances = vec![
+        (MEMBER1.to_string(), Uint128::new(40)),
+        (MEMBER2.to_string(), Uint128::new(7)),
+    ];
+    let token = app
+        .instantiate_contract(
+            token_id,
+            Addr::unchecked(OWNER),
+            &mock_token::InstantiateMsg { balances },
+            &[],
+            "token",
+            None,
+        )
+        .unwrap();
+    let msg = InstantiateMsg {
+        weight_source: Some(token.to_string()),
+        ..group_instantiate_msg(vec![member(MEMBER1, 1), member(MEMBER2, 1)])
+    };
+    let group = instantiate_group(&mut app, msg);
+
+    let lock = ExecuteMsg::LockMember {
+        addr: MEMBER2.into(),
+    };
+    app.execute_contract(Addr::unchecked(OWNER), group.clone(), &lock, &[])
+        .unwrap();
+    let sync = ExecuteMsg::SyncWeights {
+        addrs: vec![MEMBER1.into(), MEMBER2.into()],
+    };
+    app.execute_contract(Addr::unchecked(OUTSIDER), group.clone(), &sync, &[])
+        .unwrap();
+    assert_eq!(
+        list_members(&app, &group),
+        vec![member(MEMBER1, 40), member(MEMBER2, 1)]
+    );
+}
+
+#[test]
+fn sync_weights_requires_source() {
+    let mut app = App::default();
+    let group = instantiate_group(
+        &mut app,
+        group_instantiate_msg(vec![member(MEMBER1, 1), member(MEMBER2, 1)]),
+    );
+
+    let sync = ExecuteMsg::SyncWeights {
+        addrs: vec![MEMBER1.into()],
+    };
+    let err = app
+        .execute_contract(Addr::unchecked(OWNER), group, &sync, &[])
+        .unwrap_err();
+    assert_eq!(
+        crate::ContractError::NoWeightSource {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn quorum_crossings_notify_hooks_once() {
+    let mut app = App::default();
+    let group = instantiate_group(
+        &mut app,
+        group_instantiate_msg(vec![member(MEMBER1, 5), member(MEMBER2, 3)]),
+    );
+    let hook_id = app.store_code(mock_hook::contract());
+    let hook = app
+        .instantiate_contract(
+            hook_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "hook",
+            None,
+        )
+        .unwrap();
+
+    let owner = Addr::unchecked(OWNER);
+    let msgs = vec![
+        ExecuteMsg::AddHook {
+            addr: hook.to_string(),
+            priority: None,
+        },
+        ExecuteMsg::SetQuorumWatch {
+            threshold: Some(10),
+        },
+    ];
+    for msg in msgs {
+        app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    let update = |app: &mut App, add: Vec<Member>, remove: Vec<&str>| {
+        let msg = ExecuteMsg::UpdateMembers {
+            add,
+            remove: remove.into_iter().map(String::from).collect(),
+            expected_total: None,
+        };
+        app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+            .unwrap();
+    };
+    // 8 -> 12 crosses up, 12 -> 13 stays above
+    update(&mut app, vec![member(OUTSIDER, 4)], vec![]);
+    update(&mut app, vec![member(OUTSIDER, 5)], vec![]);
+    // 13 -> 8 crosses down, 8 -> 5 stays below
+    update(&mut app, vec![], vec![OUTSIDER]);
+    update(&mut app, vec![], vec![MEMBER2]);
+
+    let crossings: Vec<QuorumCrossedHookMsg> =
+        app.wrap().query_wasm_smart(&hook, &Empty {}).unwrap();
+    assert_eq!(
+        crossings,
+        vec![
+            QuorumCrossedHookMsg {
+                threshold: 10,
+                new_total: 12,
+                direction: CrossingDirection::Up,
+            },
+            QuorumCrossedHookMsg {
+                threshold: 10,
+                new_total: 8,
+                direction: CrossingDirection::Down,
+            },
+        ]
+    );
+}
+
+#[test]
+fn add_member_with_hook_is_atomic() {
+    let mut app = App::default();
+    let group = instantiate_group(&mut app, group_instantiate_msg(vec![member(MEMBER1, 5)]));
+    let hook_id = app.store_code(mock_hook::contract());
+    let instantiate_hook = |app: &mut App, label: &str| {
+        app.instantiate_contract(hook_id, Addr::unchecked(OWNER), &Empty {}, &[], label, None)
+            .unwrap()
+    };
+    let module = instantiate_hook(&mut app, "module");
+    let other = instantiate_hook(&mut app, "other");
+
+    let owner = Addr::unchecked(OWNER);
+    let msg = ExecuteMsg::AddMemberWithHook {
+        member: member(module.as_str(), 3),
+        as_hook: true,
+    };
+    app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+        .unwrap();
+    let hooks: cw4::HooksResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &QueryMsg::Hooks {})
+        .unwrap();
+    assert_eq!(hooks.hooks, vec![module.to_string()]);
+    assert!(list_members(&app, &group).contains(&member(module.as_str(), 3)));
+
+    // without as_hook only the member is added
+    let msg = ExecuteMsg::AddMemberWithHook {
+        member: member(other.as_str(), 2),
+        as_hook: false,
+    };
+    app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+        .unwrap();
+    assert!(list_members(&app, &group).contains(&member(other.as_str(), 2)));
+
+    // a failing hook registration also reverts the membership change
+    let msg = ExecuteMsg::AddMemberWithHook {
+        member: member(module.as_str(), 7),
+        as_hook: true,
+    };
+    app.execute_contract(owner, group.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(list_members(&app, &group).contains(&member(module.as_str(), 3)));
+    let hooks: cw4::HooksResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &QueryMsg::Hooks {})
+        .unwrap();
+    assert_eq!(hooks.hooks, vec![module.to_string()]);
+}
+
+#[test]
+fn compare_to_group_with_partial_overlap() {
+    let mut app = App::default();
+    let members = |ids: &[u8]| {
+        ids.iter()
+            .map(|id| member(format!("member000{}", id), 1))
+            .collect()
+    };
+    let here = instantiate_group(&mut app, group_instantiate_msg(members(&[1, 2, 3, 5, 7])));
+    let there = instantiate_group(&mut app, group_instantiate_msg(members(&[2, 4, 5, 6])));
+    let compare = |start_after: Option<String>, limit| -> CompareToGroupResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &here,
+                &QueryMsg::CompareToGroup {
+                    other: there.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    };
+    let addrs =
+        |ids: &[u8]| -> Vec<String> { ids.iter().map(|id| format!("member000{}", id)).collect() };
+
+    // a single page covers both groups
+    let res = compare(None, None);
+    assert_eq!(res.only_here, addrs(&[1, 3, 7]));
+    assert_eq!(res.only_there, addrs(&[4, 6]));
+    assert_eq!(res.last, None);
+
+    // small pages add up to the same
+    let (mut only_here, mut only_there) = (vec![], vec![]);
+    let mut start_after = None;
+    let mut pages = 0;
+    loop {
+        let res = compare(start_after, Some(2));
+        only_here.extend(res.only_here);
+        only_there.extend(res.only_there);
+        pages += 1;
+        match res.last {
+            Some(last) => start_after = Some(last),
+            None => break,
+        }
+    }
+    assert!(pages > 1);
+    assert_eq!(only_here, addrs(&[1, 3, 7]));
+    assert_eq!(only_there, addrs(&[4, 6]));
+}
+
+#[test]
+fn filtered_hook_fires_only_for_relevant_changes() {
+    let mut app = App::default();
+    let group = instantiate_group(
+        &mut app,
+        group_instantiate_msg(vec![member(MEMBER1, 5), member(MEMBER2, 3)]),
+    );
+    let owner = Addr::unchecked(OWNER);
+    let hook_id = app.store_code(mock_hook::contract());
+    let all = app
+        .instantiate_contract(hook_id, owner.clone(), &Empty {}, &[], "all", None)
+        .unwrap();
+    let filtered = app
+        .instantiate_contract(hook_id, owner.clone(), &Empty {}, &[], "filtered", None)
+        .unwrap();
+
+    // one plain hook and one only interested in MEMBER2
+    let msgs = vec![
+        ExecuteMsg::AddHook {
+            addr: all.to_string(),
+            priority: None,
+        },
+        ExecuteMsg::AddHookFiltered {
+            addr: filtered.to_string(),
+            addrs_of_interest: vec![MEMBER2.into()],
+        },
+    ];
+    for msg in msgs {
+        app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+            .unwrap();
+    }
+    let update = |app: &mut App, add: Vec<Member>| {
+        let msg = ExecuteMsg::UpdateMembers {
+            add,
+            remove: vec![],
+            expected_total: None,
+        };
+        app.execute_contract(owner.clone(), group.clone(), &msg, &[])
+            .unwrap();
+    };
+    let changes = |app: &App, hook: &Addr| -> Vec<MemberChangedHookMsg> {
+        let raw = app
+            .wrap()
+            .query_wasm_raw(hook, mock_hook::CHANGES.as_slice())
+            .unwrap()
+            .unwrap();
+        from_slice(&raw).unwrap()
+    };
+
+    // a change to MEMBER1 only reaches the plain hook
+    update(&mut app, vec![member(MEMBER1, 6)]);
+    assert_eq!(changes(&app, &all).len(), 1);
+    assert_eq!(changes(&app, &filtered), vec![]);
+
+    // a batch touching MEMBER2 reaches both, with the full diff
+    update(&mut app, vec![member(OUTSIDER, 1), member(MEMBER2, 4)]);
+    let seen = changes(&app, &filtered);
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].diffs.len(), 2);
+    assert_eq!(changes(&app, &all).len(), 2);
+}
+
+#[test]
+fn hooks_info_lists_contract_versions() {
+    let mut app = App::default();
+    let group = instantiate_group(&mut app, group_instantiate_msg(vec![member(MEMBER1, 5)]));
+    let owner = Addr::unchecked(OWNER);
+    let hook_id = app.store_code(mock_hook::contract());
+    let first = app
+        .instantiate_contract(hook_id, owner.clone(), &Empty {}, &[], "first", None)
+        .unwrap();
+    let second = app
+        .instantiate_contract(hook_id, owner.clone(), &Empty {}, &[], "second", None)
+        .unwrap();
+    let token_id = app.store_code(mock_token::contract());
+    let token = app
+        .instantiate_contract(
+            token_id,
+            owner.clone(),
+            &mock_token::InstantiateMsg { balances: vec![] },
+            &[],
+            "token",
+            None,
+        )
+        .unwrap();
+
+    for hook in [first.as_str(), token.as_str(), OUTSIDER, second.as_str()] {
+        let add_hook = ExecuteMsg::AddHook {
+            addr: hook.into(),
+            priority: None,
+        };
+        app.execute_contract(owner.clone(), group.clone(), &add_hook, &[])
+            .unwrap();
+    }
+
+    let res: HooksInfoResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &QueryMsg::HooksInfo {})
+        .unwrap();
+    let versioned = |addr: &Addr| HookInfo {
+        addr: addr.to_string(),
+        name: Some("mock-hook".to_string()),
+        version: Some("0.1.0".to_string()),
+    };
+    let unversioned = |addr: &str| HookInfo {
+        addr: addr.to_string(),
+        name: None,
+        version: None,
+    };
+    assert_eq!(
+        res.hooks,
+        vec![
+            versioned(&first),
+            unversioned(token.as_str()),
+            unversioned(OUTSIDER),
+            versioned(&second),
+        ]
+    );
+}
+
+#[test]
+fn hooks_must_be_contracts_when_validated() {
+    let mut app = App::default();
+    let msg = InstantiateMsg {
+        validate_hook_contracts: true,
+        ..group_instantiate_msg(vec![member(MEMBER1, 5)])
+    };
+    let group = instantiate_group(&mut app, msg);
+    let owner = Addr::unchecked(OWNER);
+
+    let add_hook = ExecuteMsg::AddHook {
+        addr: OUTSIDER.into(),
+        priority: None,
+    };
+    let err = app
+        .execute_contract(owner.clone(), group.clone(), &add_hook, &[])
+        .unwrap_err();
+    assert_eq!(
+        crate::ContractError::HookNotAContract {
+            addr: OUTSIDER.into()
+        },
+        err.downcast().unwrap()
+    );
+
+    let hook_id = app.store_code(mock_hook::contract());
+    let hook = app
+        .instantiate_contract(hook_id, owner.clone(), &Empty {}, &[], "hook", None)
+        .unwrap();
+    let add_hook = ExecuteMsg::AddHook {
+        addr: hook.to_string(),
+        priority: None,
+    };
+    app.execute_contract(owner, group.clone(), &add_hook, &[])
+        .unwrap();
+    let hooks: cw4::HooksResponse = app
+        .wrap()
+        .query_wasm_smart(&group, &QueryMsg::Hooks {})
+        .unwrap();
+    assert_eq!(hooks.hooks, vec![hook.to_string()]);
+}
+
+#[test]
+fn init_callback_reports_total() {
+    let mut app = App::default();
+    let parent_id = app.store_code(mock_parent::contract());
+    let parent = app
+        .instantiate_contract(
+            parent_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "parent",
+            None,
+        )
+        .unwrap();
+
+    let msg = InstantiateMsg {
+        init_callback: Some(InitCallback {
+            contract: parent.to_string(),
+            msg: to_binary("proposal-42").unwrap(),
+        }),
+        ..group_instantiate_msg(vec![member(MEMBER1, 30), member(MEMBER2, 12)])
+    };
+    instantiate_group(&mut app, msg);
+
+    let notice: Option<GroupInstantiatedMsg> =
+        app.wrap().query_wasm_smart(&parent, &Empty {}).unwrap();
+    assert_eq!(
+        notice,
+        Some(GroupInstantiatedMsg {
+            msg: to_binary("proposal-42").unwrap(),
+            total_weight: 42,
+        })
+    );
+}
+
+#[test]
+fn merge_from_sums_overlapping_members() {
+    let mut app = App::default();
+    let target = instantiate_group(
+        &mut app,
+        group_instantiate_msg(vec![member(MEMBER1, 10), member(MEMBER2, 5)]),
+    );
+    let source = instantiate_group(
+        &mut app,
+        group_instantiate_msg(vec![
+            member(MEMBER2, 7),
+            member(OUTSIDER, 3),
+            member("member0003", 1),
+        ]),
+    );
+
+    // only the admin can merge
+    let merge = |start_after: Option<&str>| ExecuteMsg::MergeFrom {
+        source: source.to_string(),
+        start_after: start_after.map(String::from),
+        limit: Some(2),
+    };
+    app.execute_contract(Addr::unchecked(MEMBER1), target.clone(), &merge(None), &[])
+        .unwrap_err();
+
+    // two pages of two
+    let res = app
+        .execute_contract(Addr::unchecked(OWNER), target.clone(), &merge(None), &[])
+        .unwrap();
+    let wasm = res.events.iter().find(|e| e.ty == "wasm").unwrap();
+    let attr = |key: &str| {
+        wasm.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attr("has_more"), "true");
+    let last = attr("last");
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        target.clone(),
+        &merge(Some(&last)),
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        list_members(&app, &target),
+        vec![
+            member(MEMBER1, 10),
+            member(MEMBER2, 12),
+            member("member0003", 1),
+            member(OUTSIDER, 3),
+        ]
+    );
+}
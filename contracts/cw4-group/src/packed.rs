@@ -0,0 +1,62 @@
+//! Compact binary encoding of a member list, for imports too large to send as JSON.
+//!
+//! The data is a plain concatenation of entries, each laid out as
+//! `addr_len: u16 || addr || weight: u64 || identity_len: u16 || identity`,
+//! with all integers big endian and both strings UTF-8. There is no header
+//! or entry count; the data must end exactly after the last entry.
+
+use std::convert::{TryFrom, TryInto};
+
+use cosmwasm_std::{StdError, StdResult};
+use cw4::Member;
+
+pub fn encode(members: &[Member]) -> StdResult<Vec<u8>> {
+    let mut data = vec![];
+    for member in members {
+        put_str(&mut data, &member.addr)?;
+        data.extend_from_slice(&member.weight.to_be_bytes());
+        put_str(&mut data, &member.identity)?;
+    }
+    Ok(data)
+}
+
+pub fn decode(mut data: &[u8]) -> StdResult<Vec<Member>> {
+    let mut members = vec![];
+    while !data.is_empty() {
+        let addr = take_str(&mut data)?;
+        let weight = u64::from_be_bytes(take(&mut data, 8)?.try_into().unwrap());
+        let identity = take_str(&mut data)?;
+        members.push(Member {
+            addr,
+            weight,
+            identity,
+        });
+    }
+    Ok(members)
+}
+
+fn put_str(data: &mut Vec<u8>, s: &str) -> StdResult<()> {
+    let len = u16::try_from(s.len())
+        .map_err(|_| StdError::generic_err(format!("{} is too long to pack", s)))?;
+    data.extend_from_slice(&len.to_be_bytes());
+    data.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> StdResult<&'a [u8]> {
+    if data.len() < len {
+        return Err(StdError::parse_err(
+            "packed members",
+            "unexpected end of data",
+        ));
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head)
+}
+
+fn take_str(data: &mut &[u8]) -> StdResult<String> {
+    let len = u16::from_be_bytes(take(data, 2)?.try_into().unwrap());
+    let bytes = take(data, len as usize)?;
+    String::from_utf8(bytes.to_vec()).map_err(|e| StdError::parse_err("packed members", e))
+}
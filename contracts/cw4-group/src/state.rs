@@ -1,17 +1,171 @@
-use cosmwasm_std::Addr;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Decimal, Timestamp};
 use cw4::{
-    MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY, TOTAL_KEY, TOTAL_KEY_CHANGELOG,
-    TOTAL_KEY_CHECKPOINTS,
+    MemberDiff, MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY, TOTAL_KEY,
+    TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS,
 };
 use cw_controllers::{Admin, Hooks};
 use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use cw_utils::Expiration;
 
 pub const ADMIN: Admin = Admin::new("admin");
 pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
-pub const MAX_WEIGHT: Item<u64> = Item::new("max-weight");
-pub const MIN_WEIGHT: Item<u64> = Item::new("min-weight");
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const STATS: Item<Stats> = Item::new("stats");
+pub const LAST_CHANGE_HEIGHT: Item<u64> = Item::new("last-change-height");
+pub const LAST_CHANGE_TIME: Item<Timestamp> = Item::new("last-change-time");
+/// Diffs of the most recent `UpdateMembers` batch and the height it ran at
+pub const LAST_BATCH_DIFF: Item<Vec<MemberDiff>> = Item::new("last-batch-diff");
+pub const LAST_BATCH_HEIGHT: Item<u64> = Item::new("last-batch-height");
 const IDS_KEY: &str = "member-ids";
 
+/// Bounds stored before they were folded into `CONFIG`, only read by `migrate`
+pub const LEGACY_MAX_WEIGHT: Item<u64> = Item::new("max-weight");
+pub const LEGACY_MIN_WEIGHT: Item<u64> = Item::new("min-weight");
+
+/// Tunable parameters of the group
+#[cw_serde]
+pub struct Config {
+    /// The minimum total member weight
+    pub min_weight: u64,
+    /// The maximum total member weight
+    pub max_weight: u64,
+    /// Informational label for what weights represent
+    pub weight_unit: Option<String>,
+    /// When set, removals are only applied this many blocks after being requested
+    pub removal_grace_blocks: Option<u64>,
+    /// Contract whose balances member weights can be synced from
+    pub weight_source: Option<Addr>,
+    /// Safety floor on the total weight below which removals are rejected.
+    /// Set by the admin, eg. while proposals are open
+    pub min_active_total: Option<u64>,
+    /// Reject removals of addresses that are not members instead of ignoring them
+    pub strict_removals: bool,
+    /// Reject members with an empty identity
+    pub require_identity: bool,
+    /// Total weight at which hooks are told about quorum crossings
+    pub quorum_watch: Option<u64>,
+    /// Only accept hooks that are deployed contracts
+    pub validate_hook_contracts: bool,
+    /// Lets members replace the admin by a weighted vote, if set
+    pub emergency_recovery: Option<EmergencyRecovery>,
+    /// After this the group rejects all changes and can be dissolved by anyone
+    pub expires_at: Option<Expiration>,
+    /// Scale all weights to sum to `rebalance_target` after every `UpdateMembers`
+    pub auto_rebalance: bool,
+    pub rebalance_target: u64,
+    /// Member receiving the rounding remainder of a rebalance, the heaviest if unset
+    pub rebalance_remainder_to: Option<Addr>,
+    /// Whether admin messages must be wrapped in `WithNonce`
+    pub require_admin_nonce: bool,
+    /// Whether members may remove themselves with `Leave`
+    pub allow_self_leave: bool,
+    /// Whether `UpdateMembers` adds a `member_update` event with a serialized summary
+    pub emit_payload_event: bool,
+    /// Smallest share of the total weight a member may be added or updated with
+    pub min_member_percent: Option<Decimal>,
+    /// Largest change to the total, or to any one member, a single operation may make
+    pub max_weight_delta_per_op: Option<u64>,
+    /// Share of every weight `DecayWeights` takes away per interval
+    pub decay: Option<WeightDecay>,
+    /// Blocks a proposed admin must wait before `AcceptAdmin`. While set, the admin
+    /// can only be handed over through `ProposeAdmin`
+    pub admin_change_notice_blocks: Option<u64>,
+}
+
+#[cw_serde]
+pub struct EmergencyRecovery {
+    /// Share of the total weight that must be exceeded to appoint a new admin
+    pub supermajority: Decimal,
+    /// Blocks a proposal stays open for voting
+    pub voting_blocks: u64,
+}
+
+/// Kinds of operation `SetPausedOps` can pause
+#[cw_serde]
+pub enum OpKind {
+    /// Adding a member
+    Add,
+    /// Removing a member, or scheduling its removal
+    Remove,
+    /// Changing the weight of a member
+    Reweight,
+    /// Handing the admin role, or a share of it, to someone else
+    AdminChange,
+    /// Adding or removing hooks
+    HookChange,
+}
+
+#[cw_serde]
+pub struct WeightDecay {
+    /// Share of each weight lost per interval, rounding down
+    pub percent: Decimal,
+    /// Blocks per interval
+    pub interval: u64,
+}
+
+/// Linear ramp of a member's weight between two heights
+#[cw_serde]
+pub struct VestingSchedule {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub start_weight: u64,
+    pub end_weight: u64,
+}
+
+impl VestingSchedule {
+    /// The interpolated weight at `height`, rounded towards `start_weight`
+    pub fn weight_at(&self, height: u64) -> u64 {
+        if height <= self.start_height {
+            return self.start_weight;
+        }
+        if height >= self.end_height {
+            return self.end_weight;
+        }
+        let elapsed = (height - self.start_height) as u128;
+        let duration = (self.end_height - self.start_height) as u128;
+        if self.end_weight >= self.start_weight {
+            let gain = (self.end_weight - self.start_weight) as u128 * elapsed / duration;
+            self.start_weight + gain as u64
+        } else {
+            let loss = (self.start_weight - self.end_weight) as u128 * elapsed / duration;
+            self.start_weight - loss as u64
+        }
+    }
+}
+
+#[cw_serde]
+pub struct EmergencyProposal {
+    pub addr: Addr,
+    /// Height from which the proposal can no longer be voted on
+    pub expires_at: u64,
+}
+
+/// Activity counters kept over the lifetime of the contract
+#[cw_serde]
+#[derive(Default)]
+pub struct Stats {
+    /// Number of times an address became a member
+    pub total_adds: u64,
+    /// Number of times a member was removed
+    pub total_removals: u64,
+    /// Sum of all weight increases, including new members
+    pub total_weight_added: u64,
+    /// Number of admin updates
+    pub admin_changes: u64,
+}
+
+/// Human-readable description of what the group represents
+#[cw_serde]
+#[derive(Default)]
+pub struct GroupInfo {
+    pub name: String,
+    pub description: String,
+    pub url: Option<String>,
+}
+
+pub const GROUP_INFO: Item<GroupInfo> = Item::new("group-info");
+
 pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
     TOTAL_KEY,
     TOTAL_KEY_CHECKPOINTS,
@@ -26,4 +180,128 @@ pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
     Strategy::EveryBlock,
 );
 
-pub const IDS: Map<&Addr, String> = Map::new(IDS_KEY);
+/// Identity of each member, as an id into `IDENTITY_POOL`
+pub const IDS: Map<&Addr, u64> = Map::new(IDS_KEY);
+/// `IDS` as stored before identities were interned, only used by `migrate`
+pub const LEGACY_IDS: Map<&Addr, String> = Map::new(IDS_KEY);
+
+/// Interned identity strings, so members sharing an identity store it once.
+/// Entries are never removed, identities tend to be reused
+pub const IDENTITY_POOL: Map<u64, String> = Map::new("identity-pool");
+/// Reverse lookup of `IDENTITY_POOL`
+pub const IDENTITY_POOL_IDS: Map<&str, u64> = Map::new("identity-pool-ids");
+/// Number of entries in `IDENTITY_POOL`, which is also the next id
+pub const IDENTITY_POOL_SIZE: Item<u64> = Item::new("identity-pool-size");
+
+/// Current members keyed by identity, for listing them in identity order.
+/// Identities are not unique, so each entry holds the sorted addresses sharing it.
+pub const IDENTITY_INDEX: Map<&str, Vec<Addr>> = Map::new("identity-index");
+
+/// Members carrying a tag, for listing by tag
+pub const TAGS: Map<(&str, &Addr), ()> = Map::new("tags");
+/// Tags carried by each member, so they can be cleared on removal
+pub const MEMBER_TAGS: Map<&Addr, Vec<String>> = Map::new("member-tags");
+
+/// Weight a member delegates to another, keyed by (delegator, delegate)
+pub const PARTIAL_DELEGATIONS: Map<(&Addr, &Addr), u64> = Map::new("partial-delegations");
+/// The same delegations keyed by (delegate, delegator), to look up incoming weight
+pub const INCOMING_DELEGATIONS: Map<(&Addr, &Addr), u64> = Map::new("incoming-delegations");
+
+/// Merkle root of the member set, keyed by the height at which it changed
+pub const ROOTS: Map<u64, Binary> = Map::new("roots");
+
+/// Members scheduled for removal, keyed to the height at which they may be removed
+pub const PENDING_REMOVALS: Map<&Addr, u64> = Map::new("pending-removals");
+
+/// Optional per-hook filter. A hook with a non-empty filter is only called
+/// when one of the listed addresses is part of the diff.
+pub const HOOK_FILTERS: Map<&Addr, Vec<Addr>> = Map::new("hook-filters");
+
+/// Optional per-hook priority. Hooks with a higher priority are called first,
+/// hooks without one count as zero
+pub const HOOK_PRIORITY: Map<&Addr, i32> = Map::new("hook-priority");
+
+/// Height the contract was instantiated at. Unset for groups migrated from before it was recorded
+pub const INSTANTIATED_AT: Item<u64> = Item::new("instantiated-at");
+/// Sender of the instantiation. Unset for groups migrated from before it was recorded
+pub const CREATOR: Item<Addr> = Item::new("creator");
+
+/// Height each current member joined at. Members from before this was tracked have none
+pub const JOINED_AT: Map<&Addr, u64> = Map::new("joined-at");
+/// Index of `JOINED_AT` by (join height, address)
+pub const MEMBERS_BY_JOIN: Map<(u64, &Addr), ()> = Map::new("members-by-join");
+
+/// Members whose weight is frozen. They keep their seat but count as inactive
+pub const FROZEN_MEMBERS: Map<&Addr, ()> = Map::new("frozen-members");
+
+/// Members whose seat cannot be changed or removed until unlocked
+pub const LOCKED_MEMBERS: Map<&Addr, ()> = Map::new("locked-members");
+/// Secondary addresses answering member queries on behalf of their canonical member.
+/// Aliases carry no weight of their own and always point directly at a canonical address
+pub const ALIASES: Map<&Addr, Addr> = Map::new("aliases");
+/// Expiration of a member's seat. Expired members count as inactive until removed
+pub const EXPIRY: Map<&Addr, Expiration> = Map::new("expiry");
+
+/// Nonce the next `WithNonce` message must carry, starting at 0
+pub const ADMIN_NONCE: Item<u64> = Item::new("admin-nonce");
+
+/// Address proposed with `ProposeAdmin` and the height it was proposed at
+pub const PENDING_ADMIN: Item<(Addr, u64)> = Item::new("pending-admin");
+
+/// Address sharing the admin's powers until the expiration. Lapsed entries are
+/// cleared at the start of every execution
+pub const TEMP_ADMIN: Item<(Addr, Expiration)> = Item::new("temp-admin");
+
+/// Height at which the group was dissolved. Once set, no further changes are accepted
+pub const DISSOLVED_AT: Item<u64> = Item::new("dissolved-at");
+
+/// Compressed or uncompressed secp256k1 public key allowed to sign attestations
+pub const ATTESTER_PUBKEY: Item<Binary> = Item::new("attester-pubkey");
+/// Nonce the next attestation must carry, starting at 0
+pub const ATTESTATION_NONCE: Item<u64> = Item::new("attestation-nonce");
+
+/// Weight still available to `AllocateFromBudget`
+pub const BUDGET: Item<u64> = Item::new("budget");
+
+/// Height the last applied decay interval ended at
+pub const LAST_DECAY_HEIGHT: Item<u64> = Item::new("last-decay-height");
+
+/// Named heights to compare the member set against. As with all snapshots, a
+/// checkpoint reflects the set as of the start of the block it was saved in
+pub const CHECKPOINTS: Map<&str, u64> = Map::new("checkpoints");
+
+/// Once true, the contract accepts no further messages. Unlike dissolving, the
+/// member set is kept and stays queryable
+pub const SEALED: Item<bool> = Item::new("sealed");
+
+/// While true, the member set cannot be changed
+pub const FROZEN: Item<bool> = Item::new("frozen");
+
+/// Operations rejected until lifted with `SetPausedOps`
+pub const PAUSED_OPS: Item<Vec<OpKind>> = Item::new("paused-ops");
+
+/// Block times from `start` inclusive to `end` exclusive during which every message
+/// but those managing the window is rejected
+pub const FREEZE_WINDOW: Item<(Timestamp, Timestamp)> = Item::new("freeze-window");
+
+/// Reference of each member in an off-chain registry
+pub const EXTERNAL_IDS: Map<&Addr, String> = Map::new("external-ids");
+/// Reverse lookup of `EXTERNAL_IDS`, which keeps external ids unique
+pub const EXTERNAL_ID_OWNERS: Map<&str, Addr> = Map::new("external-id-owners");
+
+/// Number of current members, so it can be read without iterating
+pub const MEMBER_COUNT: Item<u64> = Item::new("member-count");
+
+/// The open emergency admin proposal, if any
+pub const EMERGENCY_PROPOSAL: Item<EmergencyProposal> = Item::new("emergency-proposal");
+/// Members who voted for the open emergency admin proposal
+pub const EMERGENCY_VOTES: Map<&Addr, ()> = Map::new("emergency-votes");
+
+/// Current members keyed by (weight, address), for walking them in weight order
+pub const MEMBERS_BY_WEIGHT: Map<(u64, &Addr), ()> = Map::new("members-by-weight");
+
+/// Accounts allowed to run maintenance, such as pruning expired members, besides the admin
+pub const OPERATORS: Map<&Addr, ()> = Map::new("operators");
+
+/// Vesting schedules of members, applied to their weight by `CrankVesting`
+pub const VESTING: Map<&Addr, VestingSchedule> = Map::new("vesting");
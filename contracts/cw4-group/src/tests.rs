@@ -1,21 +1,54 @@
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_slice, Addr, Api, DepsMut, OwnedDeps, Querier, Storage, SubMsg};
-use cw4::{member_key, Member, MemberChangedHookMsg, MemberDiff, TOTAL_KEY};
+use cosmwasm_std::{
+    attr, from_slice, Addr, Api, Binary, CosmosMsg, Decimal, DepsMut, OwnedDeps, Querier, StdError,
+    Storage, SubMsg, Timestamp, WasmMsg,
+};
+use cw4::{member_key, Member, MemberChangedHookMsg, MemberDiff, MemberResponse, TOTAL_KEY};
 use cw_controllers::{AdminError, HookError};
+use cw_utils::Expiration;
 
 use crate::contract::{
-    execute, instantiate, query_list_members, query_member, query_total_weight, update_members,
+    execute, instantiate, migrate, query, query_average_join_height, query_can_add,
+    query_changed_members, query_churn, query_config, query_cumulative_weight, query_delegations,
+    query_diff_from_checkpoint, query_effective_threshold, query_effective_weight,
+    query_emergency_proposal, query_headroom_report, query_health, query_inactive_weight,
+    query_intersect_members, query_is_majority, query_last_change, query_list_by_identity,
+    query_list_by_join_height, query_list_by_tag, query_list_members, query_list_shares,
+    query_member, query_member_by_external_id, query_member_history, query_member_rank,
+    query_member_snapshot_bounds, query_members_to_reach, query_members_with_weight,
+    query_minimal_coalition, query_nakamoto_coefficient, query_next_member, query_overview,
+    query_pending_removals, query_quantiles, query_resolved_weight,
+    query_search_by_identity_prefix, query_state_hash, query_storage_stats, query_tag_weight,
+    query_total_weight, query_total_weight_excluding, query_verify_proof, query_weight_by_tag,
+    query_weight_for_share, query_weight_percentile, query_weight_to_enter_top, update_members,
+};
+use crate::error::ContractError;
+use crate::merkle;
+use crate::msg::{
+    AdminChangeReadyAtResponse, AdminMemberResponse, AdminNonceResponse, ChurnResponse,
+    ConfigResponse, CumulativeWeightResponse, Delegation, DissolvedResponse, ExecuteMsg,
+    ExpirationResponse, FreezeWindowResponse, FrozenResponse, HeadroomReportResponse,
+    HealthResponse, InstantiateMsg, IsLockedResponse, IsMajorityResponse, LastBatchDiffResponse,
+    MemberRankResponse, MemberUpdateSummary, MergeStrategy, MigrateMsg,
+    NakamotoCoefficientResponse, NextScheduledResponse, OperatorsResponse, PausedOpsResponse,
+    PendingRemoval, PendingRemovalsResponse, ProvenanceResponse, QueryMsg, RemainingBudgetResponse,
+    ResolvedWeightResponse, SealedResponse, TempAdminResponse, VestedWeightResponse, WeightChange,
+};
+use crate::packed;
+use crate::state::{
+    Config, EmergencyRecovery, GroupInfo, OpKind, Stats, VestingSchedule, WeightDecay, ADMIN,
+    ALIASES, CONFIG, EXPIRY, FROZEN_MEMBERS, HOOKS, HOOK_FILTERS, HOOK_PRIORITY, IDENTITY_POOL,
+    IDENTITY_POOL_SIZE, IDS, INCOMING_DELEGATIONS, JOINED_AT, LAST_CHANGE_HEIGHT, LAST_CHANGE_TIME,
+    LEGACY_IDS, LEGACY_MAX_WEIGHT, LEGACY_MIN_WEIGHT, STATS, VESTING,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::state::{ADMIN, HOOKS};
 
 const INIT_ADMIN: &str = "juan";
 const USER1: &str = "somebody";
 const USER2: &str = "else";
 const USER3: &str = "funny";
 
-fn do_instantiate(deps: DepsMut) {
-    let msg = InstantiateMsg {
+fn default_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
         admin: Some(INIT_ADMIN.into()),
         members: vec![
             Member {
@@ -31,11 +64,56 @@ fn do_instantiate(deps: DepsMut) {
         ],
         min_weight: 0,
         max_weight: 100,
-    };
+        weight_unit: Some("KUJI".to_string()),
+        removal_grace_blocks: None,
+        weight_source: None,
+        strict_removals: false,
+        require_identity: false,
+        validate_hook_contracts: false,
+        emergency_recovery: None,
+        frozen: None,
+        init_callback: None,
+        expires_at: None,
+        auto_rebalance: false,
+        rebalance_target: 0,
+        rebalance_remainder_to: None,
+        require_admin_nonce: false,
+        allow_self_leave: false,
+        emit_payload_event: false,
+    }
+}
+
+fn do_instantiate(deps: DepsMut) {
+    instantiate_with(deps, default_instantiate_msg());
+}
+
+fn instantiate_with(deps: DepsMut, msg: InstantiateMsg) {
     let info = mock_info("creator", &[]);
     instantiate(deps, mock_env(), info, msg).unwrap();
 }
 
+#[test]
+fn provenance_records_instantiation() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    env.block.height = 4321;
+    instantiate(
+        deps.as_mut(),
+        env,
+        mock_info("deployer", &[]),
+        default_instantiate_msg(),
+    )
+    .unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Provenance {}).unwrap();
+    assert_eq!(
+        from_slice::<ProvenanceResponse>(&res).unwrap(),
+        ProvenanceResponse {
+            instantiated_at: Some(4321),
+            creator: Some("deployer".into()),
+        }
+    );
+}
+
 #[test]
 fn proper_instantiation() {
     let mut deps = mock_dependencies();
@@ -49,6 +127,195 @@ fn proper_instantiation() {
     assert_eq!(17, res.weight);
 }
 
+#[test]
+fn config_round_trips() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            min_weight: 0,
+            max_weight: 100,
+            weight_unit: Some("KUJI".to_string()),
+            removal_grace_blocks: None,
+            weight_source: None,
+            min_active_total: None,
+            strict_removals: false,
+            require_identity: false,
+            quorum_watch: None,
+            validate_hook_contracts: false,
+            emergency_recovery: None,
+            expires_at: None,
+            auto_rebalance: false,
+            rebalance_target: 0,
+            rebalance_remainder_to: None,
+            require_admin_nonce: false,
+            allow_self_leave: false,
+            emit_payload_event: false,
+            min_member_percent: None,
+            max_weight_delta_per_op: None,
+            decay: None,
+            admin_change_notice_blocks: None,
+            default_limit: 10,
+            max_limit: 30,
+        }
+    );
+
+    // all tunables live in the single config item
+    let stored = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(
+        stored,
+        Config {
+            min_weight: 0,
+            max_weight: 100,
+            weight_unit: Some("KUJI".to_string()),
+            removal_grace_blocks: None,
+            weight_source: None,
+            min_active_total: None,
+            strict_removals: false,
+            require_identity: false,
+            quorum_watch: None,
+            validate_hook_contracts: false,
+            emergency_recovery: None,
+            expires_at: None,
+            auto_rebalance: false,
+            rebalance_target: 0,
+            rebalance_remainder_to: None,
+            require_admin_nonce: false,
+            allow_self_leave: false,
+            emit_payload_event: false,
+            min_member_percent: None,
+            max_weight_delta_per_op: None,
+            decay: None,
+            admin_change_notice_blocks: None,
+        }
+    );
+}
+
+#[test]
+fn migrate_folds_legacy_bounds_into_config() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // pretend this was stored by a version without CONFIG
+    CONFIG.remove(&mut deps.storage);
+    LEGACY_MIN_WEIGHT.save(&mut deps.storage, &5).unwrap();
+    LEGACY_MAX_WEIGHT.save(&mut deps.storage, &50).unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(config.min_weight, 5);
+    assert_eq!(config.max_weight, 50);
+    assert_eq!(config.weight_unit, None);
+    assert_eq!(LEGACY_MAX_WEIGHT.may_load(&deps.storage).unwrap(), None);
+}
+
+#[test]
+fn admin_changes_after_migrating_without_stats() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // pretend this was stored by a version without STATS
+    STATS.remove(&mut deps.storage);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let msg = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER1.into()),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(STATS.load(&deps.storage).unwrap().admin_changes, 1);
+}
+
+#[test]
+fn shared_identities_are_interned_once() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 11,
+                identity: "kujira:senate".to_string(),
+            },
+            Member {
+                addr: USER2.into(),
+                weight: 6,
+                identity: "kujira:senate".to_string(),
+            },
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let user1 = IDS.load(&deps.storage, &Addr::unchecked(USER1)).unwrap();
+    let user2 = IDS.load(&deps.storage, &Addr::unchecked(USER2)).unwrap();
+    assert_eq!(user1, user2);
+    assert_eq!(IDENTITY_POOL_SIZE.load(&deps.storage).unwrap(), 1);
+    assert_eq!(
+        IDENTITY_POOL.load(&deps.storage, user1).unwrap(),
+        "kujira:senate"
+    );
+
+    // queries resolve the pooled string
+    let member = query_member(deps.as_ref(), USER2.into(), None).unwrap();
+    assert_eq!(member.identity, Some("kujira:senate".to_string()));
+
+    // a new identity gets a new entry, a known one does not
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![
+            Member {
+                addr: USER3.into(),
+                weight: 1,
+                identity: "osmo:senate".to_string(),
+            },
+            Member {
+                addr: USER1.into(),
+                weight: 11,
+                identity: "osmo:senate".to_string(),
+            },
+        ],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(IDENTITY_POOL_SIZE.load(&deps.storage).unwrap(), 2);
+    let member = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(member.identity, Some("osmo:senate".to_string()));
+}
+
+#[test]
+fn migrate_interns_plain_identities() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // pretend these were stored before identities were interned
+    for (addr, identity) in [(USER1, "legacy"), (USER2, "legacy")] {
+        LEGACY_IDS
+            .save(
+                &mut deps.storage,
+                &Addr::unchecked(addr),
+                &identity.to_string(),
+            )
+            .unwrap();
+    }
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let member1 = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(member1.identity, Some("legacy".to_string()));
+    let member2 = query_member(deps.as_ref(), USER2.into(), None).unwrap();
+    assert_eq!(member2.identity, Some("legacy".to_string()));
+    assert_eq!(
+        IDS.load(&deps.storage, &Addr::unchecked(USER1)).unwrap(),
+        IDS.load(&deps.storage, &Addr::unchecked(USER2)).unwrap()
+    );
+
+    // migrating again leaves interned identities alone
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let member1 = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(member1.identity, Some("legacy".to_string()));
+}
+
 #[test]
 fn try_member_queries() {
     let mut deps = mock_dependencies();
@@ -87,7 +354,7 @@ fn assert_users<S: Storage, A: Api, Q: Querier>(
     // this is only valid if we are not doing a historical query
     if height.is_none() {
         // compute expected metrics
-        let weights = vec![user1_weight, user2_weight, user3_weight];
+        let weights = [user1_weight, user2_weight, user3_weight];
         let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
         let count = weights.iter().filter(|x| x.is_some()).count();
 
@@ -224,6 +491,7 @@ fn add_remove_hooks() {
 
     let add_msg = ExecuteMsg::AddHook {
         addr: contract1.clone(),
+        priority: None,
     };
 
     // non-admin cannot add hook
@@ -259,6 +527,7 @@ fn add_remove_hooks() {
     // add second contract
     let add_msg2 = ExecuteMsg::AddHook {
         addr: contract2.clone(),
+        priority: None,
     };
     let _ = execute(deps.as_mut(), mock_env(), admin_info.clone(), add_msg2).unwrap();
     let hooks = HOOKS.query_hooks(deps.as_ref()).unwrap();
@@ -279,6 +548,57 @@ fn add_remove_hooks() {
     assert_eq!(hooks.hooks, vec![contract2]);
 }
 
+#[test]
+fn set_hooks_replaces_registry() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+
+    for (addr, priority) in [("hook1", Some(5)), ("hook2", None)] {
+        let add = ExecuteMsg::AddHook {
+            addr: addr.into(),
+            priority,
+        };
+        execute(deps.as_mut(), mock_env(), admin_info.clone(), add).unwrap();
+    }
+
+    let set = ExecuteMsg::SetHooks {
+        addrs: vec![
+            "hook2".into(),
+            "hook3".into(),
+            "hook4".into(),
+            "hook3".into(),
+        ],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, HookError::Admin(AdminError::NotAdmin {}).into());
+
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), set).unwrap();
+    assert_eq!(
+        res.attributes[1..3],
+        [attr("added", "2"), attr("removed", "1")]
+    );
+    let hooks = HOOKS.query_hooks(deps.as_ref()).unwrap();
+    assert_eq!(hooks.hooks, vec!["hook2", "hook3", "hook4"]);
+    // nothing of the dropped hook is left behind
+    assert!(!HOOK_PRIORITY.has(&deps.storage, &Addr::unchecked("hook1")));
+
+    // an empty list clears the registry
+    let clear = ExecuteMsg::SetHooks { addrs: vec![] };
+    let res = execute(deps.as_mut(), mock_env(), admin_info, clear).unwrap();
+    assert_eq!(
+        res.attributes[1..3],
+        [attr("added", "0"), attr("removed", "3")]
+    );
+    assert!(HOOKS.query_hooks(deps.as_ref()).unwrap().hooks.is_empty());
+}
+
 #[test]
 fn hooks_fire() {
     let mut deps = mock_dependencies();
@@ -294,11 +614,13 @@ fn hooks_fire() {
     let admin_info = mock_info(INIT_ADMIN, &[]);
     let add_msg = ExecuteMsg::AddHook {
         addr: contract1.clone(),
+        priority: None,
     };
     let add_msg2 = ExecuteMsg::AddHook {
         addr: contract2.clone(),
+        priority: None,
     };
-    for msg in vec![add_msg, add_msg2] {
+    for msg in [add_msg, add_msg2] {
         let _ = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
     }
 
@@ -317,7 +639,11 @@ fn hooks_fire() {
         },
     ];
     let remove = vec![USER2.into()];
-    let msg = ExecuteMsg::UpdateMembers { remove, add };
+    let msg = ExecuteMsg::UpdateMembers {
+        remove,
+        add,
+        expected_total: None,
+    };
 
     // admin updates properly
     assert_users(&deps, Some(11), Some(6), None, None);
@@ -338,6 +664,43 @@ fn hooks_fire() {
     assert_eq!(res.messages, vec![msg1, msg2]);
 }
 
+#[test]
+fn remove_hook_drops_filter_only_for_admin() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let filtered = String::from("hook_filtered");
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let msg = ExecuteMsg::AddHookFiltered {
+        addr: filtered.clone(),
+        addrs_of_interest: vec![USER3.into()],
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    let filter = |deps: &OwnedDeps<_, _, _>| {
+        HOOK_FILTERS
+            .may_load(&deps.storage, &Addr::unchecked(&filtered))
+            .unwrap()
+    };
+    assert_eq!(filter(&deps), Some(vec![Addr::unchecked(USER3)]));
+
+    // the filter survives a rejected removal
+    let msg = ExecuteMsg::RemoveHook {
+        addr: filtered.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, HookError::Admin(AdminError::NotAdmin {}).into());
+    assert_eq!(filter(&deps), Some(vec![Addr::unchecked(USER3)]));
+
+    // removing the hook also drops its filter
+    execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+    assert_eq!(filter(&deps), None);
+}
+
 #[test]
 fn raw_queries_work() {
     // add will over-write and remove have no effect
@@ -376,3 +739,6181 @@ fn total_at_height() {
     let total = query_total_weight(deps.as_ref(), Some(height + 1)).unwrap();
     assert_eq!(17, total.weight);
 }
+
+#[test]
+fn last_change_tracks_block_time() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let env = mock_env();
+    let res = query_last_change(deps.as_ref()).unwrap();
+    assert_eq!(res.height, Some(env.block.height));
+    assert_eq!(res.time, Some(env.block.time));
+
+    // a group migrated from a version without the record has nothing to report yet
+    LAST_CHANGE_HEIGHT.remove(&mut deps.storage);
+    LAST_CHANGE_TIME.remove(&mut deps.storage);
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let res = query_last_change(deps.as_ref()).unwrap();
+    assert_eq!((res.height, res.time), (None, None));
+
+    // update members two hours later
+    let mut later = mock_env();
+    later.block.height += 1200;
+    later.block.time = later.block.time.plus_seconds(7200);
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        later.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let res = query_last_change(deps.as_ref()).unwrap();
+    assert_eq!(res.height, Some(later.block.height));
+    assert_eq!(res.time, Some(later.block.time));
+}
+
+#[test]
+fn removals_wait_for_grace_period() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        removal_grace_blocks: Some(10),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let mut env = mock_env();
+    let height = env.block.height;
+
+    // removal is only scheduled, USER1 keeps its weight
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER1.into(), USER3.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert_users(&deps, Some(11), Some(6), None, None);
+    let pending = query_pending_removals(deps.as_ref()).unwrap();
+    assert_eq!(
+        pending,
+        PendingRemovalsResponse {
+            removals: vec![PendingRemoval {
+                addr: USER1.into(),
+                remove_at: height + 10,
+            }]
+        }
+    );
+
+    // finalizing before the grace period is over does nothing
+    env.block.height = height + 9;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(USER3, &[]),
+        ExecuteMsg::FinalizeRemovals {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1].value, "0");
+    assert_users(&deps, Some(11), Some(6), None, None);
+
+    // anyone can finalize once it is due
+    env.block.height = height + 10;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(USER3, &[]),
+        ExecuteMsg::FinalizeRemovals {},
+    )
+    .unwrap();
+    assert_users(&deps, None, Some(6), None, None);
+    let pending = query_pending_removals(deps.as_ref()).unwrap();
+    assert!(pending.removals.is_empty());
+}
+
+#[test]
+fn finalize_without_removals_ignores_min_active_total() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let msg = ExecuteMsg::SetMinActiveTotal { min: Some(100) };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+    // nothing is due, so the floor the group already sits under is not checked
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER3, &[]),
+        ExecuteMsg::FinalizeRemovals {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1].value, "0");
+}
+
+#[test]
+fn admin_can_cancel_pending_removal() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        removal_grace_blocks: Some(10),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+
+    // only the admin may cancel
+    let cancel = ExecuteMsg::CancelRemoval { addr: USER2.into() };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        cancel.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        cancel.clone(),
+    )
+    .unwrap();
+
+    // nothing left to cancel or finalize
+    let err = execute(deps.as_mut(), mock_env(), admin_info, cancel).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingRemoval { addr: USER2.into() });
+    let mut env = mock_env();
+    env.block.height += 100;
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(USER1, &[]),
+        ExecuteMsg::FinalizeRemovals {},
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), None, None);
+}
+
+#[test]
+fn locked_members_survive_maintenance() {
+    let start = mock_env().block.height;
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let lock = |addr: &str| ExecuteMsg::LockMember { addr: addr.into() };
+
+    // scaling refuses to touch a locked seat
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    execute(deps.as_mut(), at(start), admin_info.clone(), lock(USER1)).unwrap();
+    let msg = ExecuteMsg::ScaleWeights {
+        numerator: 1,
+        denominator: 2,
+    };
+    let err = execute(deps.as_mut(), at(start), admin_info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::MemberLocked { addr: USER1.into() });
+
+    // a removal scheduled before the lock waits for the unlock
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        removal_grace_blocks: Some(5),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let remove = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), at(start), admin_info.clone(), remove).unwrap();
+    execute(deps.as_mut(), at(start), admin_info.clone(), lock(USER2)).unwrap();
+    let finalize = ExecuteMsg::FinalizeRemovals {};
+    let anyone = mock_info(USER3, &[]);
+    execute(
+        deps.as_mut(),
+        at(start + 5),
+        anyone.clone(),
+        finalize.clone(),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), None, None);
+    let unlock = ExecuteMsg::UnlockMember { addr: USER2.into() };
+    execute(deps.as_mut(), at(start + 5), admin_info.clone(), unlock).unwrap();
+    execute(deps.as_mut(), at(start + 5), anyone, finalize).unwrap();
+    assert_users(&deps, Some(11), None, None, None);
+
+    // expired but locked members are not pruned
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let expire = |addr: &str| ExecuteMsg::SetMemberExpiry {
+        addr: addr.into(),
+        expires: Some(Expiration::AtHeight(start + 1)),
+    };
+    for msg in [expire(USER1), expire(USER2), lock(USER1)] {
+        execute(deps.as_mut(), at(start), admin_info.clone(), msg).unwrap();
+    }
+    let prune = ExecuteMsg::PruneExpired {};
+    execute(deps.as_mut(), at(start + 2), admin_info, prune).unwrap();
+    assert_users(&deps, Some(11), None, None, None);
+}
+
+#[test]
+fn scale_weights_rounds_down() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // only the admin can scale
+    let msg = ExecuteMsg::ScaleWeights {
+        numerator: 1,
+        denominator: 2,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let zero = ExecuteMsg::ScaleWeights {
+        numerator: 1,
+        denominator: 0,
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), zero).unwrap_err();
+    assert_eq!(err, ContractError::ZeroDenominator {});
+
+    // 11 / 2 = 5.5 and 6 / 2 = 3, rounded down
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    assert_users(&deps, Some(5), Some(3), None, None);
+    let total = query_total_weight(deps.as_ref(), None).unwrap();
+    assert_eq!(total.weight, 8);
+
+    // overflowing a member weight is rejected
+    let huge = ExecuteMsg::ScaleWeights {
+        numerator: u64::MAX,
+        denominator: 1,
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, huge).unwrap_err();
+    assert!(matches!(err, ContractError::Overflow(_)));
+}
+
+#[test]
+fn apply_deltas_adjusts_weights() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add_hook).unwrap();
+
+    let msg = ExecuteMsg::ApplyDeltas {
+        deltas: vec![(USER1.into(), -4), (USER2.into(), 9), (USER1.into(), 1)],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    assert_users(&deps, Some(8), Some(15), None, None);
+    let total = query_total_weight(deps.as_ref(), None).unwrap();
+    assert_eq!(total.weight, 23);
+    // one diff per delta, in order
+    let diffs = vec![
+        MemberDiff::new(USER1, Some(11), Some(7)),
+        MemberDiff::new(USER2, Some(6), Some(15)),
+        MemberDiff::new(USER1, Some(7), Some(8)),
+    ];
+    let hook_msg = MemberChangedHookMsg { diffs };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+    );
+
+    // only existing members can be adjusted
+    let msg = ExecuteMsg::ApplyDeltas {
+        deltas: vec![(USER3.into(), 1)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+}
+
+#[test]
+fn swap_weights_preserves_total() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add_hook).unwrap();
+
+    let swap = ExecuteMsg::SwapWeights {
+        a: USER1.into(),
+        b: USER2.into(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        swap.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), swap).unwrap();
+    assert_users(&deps, Some(6), Some(11), None, None);
+    let total = query_total_weight(deps.as_ref(), None).unwrap();
+    assert_eq!(total.weight, 17);
+    let hook_msg = MemberChangedHookMsg {
+        diffs: vec![
+            MemberDiff::new(USER1, Some(11), Some(6)),
+            MemberDiff::new(USER2, Some(6), Some(11)),
+        ],
+    };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+    );
+
+    // both sides must be members
+    let swap = ExecuteMsg::SwapWeights {
+        a: USER1.into(),
+        b: USER3.into(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, swap).unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+    assert_users(&deps, Some(6), Some(11), None, None);
+}
+
+#[test]
+fn apply_deltas_rejects_underflow() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // the first delta is fine, the second takes USER2 below zero
+    let msg = ExecuteMsg::ApplyDeltas {
+        deltas: vec![(USER1.into(), 3), (USER2.into(), -7)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Overflow(_)));
+}
+
+#[test]
+fn apply_deltas_respects_min_member_percent() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let floor = ExecuteMsg::SetMinMemberPercent {
+        percent: Some(Decimal::percent(10)),
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), floor).unwrap();
+
+    // 1 of 12 is under the floor
+    let msg = ExecuteMsg::ApplyDeltas {
+        deltas: vec![(USER2.into(), -5)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BelowMinMemberPercent {
+            addr: USER2.into(),
+            min: Decimal::percent(10),
+        }
+    );
+}
+
+#[test]
+fn list_by_identity_is_sorted() {
+    let mut deps = mock_dependencies();
+    let members = [
+        ("addr1", "zeta"),
+        ("addr2", ""),
+        ("addr3", "alpha"),
+        ("addr4", "mu"),
+        ("addr5", "alpha"),
+        ("addr0", ""),
+    ];
+    let msg = InstantiateMsg {
+        members: members
+            .iter()
+            .map(|(addr, identity)| Member {
+                addr: addr.to_string(),
+                weight: 1,
+                identity: identity.to_string(),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    // one identity changes and another member is removed, the index follows
+    let add = vec![Member {
+        addr: "addr4".into(),
+        weight: 1,
+        identity: "beta".into(),
+    }];
+    update_members(
+        deps.as_mut(),
+        mock_env().block.height,
+        Addr::unchecked(INIT_ADMIN),
+        add,
+        vec!["addr1".into()],
+    )
+    .unwrap();
+
+    let expected = vec!["addr3", "addr5", "addr4", "addr0", "addr2"];
+    let all = query_list_by_identity(deps.as_ref(), None, None).unwrap();
+    let addrs: Vec<_> = all.members.iter().map(|m| m.addr.as_str()).collect();
+    assert_eq!(addrs, expected);
+
+    // paging gives the same order, crossing into the members without identity
+    let mut paged = vec![];
+    let mut start_after = None;
+    loop {
+        let page = query_list_by_identity(deps.as_ref(), start_after, Some(2)).unwrap();
+        if page.members.is_empty() {
+            break;
+        }
+        start_after = page.members.last().map(|m| m.addr.clone());
+        paged.extend(page.members);
+    }
+    assert_eq!(paged, all.members);
+}
+
+#[test]
+fn min_active_total_blocks_removals() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+
+    // only the admin sets the floor
+    let set_floor = ExecuteMsg::SetMinActiveTotal { min: Some(12) };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set_floor.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), set_floor).unwrap();
+
+    // removing USER2 keeps 11, below the floor of 12
+    let remove = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        remove.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WouldBreakQuorum {
+            projected_total: 11,
+            min: 12
+        }
+    );
+
+    // adds are still fine (starting over, as a failed tx reverts on chain)
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let set_floor = ExecuteMsg::SetMinActiveTotal { min: Some(12) };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), set_floor).unwrap();
+    let add = ExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 1,
+            identity: "identity".to_string(),
+        }],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add).unwrap();
+
+    // after lowering the floor the removal goes through
+    let lower = ExecuteMsg::SetMinActiveTotal { min: Some(10) };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), lower).unwrap();
+    execute(deps.as_mut(), mock_env(), admin_info, remove).unwrap();
+    assert_users(&deps, Some(11), None, Some(1), None);
+}
+
+#[test]
+fn max_weight_delta_limits_each_operation() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let set_max = |max| ExecuteMsg::SetMaxWeightDelta { max };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set_max(Some(5)),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_max(Some(5)),
+    )
+    .unwrap();
+
+    let update = |add: Vec<(&str, u64)>| ExecuteMsg::UpdateMembers {
+        add: add
+            .into_iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.into(),
+                weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        remove: vec![],
+        expected_total: None,
+    };
+    // within the limit
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        update(vec![(USER3, 5)]),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(5), None);
+
+    // a single member moving too far, or a batch moving the total too far.
+    // Each attempt starts over, as a failed tx reverts on chain
+    let too_far = vec![
+        (update(vec![(USER1, 17)]), 6),
+        (update(vec![(USER1, 14), (USER2, 9)]), 6),
+        (
+            ExecuteMsg::ApplyDeltas {
+                deltas: vec![(USER2.into(), -6)],
+            },
+            6,
+        ),
+    ];
+    for (msg, delta) in too_far {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            set_max(Some(5)),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::WeightDeltaTooLarge { delta, max: 5 });
+    }
+
+    // lifting the limit allows larger swings again
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), set_max(None)).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info,
+        update(vec![(USER1, 17)]),
+    )
+    .unwrap();
+    assert_users(&deps, Some(17), Some(6), Some(5), None);
+}
+
+#[test]
+fn min_member_percent_rejects_small_shares() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+
+    let set_floor = |percent| ExecuteMsg::SetMinMemberPercent { percent };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set_floor(Some(Decimal::percent(10))),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_floor(Some(Decimal::percent(101))),
+    )
+    .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_floor(Some(Decimal::percent(10))),
+    )
+    .unwrap();
+
+    let add = |weight| ExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![Member {
+            addr: USER3.into(),
+            weight,
+            identity: "identity".to_string(),
+        }],
+        expected_total: None,
+    };
+    // 1 of 18 is below 10%
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), add(1)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BelowMinMemberPercent {
+            addr: USER3.into(),
+            min: Decimal::percent(10)
+        }
+    );
+
+    // 2 of 19 clears it
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_floor(Some(Decimal::percent(10))),
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add(2)).unwrap();
+
+    // removals are never blocked by the floor
+    let remove = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), remove).unwrap();
+    assert_users(&deps, Some(11), None, Some(2), None);
+
+    // lifting the floor allows small shares again
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_floor(None),
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), admin_info, add(1)).unwrap();
+    assert_users(&deps, Some(11), None, Some(1), None);
+}
+
+#[test]
+fn paused_removals_still_allow_adds() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin = mock_info(INIT_ADMIN, &[]);
+    let update = |add: Vec<(&str, u64)>, remove: &[&str]| ExecuteMsg::UpdateMembers {
+        add: add
+            .into_iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.into(),
+                weight,
+                identity: format!("{}_id", addr),
+            })
+            .collect(),
+        remove: remove.iter().map(|a| a.to_string()).collect(),
+        expected_total: None,
+    };
+    let paused = |deps: &OwnedDeps<_, _, _>| {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PausedOps {}).unwrap();
+        from_slice::<PausedOpsResponse>(&res).unwrap().ops
+    };
+
+    let pause = ExecuteMsg::SetPausedOps {
+        ops: vec![OpKind::Remove, OpKind::Remove],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        pause.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), admin.clone(), pause).unwrap();
+    assert_eq!(paused(&deps), vec![OpKind::Remove]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin.clone(),
+        update(vec![], &[USER2]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OperationPaused { op: OpKind::Remove });
+    // members cannot get around it either
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        allow_self_leave: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let pause = ExecuteMsg::SetPausedOps {
+        ops: vec![OpKind::Remove],
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), pause).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OperationPaused { op: OpKind::Remove });
+
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let pause = ExecuteMsg::SetPausedOps {
+        ops: vec![OpKind::Remove],
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), pause).unwrap();
+    // adds and reweights go through
+    let msg = update(vec![(USER3, 5), (USER1, 12)], &[]);
+    execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+    assert_users(&deps, Some(12), Some(6), Some(5), None);
+    // as do hook and admin changes
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), add_hook).unwrap();
+
+    // until those are paused instead
+    let pause = ExecuteMsg::SetPausedOps {
+        ops: vec![OpKind::HookChange, OpKind::AdminChange],
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), pause).unwrap();
+    let remove_hook = ExecuteMsg::RemoveHook {
+        addr: "hook1".into(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin.clone(), remove_hook).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OperationPaused {
+            op: OpKind::HookChange
+        }
+    );
+    let update_admin = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER1.into()),
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin.clone(), update_admin).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OperationPaused {
+            op: OpKind::AdminChange
+        }
+    );
+    execute(deps.as_mut(), mock_env(), admin, update(vec![], &[USER2])).unwrap();
+    assert_users(&deps, Some(12), None, Some(5), None);
+}
+
+#[test]
+fn nakamoto_coefficient_of_concentrated_and_distributed_groups() {
+    let group = |weights: &[u64]| {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            members: weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| Member {
+                    addr: format!("member{}", i),
+                    weight: *weight,
+                    identity: format!("identity{}", i),
+                })
+                .collect(),
+            ..default_instantiate_msg()
+        };
+        instantiate_with(deps.as_mut(), msg);
+        deps
+    };
+    let coefficient = |deps: &OwnedDeps<_, _, _>, threshold| {
+        query_nakamoto_coefficient(deps.as_ref(), threshold)
+            .unwrap()
+            .coefficient
+    };
+
+    let concentrated = group(&[50, 5, 5, 5, 5]);
+    let res = query_nakamoto_coefficient(concentrated.as_ref(), None).unwrap();
+    assert_eq!(
+        res,
+        NakamotoCoefficientResponse {
+            coefficient: 1,
+            threshold: Decimal::from_ratio(1u128, 3u128),
+        }
+    );
+    assert_eq!(coefficient(&concentrated, Some(Decimal::percent(80))), 3);
+
+    let distributed = group(&[10; 10]);
+    assert_eq!(coefficient(&distributed, None), 4);
+    // holding exactly the threshold is not enough
+    assert_eq!(coefficient(&distributed, Some(Decimal::percent(40))), 5);
+    assert_eq!(coefficient(&distributed, Some(Decimal::percent(50))), 6);
+    assert_eq!(coefficient(&distributed, Some(Decimal::zero())), 1);
+    query_nakamoto_coefficient(distributed.as_ref(), Some(Decimal::one())).unwrap_err();
+
+    assert_eq!(coefficient(&group(&[]), None), 0);
+}
+
+#[test]
+fn supersede_moves_every_attribute() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let admin = mock_info(INIT_ADMIN, &[]);
+    let old = Addr::unchecked(USER1);
+    let new = Addr::unchecked(USER3);
+    let schedule = VestingSchedule {
+        start_height: start,
+        end_height: start + 100,
+        start_weight: 11,
+        end_weight: 50,
+    };
+    let setup = vec![
+        ExecuteMsg::SetMemberTags {
+            addr: USER1.into(),
+            tags: vec!["finance".into(), "tech".into()],
+        },
+        ExecuteMsg::SetExternalId {
+            addr: USER1.into(),
+            external_id: "ext-1".into(),
+        },
+        ExecuteMsg::SetMemberExpiry {
+            addr: USER1.into(),
+            expires: Some(Expiration::AtHeight(start + 1000)),
+        },
+        ExecuteMsg::SetVesting {
+            addr: USER1.into(),
+            schedule: Some(schedule.clone()),
+        },
+    ];
+    for msg in setup {
+        execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+    }
+    let delegate = |to: &str, amount| ExecuteMsg::DelegatePartial {
+        to: to.into(),
+        amount,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        delegate(USER2, 3),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        delegate(USER1, 2),
+    )
+    .unwrap();
+    let freeze = ExecuteMsg::SetMemberFrozen {
+        addr: USER1.into(),
+        frozen: true,
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), freeze).unwrap();
+
+    // the target must be new
+    let mut env = mock_env();
+    env.block.height = start + 3;
+    let msg = ExecuteMsg::Supersede {
+        old: USER1.into(),
+        new: USER2.into(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::AlreadyAMember { addr: USER2.into() });
+
+    let msg = ExecuteMsg::Supersede {
+        old: USER1.into(),
+        new: USER3.into(),
+    };
+    let res = execute(deps.as_mut(), env, admin, msg).unwrap();
+    assert_eq!(res.events[0].ty, "supersede");
+    assert!(res.events[0]
+        .attributes
+        .contains(&attr("tags", "finance,tech")));
+
+    // weight and identity, with the total unchanged
+    assert_users(&deps, None, Some(6), Some(11), None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 17);
+    let member = query_member(deps.as_ref(), USER3.into(), None).unwrap();
+    assert_eq!(member.identity, Some("identity".to_string()));
+    // join height
+    assert_eq!(
+        JOINED_AT.may_load(&deps.storage, &new).unwrap(),
+        Some(start)
+    );
+    assert_eq!(JOINED_AT.may_load(&deps.storage, &old).unwrap(), None);
+    // tags
+    let tech = query_list_by_tag(deps.as_ref(), "tech".into(), None, None).unwrap();
+    let addrs: Vec<_> = tech.members.iter().map(|m| m.addr.as_str()).collect();
+    assert_eq!(addrs, vec![USER3]);
+    // delegations both ways
+    let delegations = query_delegations(deps.as_ref(), USER3.into()).unwrap();
+    assert_eq!(
+        delegations.outgoing,
+        vec![Delegation {
+            addr: USER2.into(),
+            amount: 3
+        }]
+    );
+    assert_eq!(
+        delegations.incoming,
+        vec![Delegation {
+            addr: USER2.into(),
+            amount: 2
+        }]
+    );
+    let old_delegations = query_delegations(deps.as_ref(), USER1.into()).unwrap();
+    assert!(old_delegations.outgoing.is_empty() && old_delegations.incoming.is_empty());
+    // external id, expiry, vesting and freeze
+    let by_id = query_member_by_external_id(deps.as_ref(), "ext-1".into()).unwrap();
+    assert_eq!(by_id.member.unwrap().addr, USER3);
+    assert_eq!(
+        EXPIRY.may_load(&deps.storage, &new).unwrap(),
+        Some(Expiration::AtHeight(start + 1000))
+    );
+    assert_eq!(
+        VESTING.may_load(&deps.storage, &new).unwrap(),
+        Some(schedule)
+    );
+    assert!(FROZEN_MEMBERS.has(&deps.storage, &new));
+    assert!(!FROZEN_MEMBERS.has(&deps.storage, &old));
+}
+
+#[test]
+fn weight_by_overlapping_tags() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), add).unwrap();
+    for (addr, tags) in [
+        (USER1, vec!["finance", "tech"]),
+        (USER2, vec!["finance"]),
+        (USER3, vec!["tech", "legal"]),
+    ] {
+        let msg = ExecuteMsg::SetMemberTags {
+            addr: addr.into(),
+            tags: tags.into_iter().map(String::from).collect(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    }
+
+    let tags = ["tech", "finance", "legal", "unused", "tech"];
+    let res =
+        query_weight_by_tag(deps.as_ref(), tags.iter().map(|t| t.to_string()).collect()).unwrap();
+    let sums: Vec<_> = res
+        .tags
+        .iter()
+        .map(|t| (t.tag.as_str(), t.weight))
+        .collect();
+    assert_eq!(
+        sums,
+        vec![("tech", 15), ("finance", 17), ("legal", 4), ("unused", 0)]
+    );
+
+    let too_many = (0..31).map(|i| i.to_string()).collect();
+    query_weight_by_tag(deps.as_ref(), too_many).unwrap_err();
+}
+
+#[test]
+fn member_tags() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+
+    let tag = |addr: &str, tags: &[&str]| ExecuteMsg::SetMemberTags {
+        addr: addr.into(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+    };
+
+    // only admin, only members
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        tag(USER1, &["finance"]),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        tag(USER3, &["finance"]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        tag(USER1, &["finance", "tech"]),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        tag(USER2, &["finance"]),
+    )
+    .unwrap();
+
+    let finance = query_list_by_tag(deps.as_ref(), "finance".into(), None, None).unwrap();
+    let addrs: Vec<_> = finance.members.iter().map(|m| m.addr.as_str()).collect();
+    assert_eq!(addrs, vec![USER2, USER1]);
+    let tech = query_list_by_tag(deps.as_ref(), "tech".into(), None, None).unwrap();
+    assert_eq!(tech.members.len(), 1);
+    assert_eq!(tech.members[0].weight, 11);
+    let page =
+        query_list_by_tag(deps.as_ref(), "finance".into(), Some(USER2.into()), None).unwrap();
+    assert_eq!(page.members.len(), 1);
+    assert_eq!(page.members[0].addr, USER1);
+
+    assert_eq!(
+        query_tag_weight(deps.as_ref(), "finance".into())
+            .unwrap()
+            .weight,
+        17
+    );
+    assert_eq!(
+        query_tag_weight(deps.as_ref(), "tech".into())
+            .unwrap()
+            .weight,
+        11
+    );
+    assert_eq!(
+        query_tag_weight(deps.as_ref(), "none".into())
+            .unwrap()
+            .weight,
+        0
+    );
+
+    // re-tagging replaces the old set
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        tag(USER1, &["tech"]),
+    )
+    .unwrap();
+    assert_eq!(
+        query_tag_weight(deps.as_ref(), "finance".into())
+            .unwrap()
+            .weight,
+        6
+    );
+
+    // removing a member clears their tags
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER1.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+    let tech = query_list_by_tag(deps.as_ref(), "tech".into(), None, None).unwrap();
+    assert!(tech.members.is_empty());
+}
+
+#[test]
+fn stats_count_activity() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+
+    // instantiation counts as adding the initial members
+    let stats = STATS.load(&deps.storage).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            total_adds: 2,
+            total_removals: 0,
+            total_weight_added: 17,
+            admin_changes: 0,
+        }
+    );
+
+    // raise USER1 by 4, add USER3 with 5 and drop USER2
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 15,
+                identity: "identity".to_string(),
+            },
+            Member {
+                addr: USER3.into(),
+                weight: 5,
+                identity: "identity".to_string(),
+            },
+        ],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+
+    // lowering a weight does not count as added weight
+    let msg = ExecuteMsg::ScaleWeights {
+        numerator: 1,
+        denominator: 5,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER1.into()),
+    };
+    execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+
+    let stats = STATS.load(&deps.storage).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            total_adds: 3,
+            total_removals: 1,
+            total_weight_added: 26,
+            admin_changes: 1,
+        }
+    );
+}
+
+#[test]
+fn next_member_walks_address_order() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    // members sorted by address are "else" (USER2) then "somebody" (USER1)
+    let next = |after: &str| {
+        query_next_member(deps.as_ref(), after.into())
+            .unwrap()
+            .member
+    };
+
+    // before the first member
+    let first = next("aaa").unwrap();
+    assert_eq!(first.addr, USER2);
+    assert_eq!(first.weight, 6);
+    assert_eq!(first.identity, "identity_2");
+
+    // from the middle, whether or not `after` is a member
+    assert_eq!(next(USER2).unwrap().addr, USER1);
+    assert_eq!(next("funny").unwrap().addr, USER1);
+
+    // past the end
+    assert_eq!(next(USER1), None);
+}
+
+#[test]
+fn partial_delegation() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let delegate = |to: &str, amount: u64| ExecuteMsg::DelegatePartial {
+        to: to.into(),
+        amount,
+    };
+
+    // only members can delegate, and only to members
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER3, &[]),
+        delegate(USER1, 1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        delegate(USER3, 1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+
+    // USER1 (11) delegates 4 to USER2 (6)
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        delegate(USER2, 4),
+    )
+    .unwrap();
+    let user1 = query_effective_weight(deps.as_ref(), USER1.into()).unwrap();
+    assert_eq!((user1.delegated_out, user1.effective), (4, 7));
+    let user2 = query_effective_weight(deps.as_ref(), USER2.into()).unwrap();
+    assert_eq!((user2.delegated_in, user2.effective), (4, 10));
+
+    // USER2 delegates part of its own weight back
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        delegate(USER1, 2),
+    )
+    .unwrap();
+    let user1 = query_effective_weight(deps.as_ref(), USER1.into()).unwrap();
+    assert_eq!(user1.effective, 11 - 4 + 2);
+    let user2 = query_effective_weight(deps.as_ref(), USER2.into()).unwrap();
+    assert_eq!(user2.effective, 6 - 2 + 4);
+
+    // replacing a delegation counts against the remaining weight once
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        delegate(USER2, 12),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OverDelegated { available: 11 });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        delegate(USER2, 11),
+    )
+    .unwrap();
+
+    let delegations = query_delegations(deps.as_ref(), USER1.into()).unwrap();
+    assert_eq!(
+        delegations.outgoing,
+        vec![Delegation {
+            addr: USER2.into(),
+            amount: 11
+        }]
+    );
+    assert_eq!(
+        delegations.incoming,
+        vec![Delegation {
+            addr: USER2.into(),
+            amount: 2
+        }]
+    );
+
+    // a weight cut trims what is delegated, so effective weights still add up
+    let set_user1 = |weight| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER1.into(),
+            weight,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set_user1(1),
+    )
+    .unwrap();
+    let user1 = query_effective_weight(deps.as_ref(), USER1.into()).unwrap();
+    assert_eq!((user1.delegated_out, user1.effective), (1, 2));
+    let user2 = query_effective_weight(deps.as_ref(), USER2.into()).unwrap();
+    assert_eq!((user2.delegated_in, user2.effective), (1, 5));
+    assert_eq!(user1.effective + user2.effective, 7);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set_user1(11),
+    )
+    .unwrap();
+
+    // removing a member clears the delegations on both sides
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER1.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let user2 = query_effective_weight(deps.as_ref(), USER2.into()).unwrap();
+    assert_eq!(user2.effective, 6);
+    let delegations = query_delegations(deps.as_ref(), USER2.into()).unwrap();
+    assert!(delegations.outgoing.is_empty() && delegations.incoming.is_empty());
+}
+
+#[test]
+fn supersede_moves_root_and_aliases() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin = mock_info(INIT_ADMIN, &[]);
+    let msg = ExecuteMsg::SetAlias {
+        alias: "alias".into(),
+        canonical: USER1.into(),
+    };
+    execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.height += 1;
+    let msg = ExecuteMsg::Supersede {
+        old: USER1.into(),
+        new: USER3.into(),
+    };
+    execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+    // "else" (6), "funny" (11)
+    let leaves = vec![merkle::leaf(USER2, 6), merkle::leaf(USER3, 11)];
+    let proof: Vec<Binary> = merkle::proof(&leaves, 1)
+        .unwrap()
+        .into_iter()
+        .map(|h| Binary::from(h.to_vec()))
+        .collect();
+    let height = env.block.height + 1;
+    let res = query_verify_proof(deps.as_ref(), USER3.into(), 11, height, proof).unwrap();
+    assert!(res.valid);
+    // the old holder no longer verifies against the new root
+    let old_leaves = vec![merkle::leaf(USER2, 6), merkle::leaf(USER1, 11)];
+    let old_proof: Vec<Binary> = merkle::proof(&old_leaves, 1)
+        .unwrap()
+        .into_iter()
+        .map(|h| Binary::from(h.to_vec()))
+        .collect();
+    let res = query_verify_proof(deps.as_ref(), USER1.into(), 11, height, old_proof).unwrap();
+    assert!(!res.valid);
+
+    // the alias now resolves to the new holder
+    let member = query_member(deps.as_ref(), "alias".into(), None).unwrap();
+    assert_eq!(member.weight, Some(11));
+    assert_eq!(
+        ALIASES
+            .load(&deps.storage, &Addr::unchecked("alias"))
+            .unwrap(),
+        Addr::unchecked(USER3)
+    );
+
+    // locked members cannot be superseded
+    let msg = ExecuteMsg::LockMember { addr: USER2.into() };
+    execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Supersede {
+        old: USER2.into(),
+        new: "other".into(),
+    };
+    let err = execute(deps.as_mut(), env, admin, msg).unwrap_err();
+    assert_eq!(err, ContractError::MemberLocked { addr: USER2.into() });
+}
+
+#[test]
+fn verify_membership_proofs() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let height = mock_env().block.height;
+
+    // build the proof off-chain from the sorted member set: "else" (6), "somebody" (11)
+    let leaves = vec![merkle::leaf(USER2, 6), merkle::leaf(USER1, 11)];
+    let to_binary_proof = |proof: Vec<merkle::Hash>| -> Vec<Binary> {
+        proof
+            .into_iter()
+            .map(|h| Binary::from(h.to_vec()))
+            .collect()
+    };
+    let proof = to_binary_proof(merkle::proof(&leaves, 1).unwrap());
+
+    let res =
+        query_verify_proof(deps.as_ref(), USER1.into(), 11, height + 1, proof.clone()).unwrap();
+    assert!(res.valid);
+    assert_eq!(res.root.unwrap().as_slice(), merkle::root(&leaves));
+
+    // wrong weight, wrong address or a tampered sibling all fail
+    let res =
+        query_verify_proof(deps.as_ref(), USER1.into(), 12, height + 1, proof.clone()).unwrap();
+    assert!(!res.valid);
+    let res =
+        query_verify_proof(deps.as_ref(), USER3.into(), 11, height + 1, proof.clone()).unwrap();
+    assert!(!res.valid);
+    let mut tampered = proof.clone();
+    let mut bytes = tampered[0].to_vec();
+    bytes[0] ^= 1;
+    tampered[0] = Binary::from(bytes);
+    let res = query_verify_proof(deps.as_ref(), USER1.into(), 11, height + 1, tampered).unwrap();
+    assert!(!res.valid);
+
+    // nothing was committed before instantiation
+    let res = query_verify_proof(deps.as_ref(), USER1.into(), 11, height, proof.clone()).unwrap();
+    assert!(!res.valid);
+    assert_eq!(res.root, None);
+
+    // after an update, the old proof holds for the old height only
+    let add = vec![Member {
+        addr: USER3.into(),
+        weight: 5,
+        identity: "identity".to_string(),
+    }];
+    update_members(
+        deps.as_mut(),
+        height + 5,
+        Addr::unchecked(INIT_ADMIN),
+        add,
+        vec![],
+    )
+    .unwrap();
+    let res =
+        query_verify_proof(deps.as_ref(), USER1.into(), 11, height + 5, proof.clone()).unwrap();
+    assert!(res.valid);
+    let res = query_verify_proof(deps.as_ref(), USER1.into(), 11, height + 6, proof).unwrap();
+    assert!(!res.valid);
+
+    // with three members, "funny" (USER3) sorts between the other two
+    let leaves = vec![
+        merkle::leaf(USER2, 6),
+        merkle::leaf(USER3, 5),
+        merkle::leaf(USER1, 11),
+    ];
+    for (index, (addr, weight)) in [(USER2, 6), (USER3, 5), (USER1, 11)].iter().enumerate() {
+        let proof = to_binary_proof(merkle::proof(&leaves, index).unwrap());
+        let res = query_verify_proof(deps.as_ref(), addr.to_string(), *weight, height + 6, proof)
+            .unwrap();
+        assert!(res.valid);
+    }
+}
+
+#[test]
+fn strict_and_lenient_removals() {
+    let remove_absent = ExecuteMsg::UpdateMembers {
+        remove: vec![USER3.into()],
+        add: vec![],
+        expected_total: None,
+    };
+
+    // lenient by default, the absent address is ignored
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        remove_absent.clone(),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_users(&deps, Some(11), Some(6), None, None);
+
+    // strict mode rejects it
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        strict_removals: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        remove_absent,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+
+    // while removing actual members still works
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_users(&deps, Some(11), None, None, None);
+}
+
+#[test]
+fn transfer_admin_and_seat() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: Some(USER1.into()),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let hook = "hook_contract";
+    let add_hook = ExecuteMsg::AddHook {
+        addr: hook.into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), add_hook).unwrap();
+
+    let transfer = ExecuteMsg::TransferAdminAndSeat {
+        new_addr: USER3.into(),
+        identity: "identity_3".into(),
+    };
+
+    // only the admin can hand over
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        transfer.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), transfer).unwrap();
+
+    // a single hook message carries both sides of the move
+    let diffs = vec![
+        MemberDiff::new(USER1, Some(11), None),
+        MemberDiff::new(USER3, None, Some(11)),
+    ];
+    let hook_msg = MemberChangedHookMsg { diffs };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg(hook).unwrap())]
+    );
+
+    assert_eq!(
+        ADMIN.get(deps.as_ref()).unwrap(),
+        Some(Addr::unchecked(USER3))
+    );
+    assert_users(&deps, None, Some(6), Some(11), None);
+    let member = query_member(deps.as_ref(), USER3.into(), None).unwrap();
+    assert_eq!(member.identity, Some("identity_3".to_string()));
+
+    // the old admin has lost both roles
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::TransferAdminAndSeat {
+            new_addr: USER2.into(),
+            identity: "identity_2".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+}
+
+#[test]
+fn member_history_reconstructs_changes() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+
+    let mut update = |height: u64, add: Vec<Member>, remove: Vec<String>| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::UpdateMembers {
+            add,
+            remove,
+            expected_total: None,
+        };
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    };
+    let user1 = |weight| Member {
+        addr: USER1.into(),
+        weight,
+        identity: "identity".to_string(),
+    };
+    update(start + 5, vec![user1(20)], vec![]);
+    update(start + 15, vec![], vec![USER1.into()]);
+    update(start + 25, vec![user1(5)], vec![]);
+    // other members do not show up
+    update(
+        start + 30,
+        vec![Member {
+            addr: USER2.into(),
+            weight: 9,
+            identity: "identity_2".to_string(),
+        }],
+        vec![],
+    );
+
+    let change = |offset: u64, weight| WeightChange {
+        height: start + offset,
+        weight,
+    };
+    let history = query_member_history(deps.as_ref(), USER1.into(), 0, u64::MAX, None).unwrap();
+    assert_eq!(
+        history.changes,
+        vec![
+            change(0, Some(11)),
+            change(5, Some(20)),
+            change(15, None),
+            change(25, Some(5)),
+        ]
+    );
+
+    // range is inclusive on both ends
+    let history =
+        query_member_history(deps.as_ref(), USER1.into(), start + 5, start + 15, None).unwrap();
+    assert_eq!(history.changes, vec![change(5, Some(20)), change(15, None)]);
+
+    // and bounded by the limit
+    let history =
+        query_member_history(deps.as_ref(), USER1.into(), start + 1, u64::MAX, Some(1)).unwrap();
+    assert_eq!(history.changes, vec![change(5, Some(20))]);
+    assert!(history.has_more);
+    let history =
+        query_member_history(deps.as_ref(), USER1.into(), start + 1, u64::MAX, Some(3)).unwrap();
+    assert!(!history.has_more);
+
+    // a member who never changed has no history
+    let history = query_member_history(deps.as_ref(), USER3.into(), 0, u64::MAX, None).unwrap();
+    assert!(history.changes.is_empty());
+}
+
+#[test]
+fn require_identity() {
+    let anonymous = Member {
+        addr: USER3.into(),
+        weight: 5,
+        identity: "".to_string(),
+    };
+    let add_anonymous = ExecuteMsg::UpdateMembers {
+        add: vec![anonymous.clone()],
+        remove: vec![],
+        expected_total: None,
+    };
+
+    // accepted when the flag is off
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        add_anonymous.clone(),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(5), None);
+
+    // rejected on add when it is on
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        require_identity: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        add_anonymous,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IdentityRequired { addr: USER3.into() });
+
+    // and at instantiation
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        require_identity: true,
+        members: vec![anonymous],
+        ..default_instantiate_msg()
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::IdentityRequired { addr: USER3.into() });
+}
+
+#[test]
+fn inactive_weight_counts_frozen_and_expired() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), add).unwrap();
+
+    let inactive = query_inactive_weight(deps.as_ref(), &mock_env()).unwrap();
+    assert_eq!(inactive.inactive, 0);
+    assert_eq!(inactive.active, 21);
+
+    // only the admin can freeze
+    let freeze = ExecuteMsg::SetMemberFrozen {
+        addr: USER1.into(),
+        frozen: true,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        freeze.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        freeze,
+    )
+    .unwrap();
+
+    // USER2 expires a few blocks from now, USER3 is both frozen and expiring
+    let expires_at = mock_env().block.height + 10;
+    for addr in [USER2, USER3] {
+        let msg = ExecuteMsg::SetMemberExpiry {
+            addr: addr.into(),
+            expires: Some(Expiration::AtHeight(expires_at)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    }
+    let msg = ExecuteMsg::SetMemberFrozen {
+        addr: USER3.into(),
+        frozen: true,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+    let inactive = query_inactive_weight(deps.as_ref(), &mock_env()).unwrap();
+    assert_eq!(inactive.inactive, 15);
+    assert_eq!(inactive.active, 6);
+
+    let mut later = mock_env();
+    later.block.height = expires_at;
+    let inactive = query_inactive_weight(deps.as_ref(), &later).unwrap();
+    assert_eq!(inactive.inactive, 21);
+    assert_eq!(inactive.active, 0);
+
+    // unfreezing and clearing the expiry makes a member active again
+    let msg = ExecuteMsg::SetMemberFrozen {
+        addr: USER1.into(),
+        frozen: false,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SetMemberExpiry {
+        addr: USER2.into(),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let inactive = query_inactive_weight(deps.as_ref(), &later).unwrap();
+    assert_eq!(inactive.inactive, 4);
+    assert_eq!(inactive.active, 17);
+
+    // only members can be frozen
+    let msg = ExecuteMsg::SetMemberFrozen {
+        addr: "outsider".into(),
+        frozen: true,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotAMember {
+            addr: "outsider".into()
+        }
+    );
+}
+
+#[test]
+fn dissolve_empties_and_locks_group() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Dissolve {},
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let mut env = mock_env();
+    env.block.height += 5;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::Dissolve {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "removed_weight")
+            .unwrap()
+            .value,
+        "17"
+    );
+
+    // the group is empty
+    assert_users(&deps, None, None, None, None);
+
+    // no further changes are accepted, not even by the admin
+    let height = env.block.height;
+    let msgs = vec![
+        ExecuteMsg::UpdateMembers {
+            add: vec![Member {
+                addr: USER1.into(),
+                weight: 5,
+                identity: "identity".to_string(),
+            }],
+            remove: vec![],
+            expected_total: None,
+        },
+        ExecuteMsg::UpdateAdmin {
+            admin: Some(USER1.into()),
+        },
+        ExecuteMsg::Dissolve {},
+    ];
+    for msg in msgs {
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Dissolved { height });
+    }
+
+    // history remains queryable
+    assert_users(&deps, Some(11), Some(6), None, Some(height));
+    let res: DissolvedResponse =
+        from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::Dissolved {}).unwrap()).unwrap();
+    assert_eq!(res.dissolved_at, Some(height));
+}
+
+#[test]
+fn instantiate_frozen() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        frozen: Some(true),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    assert_users(&deps, Some(11), Some(6), None, None);
+
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 5,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+
+    // only the admin can unfreeze
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Unfreeze {},
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::Unfreeze {},
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update,
+    )
+    .unwrap();
+    assert_users(&deps, None, Some(6), Some(5), None);
+
+    // and freeze again
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::Freeze {},
+    )
+    .unwrap();
+    let res: FrozenResponse =
+        from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::Frozen {}).unwrap()).unwrap();
+    assert!(res.frozen);
+}
+
+#[test]
+fn effective_threshold_takes_the_higher_rule() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    // total weight is 17
+
+    // absolute dominates
+    let res = query_effective_threshold(deps.as_ref(), Some(10), Some(Decimal::percent(50)), None)
+        .unwrap();
+    assert_eq!(res.threshold, 10);
+    assert_eq!(res.total_weight, 17);
+
+    // percentage dominates, rounding up
+    let res = query_effective_threshold(deps.as_ref(), Some(5), Some(Decimal::percent(50)), None)
+        .unwrap();
+    assert_eq!(res.threshold, 9);
+
+    // equal
+    let res =
+        query_effective_threshold(deps.as_ref(), Some(17), Some(Decimal::one()), None).unwrap();
+    assert_eq!(res.threshold, 17);
+
+    // only one supplied
+    let res = query_effective_threshold(deps.as_ref(), Some(3), None, None).unwrap();
+    assert_eq!(res.threshold, 3);
+    let res =
+        query_effective_threshold(deps.as_ref(), None, Some(Decimal::percent(10)), None).unwrap();
+    assert_eq!(res.threshold, 2);
+
+    // neither, or an invalid percentage
+    query_effective_threshold(deps.as_ref(), None, None, None).unwrap_err();
+    query_effective_threshold(deps.as_ref(), None, Some(Decimal::percent(101)), None).unwrap_err();
+
+    // resolved against the total at the given height
+    let mut env = mock_env();
+    env.block.height += 1;
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let res =
+        query_effective_threshold(deps.as_ref(), None, Some(Decimal::percent(50)), None).unwrap();
+    assert_eq!(res.threshold, 3);
+    let res = query_effective_threshold(
+        deps.as_ref(),
+        None,
+        Some(Decimal::percent(50)),
+        Some(env.block.height),
+    )
+    .unwrap();
+    assert_eq!(res.threshold, 9);
+}
+
+#[test]
+fn search_by_identity_prefix() {
+    let mut deps = mock_dependencies();
+    let member = |addr: &str, identity: &str| Member {
+        addr: addr.into(),
+        weight: 1,
+        identity: identity.to_string(),
+    };
+    let msg = InstantiateMsg {
+        members: vec![
+            member("senator_b", "kujira:bob"),
+            member("senator_a", "kujira:alice"),
+            member("senator_c", "kujira:alice"),
+            member("senator_d", "kujiraalice"),
+            member("senator_e", "osmo:alice"),
+            member("senator_f", "kuji"),
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let search = |prefix: &str, limit| {
+        query_search_by_identity_prefix(deps.as_ref(), prefix.into(), limit)
+            .unwrap()
+            .members
+            .into_iter()
+            .map(|m| m.addr)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        search("kujira:", None),
+        vec!["senator_a", "senator_c", "senator_b"]
+    );
+    assert_eq!(search("kujira:a", None), vec!["senator_a", "senator_c"]);
+    assert_eq!(
+        search("kuji", None),
+        vec![
+            "senator_f",
+            "senator_a",
+            "senator_c",
+            "senator_b",
+            "senator_d"
+        ]
+    );
+    assert_eq!(search("kujira:", Some(2)), vec!["senator_a", "senator_c"]);
+    assert!(search("cosmos", None).is_empty());
+
+    // the prefix must be non-empty and bounded
+    query_search_by_identity_prefix(deps.as_ref(), "".into(), None).unwrap_err();
+    query_search_by_identity_prefix(deps.as_ref(), "x".repeat(65), None).unwrap_err();
+}
+
+#[test]
+fn external_ids() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let set = |addr: &str, external_id: &str| ExecuteMsg::SetExternalId {
+        addr: addr.into(),
+        external_id: external_id.into(),
+    };
+    let lookup = |deps: &OwnedDeps<_, _, _>, external_id: &str| {
+        query_member_by_external_id(deps.as_ref(), external_id.into())
+            .unwrap()
+            .member
+            .map(|m| m.addr)
+    };
+
+    // admin only
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set(USER1, "hr-1"),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set(USER1, "hr-1"),
+    )
+    .unwrap();
+    assert_eq!(lookup(&deps, "hr-1"), Some(USER1.to_string()));
+    assert_eq!(lookup(&deps, "hr-2"), None);
+
+    // ids are unique
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set(USER2, "hr-1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ExternalIdTaken {
+            external_id: "hr-1".into(),
+            owner: USER1.into()
+        }
+    );
+
+    // replacing an id frees the old one
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set(USER1, "hr-3"),
+    )
+    .unwrap();
+    assert_eq!(lookup(&deps, "hr-1"), None);
+    assert_eq!(lookup(&deps, "hr-3"), Some(USER1.to_string()));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        set(USER2, "hr-1"),
+    )
+    .unwrap();
+    assert_eq!(lookup(&deps, "hr-1"), Some(USER2.to_string()));
+
+    // and removal clears it
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(lookup(&deps, "hr-3"), None);
+}
+
+#[test]
+fn list_queries_report_has_more() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 11,
+                identity: "identity".to_string(),
+            },
+            Member {
+                addr: USER2.into(),
+                weight: 6,
+                identity: "identity_2".to_string(),
+            },
+            Member {
+                addr: USER3.into(),
+                weight: 4,
+                identity: "identity_3".to_string(),
+            },
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    // mid-list
+    let page = query_list_members(deps.as_ref(), None, Some(2)).unwrap();
+    assert_eq!(page.members.len(), 2);
+    assert!(page.has_more);
+
+    // final page, including one that ends exactly at the limit
+    let last = page.members.last().unwrap().addr.clone();
+    let page = query_list_members(deps.as_ref(), Some(last), Some(2)).unwrap();
+    assert_eq!(page.members.len(), 1);
+    assert!(!page.has_more);
+    let page = query_list_members(deps.as_ref(), None, Some(3)).unwrap();
+    assert!(!page.has_more);
+
+    let page = query_list_by_identity(deps.as_ref(), None, Some(2)).unwrap();
+    assert!(page.has_more);
+    let page = query_list_by_identity(deps.as_ref(), Some(USER2.into()), Some(2)).unwrap();
+    assert_eq!(page.members.len(), 1);
+    assert!(!page.has_more);
+
+    let page = query_search_by_identity_prefix(deps.as_ref(), "identity".into(), Some(2)).unwrap();
+    assert!(page.has_more);
+    let page = query_search_by_identity_prefix(deps.as_ref(), "identity".into(), None).unwrap();
+    assert_eq!(page.members.len(), 3);
+    assert!(!page.has_more);
+}
+
+#[test]
+fn overview_matches_individual_queries() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let check = |deps: &OwnedDeps<_, _, _>| {
+        let overview = query_overview(deps.as_ref()).unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        let members = query_list_members(deps.as_ref(), None, Some(30)).unwrap();
+        let frozen: FrozenResponse =
+            from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::Frozen {}).unwrap()).unwrap();
+        assert_eq!(
+            overview.admin,
+            ADMIN.query_admin(deps.as_ref()).unwrap().admin
+        );
+        assert_eq!(
+            overview.total_weight,
+            query_total_weight(deps.as_ref(), None).unwrap().weight
+        );
+        assert_eq!(overview.member_count, members.members.len() as u64);
+        assert_eq!(overview.min_weight, config.min_weight);
+        assert_eq!(overview.max_weight, config.max_weight);
+        assert_eq!(overview.frozen, frozen.frozen);
+        overview
+    };
+    let overview = check(&deps);
+    assert_eq!(overview.member_count, 2);
+    assert_eq!(overview.total_weight, 17);
+
+    // the count follows adds, re-adds and removals
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![
+            Member {
+                addr: USER3.into(),
+                weight: 4,
+                identity: "identity_3".to_string(),
+            },
+            Member {
+                addr: USER1.into(),
+                weight: 2,
+                identity: "identity".to_string(),
+            },
+        ],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::Freeze {},
+    )
+    .unwrap();
+    let overview = check(&deps);
+    assert_eq!(overview.member_count, 2);
+    assert_eq!(overview.total_weight, 6);
+    assert!(overview.frozen);
+}
+
+#[test]
+fn last_batch_diff_reflects_latest_batch() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let last_batch = |deps: &OwnedDeps<_, _, _>| -> LastBatchDiffResponse {
+        from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::LastBatchDiff {}).unwrap()).unwrap()
+    };
+    assert_eq!(
+        last_batch(&deps),
+        LastBatchDiffResponse {
+            height: None,
+            diffs: vec![]
+        }
+    );
+
+    let mut env = mock_env();
+    env.block.height += 3;
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        last_batch(&deps),
+        LastBatchDiffResponse {
+            height: Some(env.block.height),
+            diffs: vec![
+                MemberDiff::new(USER3, None, Some(4)),
+                MemberDiff::new(USER1, Some(11), None),
+            ]
+        }
+    );
+
+    // the next batch replaces it
+    env.block.height += 1;
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        last_batch(&deps),
+        LastBatchDiffResponse {
+            height: Some(env.block.height),
+            diffs: vec![MemberDiff::new(USER2, Some(6), None)]
+        }
+    );
+}
+
+#[test]
+fn weight_for_share() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    // total weight is 17, USER2 holds 6
+    let additional = |addr: &str, target| {
+        query_weight_for_share(deps.as_ref(), addr.into(), target)
+            .unwrap()
+            .additional
+    };
+
+    // below the target: 11 / 22 is exactly half
+    assert_eq!(additional(USER2, Decimal::percent(50)), Some(5));
+    // rounding up: 16 / 27 falls short of 60%, 17 / 28 does not
+    assert_eq!(additional(USER2, Decimal::percent(60)), Some(11));
+    // non-members start from nothing: 17 / 34
+    assert_eq!(additional(USER3, Decimal::percent(50)), Some(17));
+
+    // already above the target
+    assert_eq!(additional(USER1, Decimal::percent(50)), Some(0));
+    assert_eq!(additional(USER2, Decimal::zero()), Some(0));
+
+    // all of the weight can never be reached by someone sharing it, nor more than that
+    assert_eq!(additional(USER2, Decimal::one()), None);
+    assert_eq!(additional(USER2, Decimal::percent(150)), None);
+    assert_eq!(additional(USER2, Decimal::MAX), None);
+}
+
+#[test]
+fn emergency_admin_recovery() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        emergency_recovery: Some(EmergencyRecovery {
+            supermajority: Decimal::percent(67),
+            voting_blocks: 10,
+        }),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let new_admin = "recovered";
+    let propose = ExecuteMsg::ProposeEmergencyAdmin {
+        addr: new_admin.into(),
+    };
+
+    // only members take part
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER3, &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+
+    // 6 of 17 is not enough
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        propose.clone(),
+    )
+    .unwrap();
+    let proposal = query_emergency_proposal(deps.as_ref(), &mock_env()).unwrap();
+    assert_eq!(proposal.addr, Some(new_admin.to_string()));
+    assert_eq!(proposal.votes, 6);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::EmergencyProposalOpen {});
+
+    // the proposal expires without reaching the supermajority
+    let mut expired = mock_env();
+    expired.block.height += 10;
+    let err = execute(
+        deps.as_mut(),
+        expired.clone(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::VoteEmergencyAdmin {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoEmergencyProposal {});
+    assert_eq!(
+        ADMIN.get(deps.as_ref()).unwrap(),
+        Some(Addr::unchecked(INIT_ADMIN))
+    );
+
+    // a new proposal starts over, 11 of 17 falls short of 67%
+    execute(
+        deps.as_mut(),
+        expired.clone(),
+        mock_info(USER1, &[]),
+        propose,
+    )
+    .unwrap();
+    let proposal = query_emergency_proposal(deps.as_ref(), &expired).unwrap();
+    assert_eq!(proposal.votes, 11);
+    assert_eq!(
+        ADMIN.get(deps.as_ref()).unwrap(),
+        Some(Addr::unchecked(INIT_ADMIN))
+    );
+
+    // and 17 of 17 exceeds it
+    execute(
+        deps.as_mut(),
+        expired.clone(),
+        mock_info(USER2, &[]),
+        ExecuteMsg::VoteEmergencyAdmin {},
+    )
+    .unwrap();
+    assert_eq!(
+        ADMIN.get(deps.as_ref()).unwrap(),
+        Some(Addr::unchecked(new_admin))
+    );
+    let proposal = query_emergency_proposal(deps.as_ref(), &expired).unwrap();
+    assert_eq!(proposal.addr, None);
+    assert_eq!(proposal.votes, 0);
+}
+
+#[test]
+fn emergency_recovery_disabled_by_default() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::ProposeEmergencyAdmin { addr: USER1.into() },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RecoveryDisabled {});
+}
+
+#[test]
+fn average_join_height_is_weighted() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let average =
+        |deps: &OwnedDeps<_, _, _>| query_average_join_height(deps.as_ref()).unwrap().height;
+    assert_eq!(average(&deps), None);
+
+    let join = |deps: &mut OwnedDeps<_, _, _>, height, addr: &str, weight| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::UpdateMembers {
+            add: vec![Member {
+                addr: addr.into(),
+                weight,
+                identity: "identity".to_string(),
+            }],
+            remove: vec![],
+            expected_total: None,
+        };
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    };
+    join(&mut deps, 100, USER1, 3);
+    join(&mut deps, 200, USER2, 1);
+    // (100 * 3 + 200 * 1) / 4
+    assert_eq!(average(&deps), Some(125));
+
+    // weight changes keep the original join height
+    join(&mut deps, 300, USER1, 1);
+    assert_eq!(average(&deps), Some(150));
+    join(&mut deps, 400, USER3, 2);
+    // (100 + 200 + 400 * 2) / 4
+    assert_eq!(average(&deps), Some(275));
+
+    // leaving and rejoining starts over
+    let remove = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        remove,
+    )
+    .unwrap();
+    join(&mut deps, 500, USER1, 1);
+    // (500 + 200 + 400 * 2) / 4
+    assert_eq!(average(&deps), Some(375));
+}
+
+#[test]
+fn average_join_height_skips_untracked_members() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let average = query_average_join_height(deps.as_ref()).unwrap();
+    assert_eq!(average.height, Some(start));
+    assert_eq!(average.untracked_weight, 0);
+
+    // as for members stored before join heights were tracked
+    JOINED_AT.remove(&mut deps.storage, &Addr::unchecked(USER1));
+    let average = query_average_join_height(deps.as_ref()).unwrap();
+    assert_eq!(average.height, Some(start));
+    assert_eq!(average.untracked_weight, 11);
+
+    JOINED_AT.remove(&mut deps.storage, &Addr::unchecked(USER2));
+    let average = query_average_join_height(deps.as_ref()).unwrap();
+    assert_eq!(average.height, None);
+    assert_eq!(average.untracked_weight, 17);
+}
+
+#[test]
+fn list_by_join_height_in_both_directions() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let update = |deps: &mut OwnedDeps<_, _, _>, height, add: &[&str], remove: &[&str]| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::UpdateMembers {
+            add: add
+                .iter()
+                .map(|addr| Member {
+                    addr: addr.to_string(),
+                    weight: 1,
+                    identity: "identity".to_string(),
+                })
+                .collect(),
+            remove: remove.iter().map(|addr| addr.to_string()).collect(),
+            expected_total: None,
+        };
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    };
+    let list = |deps: &OwnedDeps<_, _, _>, start_after: Option<&str>, limit, reverse| {
+        let page = query_list_by_join_height(
+            deps.as_ref(),
+            start_after.map(String::from),
+            Some(limit),
+            reverse,
+        )
+        .unwrap();
+        let addrs: Vec<_> = page.members.into_iter().map(|m| m.addr).collect();
+        (addrs, page.has_more)
+    };
+
+    update(&mut deps, 100, &[USER3], &[]);
+    update(&mut deps, 200, &[USER1], &[]);
+    update(&mut deps, 300, &[USER2], &[]);
+    // reweighting keeps the original join height
+    update(&mut deps, 400, &[USER3], &[]);
+    assert_eq!(
+        list(&deps, None, 10, false),
+        (vec![USER3.into(), USER1.into(), USER2.into()], false)
+    );
+    assert_eq!(
+        list(&deps, None, 10, true),
+        (vec![USER2.into(), USER1.into(), USER3.into()], false)
+    );
+
+    // leaving and rejoining moves a member to the newest end
+    update(&mut deps, 500, &[], &[USER3]);
+    assert_eq!(
+        list(&deps, None, 10, false),
+        (vec![USER1.into(), USER2.into()], false)
+    );
+    update(&mut deps, 600, &[USER3], &[]);
+    assert_eq!(
+        list(&deps, None, 10, false),
+        (vec![USER1.into(), USER2.into(), USER3.into()], false)
+    );
+
+    // pages continue from the last address in either direction
+    assert_eq!(
+        list(&deps, None, 2, false),
+        (vec![USER1.into(), USER2.into()], true)
+    );
+    assert_eq!(
+        list(&deps, Some(USER2), 2, false),
+        (vec![USER3.into()], false)
+    );
+    assert_eq!(
+        list(&deps, None, 2, true),
+        (vec![USER3.into(), USER2.into()], true)
+    );
+    assert_eq!(
+        list(&deps, Some(USER2), 2, true),
+        (vec![USER1.into()], false)
+    );
+
+    // a cursor that is not a member is rejected
+    query_list_by_join_height(deps.as_ref(), Some("nobody".into()), None, false).unwrap_err();
+}
+
+#[test]
+fn members_to_reach_from_both_ends() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: [1, 1, 1, 2, 5, 10]
+            .iter()
+            .enumerate()
+            .map(|(i, &weight)| Member {
+                addr: format!("member{}", i),
+                weight,
+                identity: format!("identity{}", i),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let reach = |percent, from_bottom| {
+        let res =
+            query_members_to_reach(deps.as_ref(), Decimal::percent(percent), from_bottom).unwrap();
+        (res.count, res.weight)
+    };
+
+    // a total of 20: the whale alone holds half
+    assert_eq!(reach(50, false), (1, 10));
+    assert_eq!(reach(51, false), (2, 15));
+    // it takes everyone else to match it
+    assert_eq!(reach(50, true), (5, 10));
+    assert_eq!(reach(10, true), (2, 2));
+    assert_eq!(reach(0, true), (0, 0));
+    assert_eq!(reach(100, true), (6, 20));
+
+    query_members_to_reach(deps.as_ref(), Decimal::percent(101), true).unwrap_err();
+}
+
+#[test]
+fn quantiles_split_by_weight() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: (1..=7)
+            .map(|weight| Member {
+                addr: format!("member{}", weight),
+                weight,
+                identity: format!("identity{}", weight),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let tiers = |n| {
+        query_quantiles(deps.as_ref(), n)
+            .unwrap()
+            .tiers
+            .into_iter()
+            .map(|tier| (tier.count, tier.weight))
+            .collect::<Vec<_>>()
+    };
+
+    // 7 members into 3 tiers: [1, 2], [3, 4], [5, 6, 7]
+    assert_eq!(tiers(3), vec![(2, 3), (2, 7), (3, 18)]);
+    assert_eq!(tiers(1), vec![(7, 28)]);
+    assert_eq!(tiers(7), (1..=7).map(|w| (1, w)).collect::<Vec<_>>());
+    // more tiers than members leaves some empty
+    assert_eq!(
+        tiers(10),
+        vec![
+            (0, 0),
+            (1, 1),
+            (1, 2),
+            (0, 0),
+            (1, 3),
+            (1, 4),
+            (0, 0),
+            (1, 5),
+            (1, 6),
+            (1, 7)
+        ]
+    );
+
+    query_quantiles(deps.as_ref(), 0).unwrap_err();
+    query_quantiles(deps.as_ref(), 101).unwrap_err();
+}
+
+#[test]
+fn minimal_coalition() {
+    let mut deps = mock_dependencies();
+    let member = |addr: &str, weight| Member {
+        addr: addr.into(),
+        weight,
+        identity: "identity".to_string(),
+    };
+    let msg = InstantiateMsg {
+        members: vec![
+            member("senator_a", 10),
+            member("senator_b", 30),
+            member("senator_c", 5),
+            member("senator_d", 40),
+            member("senator_e", 15),
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    // total weight is 100
+
+    let coalition = |deps: &OwnedDeps<_, _, _>, percent| {
+        let res = query_minimal_coalition(deps.as_ref(), Decimal::percent(percent)).unwrap();
+        let addrs: Vec<_> = res.members.into_iter().map(|m| m.addr).collect();
+        (addrs, res.weight)
+    };
+    assert_eq!(coalition(&deps, 0), (vec![], 0));
+    assert_eq!(coalition(&deps, 40), (vec!["senator_d".to_string()], 40));
+    assert_eq!(
+        coalition(&deps, 41),
+        (vec!["senator_d".to_string(), "senator_b".to_string()], 70)
+    );
+    assert_eq!(
+        coalition(&deps, 80),
+        (
+            vec![
+                "senator_d".to_string(),
+                "senator_b".to_string(),
+                "senator_e".to_string()
+            ],
+            85
+        )
+    );
+    let (addrs, weight) = coalition(&deps, 100);
+    assert_eq!(addrs.len(), 5);
+    assert_eq!(weight, 100);
+
+    // the index follows weight changes
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![member("senator_c", 45)],
+        remove: vec!["senator_d".into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(coalition(&deps, 40), (vec!["senator_c".to_string()], 45));
+
+    query_minimal_coalition(deps.as_ref(), Decimal::percent(101)).unwrap_err();
+}
+
+#[test]
+fn update_members_with_expected_total() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let update = |expected_total| ExecuteMsg::UpdateMembers {
+        remove: vec![USER2.into()],
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        expected_total,
+    };
+
+    // a stale total is rejected
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update(Some(20)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TotalMismatch {
+            expected: 20,
+            actual: 17
+        }
+    );
+    assert_users(&deps, Some(11), Some(6), None, None);
+
+    // the current one applies
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update(Some(17)),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), None, Some(4), None);
+}
+
+#[test]
+fn storage_stats_sum_key_and_value_sizes() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let stats = query_storage_stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.member_count, 2);
+    // "identity" and "identity_2"
+    assert_eq!(stats.total_identity_bytes, 18);
+    // weights: "somebody" + "11", "else" + "6"
+    // identity ids: "somebody" + "0", "else" + "1"
+    // interned identities: 18
+    assert_eq!(stats.estimated_bytes, 10 + 5 + 9 + 5 + 18);
+    assert!(!stats.truncated);
+
+    // a shared identity is only stored once
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 1,
+            identity: "identity".to_string(),
+        }],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let stats = query_storage_stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.member_count, 3);
+    assert_eq!(stats.total_identity_bytes, 26);
+    // "funny" + "1", "funny" + "0"
+    assert_eq!(stats.estimated_bytes, 47 + 6 + 6);
+}
+
+#[test]
+fn rewrite_identity_prefix() {
+    let mut deps = mock_dependencies();
+    let member = |addr: &str, identity: &str| Member {
+        addr: addr.into(),
+        weight: 1,
+        identity: identity.to_string(),
+    };
+    let msg = InstantiateMsg {
+        members: vec![
+            member("senator_a", "terra:alice"),
+            member("senator_b", "terra:bob"),
+            member("senator_c", "terra:bob"),
+            member("senator_d", "terra2:carol"),
+            member("senator_e", "osmo:dave"),
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let rewrite = ExecuteMsg::RewriteIdentities {
+        from_prefix: "terra:".into(),
+        to_prefix: "kujira:".into(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        rewrite.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    // identities are part of the member set, so a freeze blocks the rewrite
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        ExecuteMsg::Freeze {},
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        rewrite.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        ExecuteMsg::Unfreeze {},
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), admin_info, rewrite).unwrap();
+
+    let identity = |addr: &str| {
+        query_member(deps.as_ref(), addr.into(), None)
+            .unwrap()
+            .identity
+            .unwrap()
+    };
+    assert_eq!(identity("senator_a"), "kujira:alice");
+    assert_eq!(identity("senator_b"), "kujira:bob");
+    assert_eq!(identity("senator_c"), "kujira:bob");
+    assert_eq!(identity("senator_d"), "terra2:carol");
+    assert_eq!(identity("senator_e"), "osmo:dave");
+
+    // the identity index follows
+    let found: Vec<_> = query_search_by_identity_prefix(deps.as_ref(), "kujira:".into(), None)
+        .unwrap()
+        .members
+        .into_iter()
+        .map(|m| m.addr)
+        .collect();
+    assert_eq!(found, vec!["senator_a", "senator_b", "senator_c"]);
+    let found = query_search_by_identity_prefix(deps.as_ref(), "terra:".into(), None).unwrap();
+    assert!(found.members.is_empty());
+}
+
+#[test]
+fn rotate_control_replaces_admin_and_operators() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let new_admin = "new_admin";
+    for addr in ["operator1", "operator2"] {
+        let add = ExecuteMsg::AddOperator { addr: addr.into() };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), add).unwrap();
+    }
+
+    let rotate = ExecuteMsg::RotateControl {
+        new_admin: new_admin.into(),
+        new_operators: vec!["operator2".into(), "operator3".into()],
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator1", &[]),
+        rotate.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        rotate,
+    )
+    .unwrap();
+
+    assert_eq!(
+        ADMIN.query_admin(deps.as_ref()).unwrap().admin,
+        Some(new_admin.into())
+    );
+    let res: OperatorsResponse =
+        from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::ListOperators {}).unwrap()).unwrap();
+    assert_eq!(res.operators, vec!["operator2", "operator3"]);
+    assert_eq!(STATS.load(&deps.storage).unwrap().admin_changes, 1);
+
+    // the old admin and operators are locked out
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::AddOperator {
+            addr: "operator1".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator1", &[]),
+        ExecuteMsg::PruneExpired {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // while the new control set has access
+    for sender in [new_admin, "operator3"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::PruneExpired {},
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn operators_can_prune_but_not_update_members() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let operator = "operator";
+
+    let add_operator = ExecuteMsg::AddOperator {
+        addr: operator.into(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        add_operator.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        add_operator,
+    )
+    .unwrap();
+    let res: OperatorsResponse =
+        from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::ListOperators {}).unwrap()).unwrap();
+    assert_eq!(res.operators, vec![operator.to_string()]);
+
+    let expiry = ExecuteMsg::SetMemberExpiry {
+        addr: USER2.into(),
+        expires: Some(Expiration::AtHeight(mock_env().block.height + 5)),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        expiry,
+    )
+    .unwrap();
+
+    // members cannot prune
+    let mut env = mock_env();
+    env.block.height += 5;
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::PruneExpired {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // the operator can
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(operator, &[]),
+        ExecuteMsg::PruneExpired {},
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), None, None, None);
+
+    // but cannot change membership
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![USER1.into()],
+        add: vec![],
+        expected_total: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), mock_info(operator, &[]), msg).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    // and loses the role once removed
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::RemoveOperator {
+            addr: operator.into(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(operator, &[]),
+        ExecuteMsg::PruneExpired {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn can_add_reports_every_violation() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        require_identity: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let candidate = |addr: &str, weight, identity: &str| Member {
+        addr: addr.into(),
+        weight,
+        identity: identity.to_string(),
+    };
+
+    let res = query_can_add(deps.as_ref(), candidate(USER3, 10, "identity_3")).unwrap();
+    assert!(res.ok);
+    assert!(res.violations.is_empty());
+
+    // anonymous and over the max weight at once
+    let res = query_can_add(deps.as_ref(), candidate(USER3, 90, "")).unwrap();
+    assert!(!res.ok);
+    assert_eq!(
+        res.violations,
+        vec![
+            ContractError::IdentityRequired { addr: USER3.into() }.to_string(),
+            ContractError::MaxWeightExceeded {}.to_string(),
+        ]
+    );
+
+    // existing member, while frozen
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        ExecuteMsg::Freeze {},
+    )
+    .unwrap();
+    let res = query_can_add(deps.as_ref(), candidate(USER1, 1, "identity")).unwrap();
+    assert_eq!(
+        res.violations,
+        vec![
+            ContractError::Frozen {}.to_string(),
+            ContractError::AlreadyAMember { addr: USER1.into() }.to_string(),
+        ]
+    );
+
+    // locked seats, aliases and the share floor are reported like update_members fails
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    for msg in [
+        ExecuteMsg::Unfreeze {},
+        ExecuteMsg::LockMember { addr: USER1.into() },
+        ExecuteMsg::SetAlias {
+            alias: USER3.into(),
+            canonical: USER2.into(),
+        },
+        ExecuteMsg::SetMinMemberPercent {
+            percent: Some(Decimal::percent(10)),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    }
+    let res = query_can_add(deps.as_ref(), candidate(USER1, 11, "identity")).unwrap();
+    assert_eq!(
+        res.violations,
+        vec![
+            ContractError::AlreadyAMember { addr: USER1.into() }.to_string(),
+            ContractError::MemberLocked { addr: USER1.into() }.to_string(),
+        ]
+    );
+    let res = query_can_add(deps.as_ref(), candidate(USER3, 1, "identity_3")).unwrap();
+    assert_eq!(
+        res.violations,
+        vec![
+            ContractError::AddressIsAlias { addr: USER3.into() }.to_string(),
+            ContractError::BelowMinMemberPercent {
+                addr: USER3.into(),
+                min: Decimal::percent(10),
+            }
+            .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn hooks_fire_in_priority_order() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let hooks = [
+        ("notifier", None),
+        ("treasury", Some(10)),
+        ("archive", Some(-5)),
+        ("indexer", Some(0)),
+    ];
+    for (addr, priority) in hooks {
+        let msg = ExecuteMsg::AddHook {
+            addr: addr.into(),
+            priority,
+        };
+        execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 5,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    let targets: Vec<_> = res
+        .messages
+        .iter()
+        .map(|sub| match &sub.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => contract_addr.as_str(),
+            msg => panic!("unexpected message {:?}", msg),
+        })
+        .collect();
+    // equal priorities keep registration order
+    assert_eq!(targets, vec!["treasury", "notifier", "indexer", "archive"]);
+
+    // removing a hook drops its priority
+    let remove = ExecuteMsg::RemoveHook {
+        addr: "treasury".into(),
+    };
+    execute(deps.as_mut(), mock_env(), admin_info, remove).unwrap();
+    assert_eq!(
+        HOOK_PRIORITY
+            .may_load(&deps.storage, &Addr::unchecked("treasury"))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn list_shares_pages_sum_to_total() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let mut env = mock_env();
+    let before = env.block.height;
+    env.block.height += 1;
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 3,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+    // USER2 (6) and USER3 (3) of 20
+    let page = query_list_shares(deps.as_ref(), None, Some(2), None).unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.total_weight, 20);
+    let sum = page.shares.iter().fold(Decimal::zero(), |s, m| s + m.share);
+    assert_eq!(sum, Decimal::percent(45));
+
+    let last = page.shares.last().unwrap().addr.clone();
+    let rest = query_list_shares(deps.as_ref(), Some(last), Some(2), None).unwrap();
+    assert!(!rest.has_more);
+    assert_eq!(rest.shares.len(), 1);
+    assert_eq!(rest.shares[0].share, Decimal::percent(55));
+
+    // before USER3 joined, the same page covers the two original members of 17
+    let page = query_list_shares(deps.as_ref(), None, Some(2), Some(before + 1)).unwrap();
+    assert!(!page.has_more);
+    assert_eq!(page.total_weight, 17);
+    let addrs: Vec<_> = page.shares.iter().map(|m| m.addr.as_str()).collect();
+    assert_eq!(addrs, vec![USER2, USER1]);
+    assert_eq!(page.shares[1].share, Decimal::from_ratio(11u64, 17u64));
+
+    // a group without weight has no shares to hand out
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![Member {
+            addr: USER1.into(),
+            weight: 0,
+            identity: "identity".to_string(),
+        }],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let page = query_list_shares(deps.as_ref(), None, None, None).unwrap();
+    assert_eq!(page.total_weight, 0);
+    assert_eq!(page.shares[0].share, Decimal::zero());
+}
+
+#[test]
+fn list_shares_includes_members_removed_since() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let mut env = mock_env();
+    let before = env.block.height;
+    env.block.height += 1;
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+    // one member per page still covers both members of 17 before the removal
+    let page = query_list_shares(deps.as_ref(), None, Some(1), Some(before + 1)).unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.total_weight, 17);
+    assert_eq!(page.shares[0].addr, USER2);
+    let last = page.shares[0].addr.clone();
+    let rest = query_list_shares(deps.as_ref(), Some(last), Some(1), Some(before + 1)).unwrap();
+    assert!(!rest.has_more);
+    assert_eq!(rest.shares[0].addr, USER1);
+    assert_eq!(rest.shares[0].weight, 11);
+    let weights: u64 = page
+        .shares
+        .iter()
+        .chain(rest.shares.iter())
+        .map(|m| m.weight)
+        .sum();
+    assert_eq!(weights, 17);
+
+    // the current page only has the remaining member
+    let page = query_list_shares(deps.as_ref(), None, None, None).unwrap();
+    assert_eq!(page.shares.len(), 1);
+    assert_eq!(page.total_weight, 6);
+}
+
+#[test]
+fn aliases_mirror_canonical_member() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let cold = "somebody-cold";
+    let set_alias = |alias: &str, canonical: &str| ExecuteMsg::SetAlias {
+        alias: alias.into(),
+        canonical: canonical.into(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set_alias(cold, USER1),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_alias(cold, USER1),
+    )
+    .unwrap();
+
+    // the alias answers with the canonical member's seat
+    let via_alias = query_member(deps.as_ref(), cold.into(), None).unwrap();
+    let direct = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(via_alias, direct);
+    assert_eq!(via_alias.weight, Some(11));
+    assert_eq!(via_alias.identity, Some("identity".to_string()));
+
+    // and follows it through updates, without adding weight
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER1.into(),
+            weight: 15,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), update).unwrap();
+    let via_alias = query_member(deps.as_ref(), cold.into(), None).unwrap();
+    assert_eq!(via_alias.weight, Some(15));
+    assert_users(&deps, Some(15), Some(6), None, None);
+
+    // no chains, cycles, member aliases or aliases of non-members
+    let attempts = vec![
+        (
+            set_alias("other-cold", cold),
+            ContractError::NotAMember { addr: cold.into() },
+        ),
+        (
+            set_alias(USER2, USER1),
+            ContractError::AlreadyAMember { addr: USER2.into() },
+        ),
+        (
+            set_alias(USER1, USER1),
+            ContractError::AlreadyAMember { addr: USER1.into() },
+        ),
+        (
+            set_alias(USER1, USER3),
+            ContractError::NotAMember { addr: USER3.into() },
+        ),
+    ];
+    for (msg, expected) in attempts {
+        let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(err, expected);
+    }
+
+    // an alias cannot take a seat of its own
+    let add_cold = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: cold.into(),
+            weight: 1,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        add_cold.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AddressIsAlias { addr: cold.into() });
+
+    // removing the canonical member drops its aliases
+    let remove_user1 = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), remove_user1).unwrap();
+    assert!(!ALIASES.has(&deps.storage, &Addr::unchecked(cold)));
+    let via_alias = query_member(deps.as_ref(), cold.into(), None).unwrap();
+    assert_eq!(via_alias.weight, None);
+
+    // after which the address can join and answers for its own seat
+    execute(deps.as_mut(), mock_env(), admin_info, add_cold).unwrap();
+    let own = query_member(deps.as_ref(), cold.into(), None).unwrap();
+    assert_eq!(own.weight, Some(1));
+}
+
+#[test]
+fn locked_members_resist_changes() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let is_locked = |deps: &OwnedDeps<_, _, _>, addr: &str| -> bool {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IsLocked { addr: addr.into() },
+        )
+        .unwrap();
+        from_slice::<IsLockedResponse>(&res).unwrap().locked
+    };
+
+    let lock = ExecuteMsg::LockMember { addr: USER1.into() };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        lock.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), lock).unwrap();
+    assert!(is_locked(&deps, USER1));
+    assert!(!is_locked(&deps, USER2));
+
+    let locked_err = ContractError::MemberLocked { addr: USER1.into() };
+    let update = |add: Vec<(&str, u64)>, remove: Vec<&str>| ExecuteMsg::UpdateMembers {
+        add: add
+            .into_iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.into(),
+                weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        remove: remove.into_iter().map(String::from).collect(),
+        expected_total: None,
+    };
+    let attempts = vec![
+        update(vec![(USER1, 20)], vec![]),
+        update(vec![], vec![USER1]),
+        ExecuteMsg::ApplyDeltas {
+            deltas: vec![(USER1.into(), 1)],
+        },
+    ];
+    for msg in attempts {
+        let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(err, locked_err);
+    }
+
+    // others remain editable
+    let msg = update(vec![(USER2, 7)], vec![]);
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    assert_users(&deps, Some(11), Some(7), None, None);
+
+    // once unlocked, the seat can change again
+    let unlock = ExecuteMsg::UnlockMember { addr: USER1.into() };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), unlock).unwrap();
+    assert!(!is_locked(&deps, USER1));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info,
+        update(vec![], vec![USER1]),
+    )
+    .unwrap();
+    assert_users(&deps, None, Some(7), None, None);
+}
+
+#[test]
+fn locked_admin_seat_cannot_be_transferred() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        admin: Some(USER1.into()),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let admin_info = mock_info(USER1, &[]);
+
+    let lock = ExecuteMsg::LockMember { addr: USER1.into() };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), lock).unwrap();
+    let transfer = ExecuteMsg::TransferAdminAndSeat {
+        new_addr: USER3.into(),
+        identity: "identity_3".to_string(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, transfer).unwrap_err();
+    assert_eq!(err, ContractError::MemberLocked { addr: USER1.into() });
+}
+
+#[test]
+fn churn_within_window() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let update = |deps: &mut OwnedDeps<_, _, _>, height, add: Vec<(&str, u64)>, remove: &[&str]| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::UpdateMembers {
+            add: add
+                .into_iter()
+                .map(|(addr, weight)| Member {
+                    addr: addr.into(),
+                    weight,
+                    identity: format!("{}_id", addr),
+                })
+                .collect(),
+            remove: remove.iter().map(|a| a.to_string()).collect(),
+            expected_total: None,
+        };
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    };
+    update(&mut deps, start + 1, vec![(USER3, 5), (USER1, 20)], &[]);
+    update(&mut deps, start + 2, vec![], &[USER2]);
+    update(&mut deps, start + 3, vec![], &[USER3]);
+
+    let churn = |from, to| query_churn(deps.as_ref(), from, to, None, None).unwrap();
+    assert_eq!(
+        churn(start, start),
+        ChurnResponse {
+            added: 2,
+            removed: 0,
+            net_weight_change: 17,
+            last: None,
+        }
+    );
+    assert_eq!(
+        churn(start + 1, start + 2),
+        ChurnResponse {
+            added: 1,
+            removed: 1,
+            net_weight_change: 8,
+            last: None,
+        }
+    );
+    assert_eq!(
+        churn(start + 1, start + 3),
+        ChurnResponse {
+            added: 1,
+            removed: 2,
+            net_weight_change: 3,
+            last: None,
+        }
+    );
+    assert_eq!(
+        churn(start + 3, start + 10),
+        ChurnResponse {
+            added: 0,
+            removed: 1,
+            net_weight_change: -5,
+            last: None,
+        }
+    );
+    // the whole history nets out to the current total
+    assert_eq!(churn(0, start + 10).net_weight_change, 20);
+
+    // or page by page, in address order: else, funny, somebody
+    let page = |start_after: Option<&str>| {
+        query_churn(
+            deps.as_ref(),
+            0,
+            start + 10,
+            start_after.map(String::from),
+            Some(2),
+        )
+        .unwrap()
+    };
+    let first = page(None);
+    assert_eq!(first.last, Some(USER3.to_string()));
+    let second = page(first.last.as_deref());
+    assert_eq!(second.last, None);
+    assert_eq!(first.added + second.added, 3);
+    assert_eq!(first.removed + second.removed, 2);
+    assert_eq!(first.net_weight_change + second.net_weight_change, 20);
+}
+
+#[test]
+fn allocate_from_budget() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let remaining = |deps: &OwnedDeps<_, _, _>| {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RemainingBudget {}).unwrap();
+        from_slice::<RemainingBudgetResponse>(&res)
+            .unwrap()
+            .remaining
+    };
+    let allocate = |addr: &str, amount| ExecuteMsg::AllocateFromBudget {
+        addr: addr.into(),
+        amount,
+    };
+    assert_eq!(remaining(&deps), 0);
+
+    let set = ExecuteMsg::SetBudget { amount: 20 };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), set).unwrap();
+    assert_eq!(remaining(&deps), 20);
+
+    // tops up an existing member, keeping their identity
+    let mut env = mock_env();
+    env.block.height += 1;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        allocate(USER2, 4),
+    )
+    .unwrap();
+    // and adds a new one
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        allocate(USER3, 16),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(10), Some(16), None);
+    let identity = query_member(deps.as_ref(), USER2.into(), None)
+        .unwrap()
+        .identity;
+    assert_eq!(identity.as_deref(), Some("identity_2"));
+    assert_eq!(remaining(&deps), 0);
+
+    // nothing left to hand out
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        allocate(USER1, 1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientBudget { remaining: 0 });
+
+    let set = ExecuteMsg::SetBudget { amount: 5 };
+    execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), set).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(INIT_ADMIN, &[]),
+        allocate(USER1, 6),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientBudget { remaining: 5 });
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(INIT_ADMIN, &[]),
+        allocate(USER1, 5),
+    )
+    .unwrap();
+    assert_users(&deps, Some(16), Some(10), Some(16), None);
+    assert_eq!(remaining(&deps), 0);
+}
+
+#[test]
+fn is_majority_around_the_boundary() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let is_majority = |addrs: &[&str], percent: u64, height: Option<u64>| {
+        query_is_majority(
+            deps.as_ref(),
+            addrs.iter().map(|a| a.to_string()).collect(),
+            Decimal::percent(percent),
+            height,
+        )
+        .unwrap()
+    };
+
+    // USER2 holds 6 of 17, about 35.3%
+    let res = is_majority(&[USER2], 30, None);
+    assert_eq!(
+        res,
+        IsMajorityResponse {
+            is_majority: true,
+            their_weight: 6,
+            total_weight: 17,
+        }
+    );
+    assert!(!is_majority(&[USER2], 36, None).is_majority);
+    // non-members and repeats add nothing
+    assert!(!is_majority(&[USER2, USER2, USER3], 36, None).is_majority);
+    assert!(is_majority(&[USER1, USER2], 100, None).their_weight == 17);
+
+    // exactly at the fraction is not enough
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 5,
+                identity: "identity".to_string(),
+            },
+            Member {
+                addr: USER2.into(),
+                weight: 5,
+                identity: "identity_2".to_string(),
+            },
+        ],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let majority = |deps: &OwnedDeps<_, _, _>, percent| {
+        query_is_majority(
+            deps.as_ref(),
+            vec![USER1.into()],
+            Decimal::percent(percent),
+            None,
+        )
+        .unwrap()
+        .is_majority
+    };
+    assert!(majority(&deps, 49));
+    assert!(!majority(&deps, 50));
+    assert!(!majority(&deps, 51));
+
+    // the list is bounded like a page
+    let addrs = vec![USER1.to_string(); 31];
+    query_is_majority(deps.as_ref(), addrs, Decimal::percent(50), None).unwrap_err();
+
+    // past heights use the weights of the time
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER1.into(),
+            weight: 15,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let mut env = mock_env();
+    env.block.height += 2;
+    execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), add).unwrap();
+    assert!(majority(&deps, 50));
+    let before = query_is_majority(
+        deps.as_ref(),
+        vec![USER1.into()],
+        Decimal::percent(50),
+        Some(mock_env().block.height + 1),
+    )
+    .unwrap();
+    assert!(!before.is_majority);
+    assert_eq!((before.their_weight, before.total_weight), (5, 10));
+}
+
+#[test]
+fn intersect_members_with_address_set() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let addrs = |addrs: &[&str]| addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+    let res = query_intersect_members(
+        deps.as_ref(),
+        addrs(&[USER3, USER2, "outsider", USER1, USER2]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.members,
+        vec![
+            Member {
+                addr: USER2.into(),
+                weight: 6,
+                identity: "identity_2".to_string(),
+            },
+            Member {
+                addr: USER1.into(),
+                weight: 11,
+                identity: "identity".to_string(),
+            },
+        ]
+    );
+    let res = query_intersect_members(deps.as_ref(), addrs(&[USER3])).unwrap();
+    assert!(res.members.is_empty());
+
+    let too_many = (0..31).map(|i| format!("addr{}", i)).collect();
+    query_intersect_members(deps.as_ref(), too_many).unwrap_err();
+}
+
+#[test]
+fn total_weight_excluding_members_at_height() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let mut env = mock_env();
+    let before = env.block.height + 1;
+    env.block.height = before;
+
+    // USER2 leaves and USER3 joins
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    let excluding = |deps: &OwnedDeps<_, _, _>, addrs: &[&str], height| {
+        let addrs = addrs.iter().map(|a| a.to_string()).collect();
+        query_total_weight_excluding(deps.as_ref(), addrs, height)
+            .unwrap()
+            .weight
+    };
+
+    // now: USER1 (11) + USER3 (4)
+    assert_eq!(excluding(&deps, &[], None), 15);
+    assert_eq!(excluding(&deps, &[USER1], None), 4);
+    // former members and duplicates are not subtracted again
+    assert_eq!(excluding(&deps, &[USER2, USER3, USER3], None), 11);
+
+    // before the change: USER1 (11) + USER2 (6)
+    assert_eq!(excluding(&deps, &[USER2], Some(before)), 11);
+    assert_eq!(excluding(&deps, &[USER3, USER1], Some(before)), 6);
+    assert_eq!(excluding(&deps, &[USER1, USER2], Some(before)), 0);
+
+    // the list is bounded like a page
+    let addrs = vec![USER1.to_string(); 31];
+    query_total_weight_excluding(deps.as_ref(), addrs, None).unwrap_err();
+}
+
+#[test]
+fn health_of_fresh_group() {
+    let mut deps = mock_dependencies();
+    let health = query_health(deps.as_ref()).unwrap();
+    assert!(!health.initialized);
+    assert!(!health.within_bounds);
+
+    do_instantiate(deps.as_mut());
+    let health = query_health(deps.as_ref()).unwrap();
+    assert_eq!(
+        health,
+        HealthResponse {
+            initialized: true,
+            total_weight: 17,
+            member_count: 2,
+            within_bounds: true,
+            frozen: false,
+        }
+    );
+}
+
+#[test]
+fn headroom_report_near_bounds() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    // 17 of 100, with an average member weight of 8
+    assert_eq!(
+        query_headroom_report(deps.as_ref()).unwrap(),
+        HeadroomReportResponse {
+            weight_to_max: 83,
+            weight_to_min: 17,
+            members_to_max_count: Some(10),
+        }
+    );
+
+    // close to both bounds, not even an average member fits
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        min_weight: 15,
+        max_weight: 20,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    assert_eq!(
+        query_headroom_report(deps.as_ref()).unwrap(),
+        HeadroomReportResponse {
+            weight_to_max: 3,
+            weight_to_min: 2,
+            members_to_max_count: Some(0),
+        }
+    );
+
+    // exactly at both bounds
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        min_weight: 17,
+        max_weight: 17,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let report = query_headroom_report(deps.as_ref()).unwrap();
+    assert_eq!(report.weight_to_max, 0);
+    assert_eq!(report.weight_to_min, 0);
+
+    // an empty group has no average to project from
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let report = query_headroom_report(deps.as_ref()).unwrap();
+    assert_eq!(report.weight_to_max, 100);
+    assert_eq!(report.members_to_max_count, None);
+}
+
+#[test]
+fn packed_members_round_trip() {
+    let members = vec![
+        Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        },
+        Member {
+            addr: "addr4".into(),
+            weight: u64::MAX,
+            identity: "".to_string(),
+        },
+    ];
+    let data = packed::encode(&members).unwrap();
+    // 2 + 5 + 8 + 2 + 10 and 2 + 5 + 8 + 2 + 0
+    assert_eq!(data.len(), 44);
+    assert_eq!(packed::decode(&data).unwrap(), members);
+    assert_eq!(packed::decode(&[]).unwrap(), vec![]);
+
+    // truncated data is rejected
+    let err = packed::decode(&data[..data.len() - 1]).unwrap_err();
+    assert!(matches!(err, StdError::ParseErr { .. }));
+}
+
+#[test]
+fn import_packed_members() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+
+    let members = vec![
+        Member {
+            addr: USER1.into(),
+            weight: 3,
+            identity: "identity".to_string(),
+        },
+        Member {
+            addr: USER3.into(),
+            weight: 5,
+            identity: "identity_3".to_string(),
+        },
+    ];
+    let msg = ExecuteMsg::ImportPacked {
+        data: Binary::from(packed::encode(&members).unwrap()),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_users(&deps, Some(3), Some(6), Some(5), None);
+    let total = query_total_weight(deps.as_ref(), None).unwrap();
+    assert_eq!(total.weight, 14);
+
+    // caps apply as for UpdateMembers
+    let heavy = vec![Member {
+        addr: USER3.into(),
+        weight: 100,
+        identity: "identity_3".to_string(),
+    }];
+    let msg = ExecuteMsg::ImportPacked {
+        data: Binary::from(packed::encode(&heavy).unwrap()),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::MaxWeightExceeded {});
+}
+
+#[test]
+fn member_rank_breaks_ties_by_address() {
+    let mut deps = mock_dependencies();
+    let members = [
+        ("addr1", 5),
+        ("addr2", 9),
+        ("addr3", 5),
+        ("addr4", 1),
+        ("addr0", 5),
+    ];
+    let msg = InstantiateMsg {
+        members: members
+            .iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.to_string(),
+                weight: *weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let rank = |addr: &str| query_member_rank(deps.as_ref(), addr.into()).unwrap();
+    assert_eq!(rank("addr2"), MemberRankResponse { rank: 1, out_of: 5 });
+    assert_eq!(rank("addr0").rank, 2);
+    assert_eq!(rank("addr1").rank, 3);
+    assert_eq!(rank("addr3").rank, 4);
+    assert_eq!(rank("addr4").rank, 5);
+
+    query_member_rank(deps.as_ref(), USER3.into()).unwrap_err();
+}
+
+#[test]
+fn group_info_round_trips() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let group_info = |deps: &OwnedDeps<_, _, _>| -> GroupInfo {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GroupInfo {}).unwrap();
+        from_slice(&res).unwrap()
+    };
+    assert_eq!(group_info(&deps), GroupInfo::default());
+
+    let msg = ExecuteMsg::SetGroupInfo {
+        name: "Senate".to_string(),
+        description: "Validators voting on chain upgrades".to_string(),
+        url: Some("https://kujira.network".to_string()),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        group_info(&deps),
+        GroupInfo {
+            name: "Senate".to_string(),
+            description: "Validators voting on chain upgrades".to_string(),
+            url: Some("https://kujira.network".to_string()),
+        }
+    );
+
+    let msg = ExecuteMsg::SetGroupInfo {
+        name: "".to_string(),
+        description: "".to_string(),
+        url: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidLength {
+            field: "name".to_string(),
+            min: 1,
+            max: 64
+        }
+    );
+
+    let msg = ExecuteMsg::SetGroupInfo {
+        name: "Senate".to_string(),
+        description: "x".repeat(1025),
+        url: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidLength { .. }));
+}
+
+#[test]
+fn cumulative_weight_of_top_members() {
+    let mut deps = mock_dependencies();
+    let weights = [
+        ("addr1", 5),
+        ("addr2", 20),
+        ("addr3", 5),
+        ("addr4", 1),
+        ("addr5", 9),
+    ];
+    let msg = InstantiateMsg {
+        members: weights
+            .iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.to_string(),
+                weight: *weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let cumulative = |top_n| query_cumulative_weight(deps.as_ref(), top_n).unwrap();
+    assert_eq!(cumulative(0).weight, 0);
+    assert_eq!(cumulative(1).weight, 20);
+    assert_eq!(cumulative(2).weight, 29);
+    assert_eq!(cumulative(4).weight, 39);
+    // capped at the member count
+    assert_eq!(
+        cumulative(100),
+        CumulativeWeightResponse {
+            top_n: 5,
+            weight: 40
+        }
+    );
+}
+
+#[test]
+fn vesting_ramps_weight_linearly() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height + 10;
+    let schedule = VestingSchedule {
+        start_height: start,
+        end_height: start + 100,
+        start_weight: 10,
+        end_weight: 60,
+    };
+
+    let set = ExecuteMsg::SetVesting {
+        addr: USER1.into(),
+        schedule: Some(VestingSchedule {
+            end_height: start,
+            ..schedule.clone()
+        }),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), set).unwrap_err();
+    assert_eq!(err, ContractError::InvalidVestingSchedule {});
+    let set = ExecuteMsg::SetVesting {
+        addr: USER1.into(),
+        schedule: Some(schedule),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), set).unwrap();
+
+    let vested = |deps: &OwnedDeps<_, _, _>, height| -> Option<u64> {
+        let msg = QueryMsg::VestedWeight {
+            addr: USER1.into(),
+            at_height: Some(height),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        from_slice::<VestedWeightResponse>(&res).unwrap().weight
+    };
+    assert_eq!(vested(&deps, start - 5), Some(10));
+    assert_eq!(vested(&deps, start), Some(10));
+    assert_eq!(vested(&deps, start + 33), Some(26));
+    assert_eq!(vested(&deps, start + 50), Some(35));
+    assert_eq!(vested(&deps, start + 100), Some(60));
+    assert_eq!(vested(&deps, start + 500), Some(60));
+
+    // cranking writes the vested weight into the snapshots
+    let crank = |deps: &mut OwnedDeps<_, _, _>, height| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::CrankVesting {
+            addrs: vec![USER1.into(), USER2.into()],
+        };
+        execute(deps.as_mut(), env, mock_info(USER3, &[]), msg).unwrap();
+    };
+    crank(&mut deps, start);
+    assert_users(&deps, Some(10), Some(6), None, None);
+    crank(&mut deps, start + 50);
+    assert_users(&deps, Some(35), Some(6), None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 41);
+    crank(&mut deps, start + 100);
+    assert_users(&deps, Some(60), Some(6), None, None);
+
+    let member = query_member(deps.as_ref(), USER1.into(), Some(start + 51)).unwrap();
+    assert_eq!(member.weight, Some(35));
+}
+
+#[test]
+fn member_snapshot_bounds_follow_changes() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let update = |deps: &mut OwnedDeps<_, _, _>, height, weight| {
+        let mut env = mock_env();
+        env.block.height = height;
+        let msg = ExecuteMsg::UpdateMembers {
+            add: vec![Member {
+                addr: USER3.into(),
+                weight,
+                identity: "identity_3".to_string(),
+            }],
+            remove: vec![],
+            expected_total: None,
+        };
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    };
+
+    let bounds = query_member_snapshot_bounds(deps.as_ref(), USER3.into()).unwrap();
+    assert_eq!(bounds.first_height, None);
+    assert_eq!(bounds.last_height, None);
+
+    update(&mut deps, start + 3, 4);
+    update(&mut deps, start + 8, 7);
+    let bounds = query_member_snapshot_bounds(deps.as_ref(), USER3.into()).unwrap();
+    assert_eq!(bounds.first_height, Some(start + 3));
+    assert_eq!(bounds.last_height, Some(start + 8));
+
+    // members from instantiation start at that height
+    let bounds = query_member_snapshot_bounds(deps.as_ref(), USER1.into()).unwrap();
+    assert_eq!(bounds.first_height, Some(start));
+    assert_eq!(bounds.last_height, Some(start));
+}
+
+#[test]
+fn diff_from_checkpoint() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let start = mock_env().block.height;
+    let save = |name: &str| ExecuteMsg::SaveCheckpoint { name: name.into() };
+
+    let err = execute(
+        deps.as_mut(),
+        at(start + 1),
+        mock_info(USER1, &[]),
+        save("baseline"),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let err = execute(deps.as_mut(), at(start + 1), admin_info.clone(), save("")).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidLength { .. }));
+    execute(
+        deps.as_mut(),
+        at(start + 1),
+        admin_info.clone(),
+        save("baseline"),
+    )
+    .unwrap();
+
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![
+            Member {
+                addr: USER3.into(),
+                weight: 4,
+                identity: "identity".to_string(),
+            },
+            Member {
+                addr: USER1.into(),
+                weight: 12,
+                identity: "identity".to_string(),
+            },
+        ],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), at(start + 2), admin_info.clone(), update).unwrap();
+
+    let diff = |deps: &OwnedDeps<_, _, _>, height, start_after: Option<&str>, limit| {
+        query_diff_from_checkpoint(
+            deps.as_ref(),
+            &at(height),
+            "baseline".into(),
+            start_after.map(String::from),
+            limit,
+        )
+        .unwrap()
+        .changes
+    };
+    assert_eq!(
+        diff(&deps, start + 3, None, None),
+        vec![
+            MemberDiff::new(USER3, None, Some(4)),
+            MemberDiff::new(USER1, Some(11), Some(12)),
+        ]
+    );
+    assert_eq!(
+        diff(&deps, start + 3, Some(USER3), Some(1)),
+        vec![MemberDiff::new(USER1, Some(11), Some(12))]
+    );
+
+    // changes from the current block are part of the diff
+    let remove = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), at(start + 3), admin_info, remove).unwrap();
+    assert_eq!(
+        diff(&deps, start + 3, None, Some(1)),
+        vec![MemberDiff::new(USER2, Some(6), None)]
+    );
+
+    query_diff_from_checkpoint(deps.as_ref(), &at(start + 3), "unknown".into(), None, None)
+        .unwrap_err();
+}
+
+#[test]
+fn changed_members_between_heights() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let update =
+        |deps: &mut OwnedDeps<_, _, _>, height, add: Vec<(&str, u64)>, remove: Vec<&str>| {
+            let mut env = mock_env();
+            env.block.height = height;
+            let msg = ExecuteMsg::UpdateMembers {
+                add: add
+                    .into_iter()
+                    .map(|(addr, weight)| Member {
+                        addr: addr.into(),
+                        weight,
+                        identity: "identity".to_string(),
+                    })
+                    .collect(),
+                remove: remove.into_iter().map(String::from).collect(),
+                expected_total: None,
+            };
+            execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        };
+    // USER3 joins, USER2 leaves, USER1 changes and changes back
+    update(&mut deps, start + 2, vec![(USER3, 4), (USER1, 12)], vec![]);
+    update(&mut deps, start + 4, vec![(USER1, 11)], vec![USER2]);
+
+    let changed = |from, to, start_after: Option<&str>, limit| {
+        query_changed_members(
+            deps.as_ref(),
+            from,
+            to,
+            start_after.map(String::from),
+            limit,
+        )
+        .unwrap()
+        .changes
+    };
+    // ordered by address: else (USER2), funny (USER3), somebody (USER1)
+    assert_eq!(
+        changed(start + 1, start + 5, None, None),
+        vec![
+            MemberDiff::new(USER2, Some(6), None),
+            MemberDiff::new(USER3, None, Some(4)),
+        ]
+    );
+    assert_eq!(
+        changed(start + 1, start + 3, None, None),
+        vec![
+            MemberDiff::new(USER3, None, Some(4)),
+            MemberDiff::new(USER1, Some(11), Some(12)),
+        ]
+    );
+    // paginated
+    assert_eq!(
+        changed(start + 1, start + 5, None, Some(1)),
+        vec![MemberDiff::new(USER2, Some(6), None)]
+    );
+    assert_eq!(
+        changed(start + 1, start + 5, Some(USER2), Some(1)),
+        vec![MemberDiff::new(USER3, None, Some(4))]
+    );
+    assert!(changed(start + 5, start + 9, None, None).is_empty());
+
+    // the limit bounds the addresses checked, not the changes found
+    let page = query_changed_members(deps.as_ref(), start + 3, start + 5, None, Some(2)).unwrap();
+    assert_eq!(page.changes, vec![MemberDiff::new(USER2, Some(6), None)]);
+    assert_eq!(page.last, Some(USER3.to_string()));
+    let rest =
+        query_changed_members(deps.as_ref(), start + 3, start + 5, page.last, Some(2)).unwrap();
+    assert_eq!(
+        rest.changes,
+        vec![MemberDiff::new(USER1, Some(12), Some(11))]
+    );
+    assert_eq!(rest.last, None);
+}
+
+#[test]
+fn expired_group_dissolves_and_rejects_changes() {
+    let mut deps = mock_dependencies();
+    let expires = mock_env().block.height + 10;
+    let msg = InstantiateMsg {
+        expires_at: Some(Expiration::AtHeight(expires)),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let at = |height| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 1,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+
+    // before expiry nothing happens and changes still go through
+    let res = execute(
+        deps.as_mut(),
+        at(expires - 1),
+        mock_info(USER1, &[]),
+        ExecuteMsg::CheckExpiry {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("expired", "false"));
+    execute(
+        deps.as_mut(),
+        at(expires - 1),
+        mock_info(INIT_ADMIN, &[]),
+        update.clone(),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(1), None);
+
+    // once expired, changes are rejected even before anyone checks
+    let err = execute(
+        deps.as_mut(),
+        at(expires),
+        mock_info(INIT_ADMIN, &[]),
+        update.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractExpired {});
+    let res = query(deps.as_ref(), at(expires), QueryMsg::Expiration {}).unwrap();
+    let expiration: ExpirationResponse = from_slice(&res).unwrap();
+    assert!(expiration.expired);
+
+    // anyone can dissolve it
+    execute(
+        deps.as_mut(),
+        at(expires),
+        mock_info(USER2, &[]),
+        ExecuteMsg::CheckExpiry {},
+    )
+    .unwrap();
+    assert_users(&deps, None, None, None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 0);
+    let err = execute(
+        deps.as_mut(),
+        at(expires + 1),
+        mock_info(INIT_ADMIN, &[]),
+        update,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractExpired {});
+}
+
+#[test]
+fn weight_to_enter_top_n() {
+    let mut deps = mock_dependencies();
+    let weights = [("addr1", 5), ("addr2", 20), ("addr3", 5), ("addr4", 1)];
+    let msg = InstantiateMsg {
+        members: weights
+            .iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.to_string(),
+                weight: *weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let bar = |n| query_weight_to_enter_top(deps.as_ref(), n).unwrap().weight;
+    assert_eq!(bar(1), 21);
+    // the 2nd and 3rd member tie at 5
+    assert_eq!(bar(2), 6);
+    assert_eq!(bar(3), 6);
+    // exactly as many members as places
+    assert_eq!(bar(4), 2);
+    // more places than members
+    assert_eq!(bar(5), 0);
+    query_weight_to_enter_top(deps.as_ref(), 0).unwrap_err();
+}
+
+#[test]
+fn admin_change_waits_for_notice_period() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let at = |height| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let admin = mock_info(INIT_ADMIN, &[]);
+    let ready_at = |deps: &OwnedDeps<_, _, _>| {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::AdminChangeReadyAt {}).unwrap();
+        from_slice::<AdminChangeReadyAtResponse>(&res).unwrap()
+    };
+
+    let notice = ExecuteMsg::SetAdminChangeNotice { blocks: Some(10) };
+    execute(deps.as_mut(), at(start), admin.clone(), notice).unwrap();
+    // no more immediate hand-overs
+    let update = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER3.into()),
+    };
+    let err = execute(deps.as_mut(), at(start), admin.clone(), update).unwrap_err();
+    assert_eq!(err, ContractError::AdminChangeNeedsNotice {});
+
+    let propose = ExecuteMsg::ProposeAdmin { addr: USER3.into() };
+    let err = execute(
+        deps.as_mut(),
+        at(start),
+        mock_info(USER1, &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), at(start + 1), admin, propose).unwrap();
+    assert_eq!(
+        ready_at(&deps),
+        AdminChangeReadyAtResponse {
+            proposed: Some(USER3.into()),
+            ready_at: Some(start + 11),
+        }
+    );
+
+    // too early
+    let accept = ExecuteMsg::AcceptAdmin {};
+    let err = execute(
+        deps.as_mut(),
+        at(start + 10),
+        mock_info(USER3, &[]),
+        accept.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoticePeriodNotMet {
+            ready_at: start + 11
+        }
+    );
+    // only by the proposed address
+    let err = execute(
+        deps.as_mut(),
+        at(start + 11),
+        mock_info(USER1, &[]),
+        accept.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        at(start + 11),
+        mock_info(USER3, &[]),
+        accept.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        ADMIN.get(deps.as_ref()).unwrap(),
+        Some(Addr::unchecked(USER3))
+    );
+    assert_eq!(
+        ready_at(&deps),
+        AdminChangeReadyAtResponse {
+            proposed: None,
+            ready_at: None,
+        }
+    );
+    let err = execute(deps.as_mut(), at(start + 12), mock_info(USER3, &[]), accept).unwrap_err();
+    assert_eq!(err, ContractError::NoAdminProposal {});
+}
+
+#[test]
+fn resolved_weight_combines_delegation_and_freeze() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let mut env = mock_env();
+    env.block.height = start + 5;
+    let admin = mock_info(INIT_ADMIN, &[]);
+    let resolved = |deps: &OwnedDeps<_, _, _>, addr: &str, height| {
+        query_resolved_weight(deps.as_ref(), &env, addr.into(), height).unwrap()
+    };
+
+    let delegate = ExecuteMsg::DelegatePartial {
+        to: USER2.into(),
+        amount: 4,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), delegate).unwrap();
+    assert_eq!(
+        resolved(&deps, USER2, None),
+        ResolvedWeightResponse {
+            weight: 6,
+            delegated_out: 0,
+            delegated_in: 4,
+            frozen: false,
+            expired: false,
+            resolved: 10,
+        }
+    );
+    assert_eq!(resolved(&deps, USER1, None).resolved, 7);
+
+    // a frozen delegator passes nothing on, and controls nothing itself
+    let freeze = |addr: &str, frozen| ExecuteMsg::SetMemberFrozen {
+        addr: addr.into(),
+        frozen,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin.clone(),
+        freeze(USER1, true),
+    )
+    .unwrap();
+    assert_eq!(
+        resolved(&deps, USER1, None),
+        ResolvedWeightResponse {
+            weight: 11,
+            delegated_out: 4,
+            delegated_in: 0,
+            frozen: true,
+            expired: false,
+            resolved: 0,
+        }
+    );
+    assert_eq!(resolved(&deps, USER2, None).delegated_in, 0);
+    assert_eq!(resolved(&deps, USER2, None).resolved, 6);
+
+    // a frozen delegate loses what it received as well
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin.clone(),
+        freeze(USER1, false),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin.clone(),
+        freeze(USER2, true),
+    )
+    .unwrap();
+    assert_eq!(resolved(&deps, USER2, None).resolved, 0);
+    assert_eq!(resolved(&deps, USER1, None).resolved, 7);
+
+    // an expired seat counts the same as a frozen one
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin.clone(),
+        freeze(USER2, false),
+    )
+    .unwrap();
+    let expire = ExecuteMsg::SetMemberExpiry {
+        addr: USER2.into(),
+        expires: Some(Expiration::AtHeight(start + 5)),
+    };
+    execute(deps.as_mut(), env.clone(), admin.clone(), expire).unwrap();
+    let res = resolved(&deps, USER2, None);
+    assert!(res.expired);
+    assert_eq!(res.resolved, 0);
+
+    // the own weight follows the height
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER1.into(),
+            weight: 20,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), env.clone(), admin, update).unwrap();
+    assert_eq!(resolved(&deps, USER1, None).resolved, 16);
+    assert_eq!(resolved(&deps, USER1, Some(start + 1)).resolved, 7);
+
+    // at a past height, delegations and expiry are still read as they are now
+    let past = resolved(&deps, USER2, Some(start + 1));
+    assert_eq!((past.weight, past.delegated_in), (6, 4));
+    assert!(past.expired);
+    assert_eq!(past.resolved, 0);
+
+    // a stored delegation larger than the delegator's weight is capped
+    let (user1, user3) = (Addr::unchecked(USER1), Addr::unchecked(USER3));
+    INCOMING_DELEGATIONS
+        .save(&mut deps.storage, (&user3, &user1), &50)
+        .unwrap();
+    assert_eq!(resolved(&deps, USER3, None).delegated_in, 20);
+}
+
+#[test]
+fn backfill_weight_rewrites_history() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let at = |height| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let backfill = |addr: &str, weight, at_height| ExecuteMsg::BackfillWeight {
+        addr: addr.into(),
+        weight,
+        at_height,
+    };
+    let weight_at = |deps: &OwnedDeps<_, _, _>, addr: &str, height| {
+        query_member(deps.as_ref(), addr.into(), height)
+            .unwrap()
+            .weight
+    };
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER1.into(),
+            weight: 20,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        at(start + 5),
+        mock_info(INIT_ADMIN, &[]),
+        update,
+    )
+    .unwrap();
+
+    // up to the next change, which stays as it was
+    let msg = backfill(USER1, 15, start + 2);
+    let err = execute(
+        deps.as_mut(),
+        at(start + 10),
+        mock_info(USER1, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        at(start + 10),
+        mock_info(INIT_ADMIN, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(weight_at(&deps, USER1, Some(start + 1)), Some(11));
+    assert_eq!(weight_at(&deps, USER1, Some(start + 2)), Some(15));
+    assert_eq!(weight_at(&deps, USER1, Some(start + 5)), Some(15));
+    assert_eq!(weight_at(&deps, USER1, Some(start + 6)), Some(20));
+    assert_eq!(weight_at(&deps, USER1, None), Some(20));
+
+    // with no later change, the current weight is corrected as well
+    let msg = backfill(USER2, 8, start + 3);
+    execute(
+        deps.as_mut(),
+        at(start + 10),
+        mock_info(INIT_ADMIN, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(weight_at(&deps, USER2, Some(start + 2)), Some(6));
+    assert_eq!(weight_at(&deps, USER2, Some(start + 3)), Some(8));
+    assert_eq!(weight_at(&deps, USER2, Some(start + 10)), Some(8));
+    assert_eq!(weight_at(&deps, USER2, None), Some(8));
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 28);
+
+    // historical totals agree with the rewritten weights
+    let total_at = |height| {
+        query_total_weight(deps.as_ref(), Some(height))
+            .unwrap()
+            .weight
+    };
+    assert_eq!(total_at(start + 1), 17);
+    assert_eq!(total_at(start + 2), 21);
+    assert_eq!(total_at(start + 3), 23);
+    assert_eq!(total_at(start + 5), 23);
+    assert_eq!(total_at(start + 6), 28);
+    assert_eq!(total_at(start + 10), 28);
+    // and so do the roots proofs are checked against
+    let verifies = |height, weights: [u64; 2]| {
+        // "else", "somebody"
+        let leaves = vec![
+            merkle::leaf(USER2, weights[0]),
+            merkle::leaf(USER1, weights[1]),
+        ];
+        let proof = merkle::proof(&leaves, 1)
+            .unwrap()
+            .into_iter()
+            .map(|h| Binary::from(h.to_vec()))
+            .collect();
+        query_verify_proof(deps.as_ref(), USER1.into(), weights[1], height, proof)
+            .unwrap()
+            .valid
+    };
+    assert!(verifies(start + 1, [6, 11]));
+    assert!(verifies(start + 2, [6, 15]));
+    assert!(!verifies(start + 2, [6, 11]));
+    assert!(verifies(start + 3, [8, 15]));
+    assert!(verifies(start + 6, [8, 20]));
+    assert!(verifies(start + 11, [8, 20]));
+
+    // only while a member, and never ahead of the current block
+    for (addr, height) in [(USER1, start), (USER3, start + 2), (USER1, start + 11)] {
+        let err = execute(
+            deps.as_mut(),
+            at(start + 10),
+            mock_info(INIT_ADMIN, &[]),
+            backfill(addr, 1, height),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidBackfill {
+                addr: addr.into(),
+                height,
+            }
+        );
+    }
+}
+
+#[test]
+fn freeze_window_rejects_mutations_inside() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let now = mock_env().block.time;
+    let (start, end) = (now.plus_seconds(100), now.plus_seconds(200));
+    let at = |time: Timestamp| {
+        let mut env = mock_env();
+        env.block.time = time;
+        env
+    };
+    let add = |weight| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let window = |deps: &OwnedDeps<_, _, _>, time| {
+        let res = query(deps.as_ref(), at(time), QueryMsg::FreezeWindow {}).unwrap();
+        from_slice::<FreezeWindowResponse>(&res).unwrap()
+    };
+
+    let invalid = ExecuteMsg::SetFreezeWindow { start, end: start };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        invalid,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFreezeWindow {});
+    let set = ExecuteMsg::SetFreezeWindow { start, end };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        set.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), set).unwrap();
+    assert_eq!(
+        window(&deps, now),
+        FreezeWindowResponse {
+            start: Some(start),
+            end: Some(end),
+            active: false,
+        }
+    );
+    assert!(window(&deps, start).active);
+
+    // before the window
+    execute(deps.as_mut(), at(now), mock_info(INIT_ADMIN, &[]), add(1)).unwrap();
+    // inside it, from the start up to just before the end
+    for time in [start, now.plus_seconds(150), end.minus_nanos(1)] {
+        let err = execute(deps.as_mut(), at(time), mock_info(INIT_ADMIN, &[]), add(2)).unwrap_err();
+        assert_eq!(err, ContractError::InFreezeWindow { end });
+    }
+    assert_users(&deps, Some(11), Some(6), Some(1), None);
+    // from the end on
+    execute(deps.as_mut(), at(end), mock_info(INIT_ADMIN, &[]), add(3)).unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(3), None);
+    assert!(!window(&deps, end).active);
+
+    // the window itself can still be managed while active
+    let clear = ExecuteMsg::ClearFreezeWindow {};
+    execute(deps.as_mut(), at(start), mock_info(INIT_ADMIN, &[]), clear).unwrap();
+    execute(deps.as_mut(), at(start), mock_info(INIT_ADMIN, &[]), add(4)).unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(4), None);
+    assert_eq!(
+        window(&deps, start),
+        FreezeWindowResponse {
+            start: None,
+            end: None,
+            active: false,
+        }
+    );
+}
+
+#[test]
+fn import_merge_strategies() {
+    let import = |strategy: MergeStrategy, members: &[(&str, u64, &str)]| {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+        let msg = ExecuteMsg::ImportMerge {
+            members: members
+                .iter()
+                .map(|(addr, weight, identity)| Member {
+                    addr: addr.to_string(),
+                    weight: *weight,
+                    identity: identity.to_string(),
+                })
+                .collect(),
+            strategy,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg);
+        (deps, res)
+    };
+    let identity = |deps: &OwnedDeps<_, _, _>| {
+        query_member(deps.as_ref(), USER1.into(), None)
+            .unwrap()
+            .identity
+            .unwrap()
+    };
+    let overlapping = [(USER1, 5, "other"), (USER3, 4, "identity_3")];
+
+    let (deps, res) = import(MergeStrategy::Sum, &overlapping);
+    res.unwrap();
+    assert_users(&deps, Some(16), Some(6), Some(4), None);
+    assert_eq!(identity(&deps), "identity");
+
+    let (deps, res) = import(MergeStrategy::Max, &overlapping);
+    res.unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(4), None);
+    assert_eq!(identity(&deps), "identity");
+    let (deps, res) = import(MergeStrategy::Max, &[(USER1, 20, "other")]);
+    res.unwrap();
+    assert_users(&deps, Some(20), Some(6), None, None);
+
+    let (deps, res) = import(MergeStrategy::Replace, &overlapping);
+    res.unwrap();
+    assert_users(&deps, Some(5), Some(6), Some(4), None);
+    assert_eq!(identity(&deps), "other");
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 15);
+
+    // repeats within the import combine too
+    let (deps, res) = import(
+        MergeStrategy::Sum,
+        &[(USER3, 4, "identity_3"), (USER3, 3, "x")],
+    );
+    res.unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(7), None);
+
+    // the merged result must respect the bounds
+    let (_, res) = import(MergeStrategy::Sum, &[(USER1, 90, "identity")]);
+    assert_eq!(res.unwrap_err(), ContractError::MaxWeightExceeded {});
+}
+
+#[test]
+fn weight_percentile_of_distribution() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        members: vec![],
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let percentile = |deps: &OwnedDeps<_, _, _>, weight| {
+        query_weight_percentile(deps.as_ref(), weight)
+            .unwrap()
+            .percentile
+    };
+    assert_eq!(percentile(&deps, 10), Decimal::zero());
+
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![
+            Member {
+                addr: USER3.into(),
+                weight: 6,
+                identity: "identity_3".to_string(),
+            },
+            Member {
+                addr: "fourth".into(),
+                weight: 20,
+                identity: "identity_4".to_string(),
+            },
+        ],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), add).unwrap();
+
+    // weights are 6, 6, 11 and 20
+    assert_eq!(percentile(&deps, 0), Decimal::zero());
+    assert_eq!(percentile(&deps, 5), Decimal::zero());
+    assert_eq!(percentile(&deps, 6), Decimal::percent(50));
+    assert_eq!(percentile(&deps, 10), Decimal::percent(50));
+    assert_eq!(percentile(&deps, 11), Decimal::percent(75));
+    assert_eq!(percentile(&deps, 19), Decimal::percent(75));
+    assert_eq!(percentile(&deps, 20), Decimal::one());
+    assert_eq!(percentile(&deps, u64::MAX), Decimal::one());
+}
+
+#[test]
+fn ensure_member_inserts_updates_or_does_nothing() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        add_hook,
+    )
+    .unwrap();
+    let ensure = |deps: &mut OwnedDeps<_, _, _>, addr: &str, weight, identity: &str| {
+        let msg = ExecuteMsg::EnsureMember {
+            member: Member {
+                addr: addr.into(),
+                weight,
+                identity: identity.into(),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap()
+    };
+
+    // insert
+    let res = ensure(&mut deps, USER3, 4, "identity_3");
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.attributes[2], attr("changed", "true"));
+    assert_users(&deps, Some(11), Some(6), Some(4), None);
+
+    // no-op, also for members present from the start
+    for (addr, weight, identity) in [(USER3, 4, "identity_3"), (USER1, 11, "identity")] {
+        let res = ensure(&mut deps, addr, weight, identity);
+        assert!(res.messages.is_empty());
+        assert_eq!(res.attributes[2], attr("changed", "false"));
+    }
+    assert_users(&deps, Some(11), Some(6), Some(4), None);
+
+    // update of the weight or the identity
+    let res = ensure(&mut deps, USER3, 7, "identity_3");
+    assert_eq!(res.messages.len(), 1);
+    assert_users(&deps, Some(11), Some(6), Some(7), None);
+    let res = ensure(&mut deps, USER1, 11, "renamed");
+    assert_eq!(res.messages.len(), 1);
+    let member = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(member.identity, Some("renamed".to_string()));
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 24);
+
+    let msg = ExecuteMsg::EnsureMember {
+        member: Member {
+            addr: USER2.into(),
+            weight: 6,
+            identity: "identity_2".into(),
+        },
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+}
+
+#[test]
+fn update_member_changes_only_given_fields() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add_hook).unwrap();
+    let update = |deps: &mut OwnedDeps<_, _, _>, weight, identity: Option<&str>| {
+        let msg = ExecuteMsg::UpdateMember {
+            addr: USER1.into(),
+            weight,
+            identity: identity.map(String::from),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap()
+    };
+    let identity = |deps: &OwnedDeps<_, _, _>| {
+        query_member(deps.as_ref(), USER1.into(), None)
+            .unwrap()
+            .identity
+    };
+
+    // weight only
+    let res = update(&mut deps, Some(15), None);
+    assert_eq!(res.messages.len(), 1);
+    assert_users(&deps, Some(15), Some(6), None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 21);
+    assert_eq!(identity(&deps), Some("identity".to_string()));
+
+    // identity only
+    let res = update(&mut deps, None, Some("renamed"));
+    assert_eq!(res.messages.len(), 1);
+    assert_users(&deps, Some(15), Some(6), None, None);
+    assert_eq!(identity(&deps), Some("renamed".to_string()));
+
+    // both
+    let res = update(&mut deps, Some(3), Some("again"));
+    let hook_msg = MemberChangedHookMsg {
+        diffs: vec![MemberDiff::new(USER1, Some(15), Some(3))],
+    };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+    );
+    assert_users(&deps, Some(3), Some(6), None, None);
+    assert_eq!(identity(&deps), Some("again".to_string()));
+
+    // nothing to change, nothing to report
+    let res = update(&mut deps, Some(3), Some("again"));
+    assert!(res.messages.is_empty());
+    let res = update(&mut deps, None, None);
+    assert!(res.messages.is_empty());
+
+    // bounds still apply
+    let msg = ExecuteMsg::UpdateMember {
+        addr: USER1.into(),
+        weight: Some(200),
+        identity: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap_err();
+    assert_eq!(err, ContractError::MaxWeightExceeded {});
+}
+
+#[test]
+fn state_hash_tracks_member_set() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let hash =
+        |deps: &OwnedDeps<_, _, _>, height| query_state_hash(deps.as_ref(), height).unwrap().hash;
+
+    let initial = hash(&deps, None);
+    assert!(initial.is_some());
+    assert_eq!(hash(&deps, None), initial);
+    assert_eq!(hash(&deps, Some(start)), None);
+
+    let mut env = mock_env();
+    env.block.height = start + 5;
+    let swap = ExecuteMsg::SwapWeights {
+        a: USER1.into(),
+        b: USER2.into(),
+    };
+    execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), swap).unwrap();
+
+    let swapped = hash(&deps, None);
+    assert_ne!(swapped, initial);
+    assert_eq!(hash(&deps, None), swapped);
+    // snapshot semantics: a change at a height shows from the next block
+    assert_eq!(hash(&deps, Some(start + 5)), initial);
+    assert_eq!(hash(&deps, Some(start + 6)), swapped);
+}
+
+#[test]
+fn auto_rebalance_scales_to_target() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        auto_rebalance: true,
+        rebalance_target: 100,
+        rebalance_remainder_to: Some(USER2.into()),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add_hook = ExecuteMsg::AddHook {
+        addr: "hook1".into(),
+        priority: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add_hook).unwrap();
+    let add = |addr: &str, weight| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: addr.into(),
+            weight,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+
+    // 11 + 6 + 4 = 21 scaled to 100: 52.38 -> 52, 28.57 -> 28, 19.04 -> 19,
+    // and the remaining 1 goes to USER2
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), add(USER3, 4)).unwrap();
+    assert_users(&deps, Some(52), Some(29), Some(19), None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 100);
+    let hook_msg = MemberChangedHookMsg {
+        diffs: vec![
+            MemberDiff::new(USER3, None, Some(4)),
+            MemberDiff::new(USER2, Some(6), Some(29)),
+            MemberDiff::new(USER3, Some(4), Some(19)),
+            MemberDiff::new(USER1, Some(11), Some(52)),
+        ],
+    };
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+    );
+
+    // 30 + 29 + 19 = 78 scaled to 100: 38.46 -> 38, 37.17 -> 37 + 1, 24.35 -> 24
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        add(USER1, 30),
+    )
+    .unwrap();
+    assert_users(&deps, Some(38), Some(38), Some(24), None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 100);
+    // already on target, nothing moves
+    execute(deps.as_mut(), mock_env(), admin_info, add(USER1, 38)).unwrap();
+    assert_users(&deps, Some(38), Some(38), Some(24), None);
+}
+
+#[test]
+fn auto_rebalance_respects_locks_and_guards() {
+    let rebalancing = |target| {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            auto_rebalance: true,
+            rebalance_target: target,
+            ..default_instantiate_msg()
+        };
+        instantiate_with(deps.as_mut(), msg);
+        deps
+    };
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add = |weight| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+
+    // a locked member would be rescaled
+    let mut deps = rebalancing(100);
+    let lock = ExecuteMsg::LockMember { addr: USER2.into() };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), lock).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), add(4)).unwrap_err();
+    assert_eq!(err, ContractError::MemberLocked { addr: USER2.into() });
+
+    // the batch moves 4, but rescaling USER1 from 11 to 53 exceeds the limit
+    let mut deps = rebalancing(100);
+    let limit = ExecuteMsg::SetMaxWeightDelta { max: Some(40) };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), limit).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), add(4)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WeightDeltaTooLarge { delta: 42, max: 40 }
+    );
+
+    // 6 of 23 clears a 25% floor, but rounding down to 2 of 10 does not
+    let mut deps = rebalancing(10);
+    let min = Decimal::percent(25);
+    let floor = ExecuteMsg::SetMinMemberPercent { percent: Some(min) };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), floor).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), add(6)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BelowMinMemberPercent {
+            addr: USER2.into(),
+            min
+        }
+    );
+}
+
+#[test]
+fn revert_undoes_auto_rebalance() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        auto_rebalance: true,
+        rebalance_target: 100,
+        rebalance_remainder_to: Some(USER2.into()),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add).unwrap();
+    assert_users(&deps, Some(52), Some(29), Some(19), None);
+
+    // the rescaling is part of the batch, so the revert restores the original weights
+    let msg = ExecuteMsg::RevertLastBatch {};
+    execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+    assert_users(&deps, Some(11), Some(6), None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 17);
+}
+
+#[test]
+fn members_with_weight_filters_by_tier() {
+    let mut deps = mock_dependencies();
+    let weights = [
+        ("addr1", 5),
+        ("addr2", 20),
+        ("addr3", 5),
+        ("addr4", 1),
+        ("addr5", 5),
+    ];
+    let msg = InstantiateMsg {
+        members: weights
+            .iter()
+            .map(|(addr, weight)| Member {
+                addr: addr.to_string(),
+                weight: *weight,
+                identity: "identity".to_string(),
+            })
+            .collect(),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let tier = |weight, start_after: Option<&str>, limit| {
+        let res =
+            query_members_with_weight(deps.as_ref(), weight, start_after.map(String::from), limit)
+                .unwrap();
+        let addrs: Vec<_> = res.members.into_iter().map(|m| m.addr).collect();
+        (addrs, res.has_more)
+    };
+    assert_eq!(
+        tier(5, None, None),
+        (vec!["addr1".into(), "addr3".into(), "addr5".into()], false)
+    );
+    assert_eq!(tier(20, None, None), (vec!["addr2".into()], false));
+    assert_eq!(tier(7, None, None), (vec![], false));
+
+    // paginated within the tier
+    assert_eq!(
+        tier(5, None, Some(2)),
+        (vec!["addr1".into(), "addr3".into()], true)
+    );
+    assert_eq!(
+        tier(5, Some("addr3"), Some(2)),
+        (vec!["addr5".into()], false)
+    );
+}
+
+#[test]
+fn revert_last_batch_restores_prior_state() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let revert = ExecuteMsg::RevertLastBatch {};
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        revert.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoBatchToRevert {});
+
+    // a mistaken update: bump USER1, add USER3, drop USER2
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![
+            Member {
+                addr: USER1.into(),
+                weight: 50,
+                identity: "identity".to_string(),
+            },
+            Member {
+                addr: USER3.into(),
+                weight: 9,
+                identity: "identity_3".to_string(),
+            },
+        ],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    assert_users(&deps, Some(50), None, Some(9), None);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        revert.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        revert.clone(),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 17);
+    let member = query_member(deps.as_ref(), USER2.into(), None).unwrap();
+    assert_eq!(member.identity, Some("identity_2".to_string()));
+    let listed = query_list_by_identity(deps.as_ref(), None, None).unwrap();
+    assert_eq!(listed.members.len(), 2);
+
+    // only once
+    let err = execute(deps.as_mut(), mock_env(), admin_info, revert).unwrap_err();
+    assert_eq!(err, ContractError::NoBatchToRevert {});
+}
+
+#[test]
+fn revert_last_batch_rejects_stale_batch() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 9,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
+    let swap = ExecuteMsg::SwapWeights {
+        a: USER1.into(),
+        b: USER3.into(),
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), swap).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info,
+        ExecuteMsg::RevertLastBatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StaleBatch {});
+}
+
+#[test]
+fn next_scheduled_for_pending_removal() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        removal_grace_blocks: Some(10),
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let next = |deps: &OwnedDeps<_, _, _>, addr: &str| -> NextScheduledResponse {
+        let msg = QueryMsg::NextScheduledFor { addr: addr.into() };
+        from_slice(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    let nothing = NextScheduledResponse {
+        at_height: None,
+        new_weight: None,
+    };
+    assert_eq!(next(&deps, USER1), nothing);
+
+    let msg = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER1.into()],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        next(&deps, USER1),
+        NextScheduledResponse {
+            at_height: Some(mock_env().block.height + 10),
+            new_weight: Some(0),
+        }
+    );
+    assert_eq!(next(&deps, USER2), nothing);
+}
+
+#[test]
+fn admin_member_reports_admin_seat() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let admin_member = |deps: &OwnedDeps<_, _, _>| -> AdminMemberResponse {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::AdminMember {}).unwrap();
+        from_slice(&res).unwrap()
+    };
+
+    // the admin holds no seat yet
+    let res = admin_member(&deps);
+    assert_eq!(res.admin, Some(INIT_ADMIN.to_string()));
+    assert_eq!(res.member, None);
+
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: INIT_ADMIN.into(),
+            weight: 3,
+            identity: "identity_admin".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), mock_env(), admin_info.clone(), add).unwrap();
+    let res = admin_member(&deps);
+    assert_eq!(res.admin, Some(INIT_ADMIN.to_string()));
+    assert_eq!(
+        res.member,
+        Some(MemberResponse {
+            weight: Some(3),
+            identity: Some("identity_admin".to_string()),
+        })
+    );
+
+    // without an admin there is nothing to report
+    let clear = ExecuteMsg::UpdateAdmin { admin: None };
+    execute(deps.as_mut(), mock_env(), admin_info, clear).unwrap();
+    let res = admin_member(&deps);
+    assert_eq!(res.admin, None);
+    assert_eq!(res.member, None);
+}
+
+#[test]
+fn temp_admin_acts_until_expiry() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let temp_info = mock_info(USER3, &[]);
+    let start = mock_env().block.height;
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let temp_admin = |deps: &OwnedDeps<_, _, _>, height: u64| -> TempAdminResponse {
+        let res = query(deps.as_ref(), at(height), QueryMsg::TempAdmin {}).unwrap();
+        from_slice(&res).unwrap()
+    };
+    let update = |weight: u64| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER2.into(),
+            weight,
+            identity: "identity_2".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+
+    let delegate = ExecuteMsg::DelegateAdmin {
+        addr: USER3.into(),
+        expires: Expiration::AtHeight(start + 10),
+    };
+    let err = execute(
+        deps.as_mut(),
+        at(start),
+        temp_info.clone(),
+        delegate.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let lapsed = ExecuteMsg::DelegateAdmin {
+        addr: USER3.into(),
+        expires: Expiration::AtHeight(start),
+    };
+    let err = execute(deps.as_mut(), at(start), admin_info.clone(), lapsed).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTempAdminExpiry {});
+    execute(
+        deps.as_mut(),
+        at(start),
+        admin_info.clone(),
+        delegate.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        temp_admin(&deps, start),
+        TempAdminResponse {
+            addr: Some(USER3.into()),
+            expires: Some(Expiration::AtHeight(start + 10)),
+        }
+    );
+
+    // the temporary admin manages members, but cannot hand out the admin role
+    execute(deps.as_mut(), at(start + 1), temp_info.clone(), update(7)).unwrap();
+    assert_users(&deps, Some(11), Some(7), None, None);
+    let err = execute(
+        deps.as_mut(),
+        at(start + 1),
+        temp_info.clone(),
+        ExecuteMsg::UpdateAdmin {
+            admin: Some(USER3.into()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    // once expired, the rights are gone
+    assert_eq!(temp_admin(&deps, start + 10).addr, None);
+    let err = execute(deps.as_mut(), at(start + 10), temp_info.clone(), update(8)).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    assert_users(&deps, Some(11), Some(7), None, None);
+
+    // the admin can also end a delegation early
+    let delegate = ExecuteMsg::DelegateAdmin {
+        addr: USER3.into(),
+        expires: Expiration::AtHeight(start + 30),
+    };
+    execute(deps.as_mut(), at(start + 11), admin_info.clone(), delegate).unwrap();
+    execute(deps.as_mut(), at(start + 12), temp_info.clone(), update(9)).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        at(start + 12),
+        temp_info.clone(),
+        ExecuteMsg::RevokeTempAdmin {},
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        at(start + 12),
+        admin_info,
+        ExecuteMsg::RevokeTempAdmin {},
+    )
+    .unwrap();
+    assert_eq!(temp_admin(&deps, start + 12).addr, None);
+    let err = execute(deps.as_mut(), at(start + 13), temp_info, update(10)).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    assert_users(&deps, Some(11), Some(9), None, None);
+}
+
+#[test]
+fn admin_nonce_protects_against_replays() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        require_admin_nonce: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let admin_nonce = |deps: &OwnedDeps<_, _, _>| -> u64 {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::AdminNonce {}).unwrap();
+        from_slice::<AdminNonceResponse>(&res).unwrap().nonce
+    };
+    let update = |weight: u64| ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let with_nonce = |nonce: u64, weight: u64| ExecuteMsg::WithNonce {
+        nonce,
+        msg: Box::new(update(weight)),
+    };
+    assert_eq!(admin_nonce(&deps), 0);
+
+    // the admin must use the nonce
+    let err = execute(deps.as_mut(), mock_env(), admin_info.clone(), update(1)).unwrap_err();
+    assert_eq!(err, ContractError::BadNonce { expected: 0 });
+
+    // the correct nonce goes through and is consumed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        with_nonce(0, 1),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(1), None);
+    assert_eq!(admin_nonce(&deps), 1);
+
+    // stale and future nonces are rejected
+    for nonce in [0, 5] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            with_nonce(nonce, 2),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::BadNonce { expected: 1 });
+    }
+    assert_users(&deps, Some(11), Some(6), Some(1), None);
+
+    // only the admin can consume nonces, others send messages as usual
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        with_nonce(1, 2),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::FinalizeRemovals {},
+    )
+    .unwrap();
+    assert_eq!(admin_nonce(&deps), 1);
+
+    execute(deps.as_mut(), mock_env(), admin_info, with_nonce(1, 2)).unwrap();
+    assert_users(&deps, Some(11), Some(6), Some(2), None);
+    assert_eq!(admin_nonce(&deps), 2);
+}
+
+#[test]
+fn admin_nonce_is_optional_by_default() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        update.clone(),
+    )
+    .unwrap();
+    let wrapped = ExecuteMsg::WithNonce {
+        nonce: 0,
+        msg: Box::new(update),
+    };
+    execute(deps.as_mut(), mock_env(), admin_info, wrapped).unwrap();
+    assert_users(&deps, Some(11), None, None, None);
+}
+
+#[test]
+fn sealed_group_rejects_changes_but_answers_queries() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let is_sealed = |deps: &OwnedDeps<_, _, _>| -> bool {
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::IsSealed {}).unwrap();
+        from_slice::<SealedResponse>(&res).unwrap().sealed
+    };
+    assert!(!is_sealed(&deps));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Seal {},
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    let mut env = mock_env();
+    env.block.height += 5;
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        update.clone(),
+    )
+    .unwrap();
+    env.block.height += 5;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        ExecuteMsg::Seal {},
+    )
+    .unwrap();
+    assert!(is_sealed(&deps));
+
+    let attempts = vec![
+        update,
+        ExecuteMsg::UpdateAdmin {
+            admin: Some(USER1.into()),
+        },
+        ExecuteMsg::Seal {},
+        ExecuteMsg::Dissolve {},
+        ExecuteMsg::CheckExpiry {},
+        ExecuteMsg::FinalizeRemovals {},
+    ];
+    for msg in attempts {
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Sealed {});
+    }
+
+    // current and historical state remain available
+    assert_users(&deps, Some(11), None, None, None);
+    assert_users(
+        &deps,
+        Some(11),
+        Some(6),
+        None,
+        Some(mock_env().block.height + 1),
+    );
+    assert_eq!(
+        ADMIN.query_admin(deps.as_ref()).unwrap().admin,
+        Some(INIT_ADMIN.into())
+    );
+}
+
+#[test]
+fn members_can_leave() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    // disabled unless configured
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        min_weight: 10,
+        allow_self_leave: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER3, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAMember { addr: USER3.into() });
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER2, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "leave"), attr("sender", USER2)]
+    );
+    assert_users(&deps, Some(11), None, None, None);
+    let left = query_member(deps.as_ref(), USER2.into(), None).unwrap();
+    assert_eq!(left.identity, None);
+
+    // the last member cannot take the total below the min
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::Leave {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WouldViolateMinWeight {
+            projected_total: 0,
+            min: 10
+        }
+    );
+    assert_users(&deps, Some(11), None, None, None);
+}
+
+#[test]
+fn leaving_respects_removal_guards() {
+    let leaving = |setup: ExecuteMsg| {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            allow_self_leave: true,
+            ..default_instantiate_msg()
+        };
+        instantiate_with(deps.as_mut(), msg);
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), setup).unwrap();
+        deps
+    };
+    let leave = |deps: &mut OwnedDeps<_, _, _>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::Leave {},
+        )
+    };
+
+    let mut deps = leaving(ExecuteMsg::SetMaxWeightDelta { max: Some(10) });
+    let err = leave(&mut deps).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WeightDeltaTooLarge { delta: 11, max: 10 }
+    );
+
+    let mut deps = leaving(ExecuteMsg::SetMinActiveTotal { min: Some(7) });
+    let err = leave(&mut deps).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WouldBreakQuorum {
+            projected_total: 6,
+            min: 7
+        }
+    );
+}
+
+// secp256k1 keys and a signature over the sha256 of ATTESTATION by ATTESTER_KEY
+const ATTESTER_KEY: &str = "A5qgzbO8ET3t05w6hg2lvxgnrL2vLEFl1HZ11dTWexiv";
+const OTHER_KEY: &str = "A51vjj6mbCCeovkUoqTGvUNHdojMsiZDzfENt3lHQCbA";
+const ATTESTATION: &str =
+    r#"{"nonce":0,"add":[{"addr":"funny","weight":4,"identity":"identity_3"}],"remove":["else"]}"#;
+const ATTESTATION_SIG: &str =
+    "9PcYBt1lP1MACVVpfe+qH9RdNRKv1859VpjRcEw/bYsw+0hqXEs2aRQ3qMDn8Vv7uvAgv77OlzuayZ+uXC7+Zg==";
+
+#[test]
+fn attestations_drive_membership() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let relayer = mock_info(USER1, &[]);
+    let set_attester = |key: &str| ExecuteMsg::SetAttester {
+        pubkey: Some(Binary::from_base64(key).unwrap()),
+    };
+    let apply = |attestation: &str| ExecuteMsg::ApplyAttestation {
+        attestation: Binary::from(attestation.as_bytes()),
+        signature: Binary::from_base64(ATTESTATION_SIG).unwrap(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        relayer.clone(),
+        apply(ATTESTATION),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoAttester {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        relayer.clone(),
+        set_attester(ATTESTER_KEY),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    // signed by someone else
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        set_attester(OTHER_KEY),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        relayer.clone(),
+        apply(ATTESTATION),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSignature {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info,
+        set_attester(ATTESTER_KEY),
+    )
+    .unwrap();
+    // tampered with after signing
+    let tampered = ATTESTATION.replace("\"weight\":4", "\"weight\":40");
+    let err = execute(deps.as_mut(), mock_env(), relayer.clone(), apply(&tampered)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidSignature {});
+    // not a signature at all
+    let garbage = ExecuteMsg::ApplyAttestation {
+        attestation: Binary::from(ATTESTATION.as_bytes()),
+        signature: Binary::from(b"garbage".to_vec()),
+    };
+    let err = execute(deps.as_mut(), mock_env(), relayer.clone(), garbage).unwrap_err();
+    assert_eq!(err, ContractError::InvalidSignature {});
+    assert_users(&deps, Some(11), Some(6), None, None);
+
+    // anyone can relay a valid attestation, but only once
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        relayer.clone(),
+        apply(ATTESTATION),
+    )
+    .unwrap();
+    assert_users(&deps, Some(11), None, Some(4), None);
+    let err = execute(deps.as_mut(), mock_env(), relayer, apply(ATTESTATION)).unwrap_err();
+    assert_eq!(err, ContractError::StaleAttestation { expected: 1 });
+}
+
+#[test]
+fn member_update_event_carries_payload() {
+    let update = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 4,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![USER2.into()],
+        expected_total: None,
+    };
+
+    // off by default
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update.clone(),
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        emit_payload_event: true,
+        ..default_instantiate_msg()
+    };
+    instantiate_with(deps.as_mut(), msg);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(INIT_ADMIN, &[]),
+        update,
+    )
+    .unwrap();
+    // the plain attributes are still there
+    assert_eq!(res.attributes[0], attr("action", "update_members"));
+    assert_eq!(res.events.len(), 1);
+    let event = &res.events[0];
+    assert_eq!(event.ty, "member_update");
+    assert_eq!(event.attributes.len(), 1);
+    assert_eq!(event.attributes[0].key, "payload");
+
+    let payload = Binary::from_base64(&event.attributes[0].value).unwrap();
+    let summary: MemberUpdateSummary = from_slice(&payload).unwrap();
+    assert_eq!(
+        summary,
+        MemberUpdateSummary {
+            diffs: vec![
+                MemberDiff::new(USER3, None, Some(4)),
+                MemberDiff::new(USER2, Some(6), None),
+            ],
+            total_weight: 15,
+            height: mock_env().block.height,
+        }
+    );
+}
+
+#[test]
+fn decay_spares_locked_members_and_floors() {
+    let start = mock_env().block.height;
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    let decaying = |setup: Vec<ExecuteMsg>| {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+        let set_decay = ExecuteMsg::SetDecay {
+            decay: Some(WeightDecay {
+                percent: Decimal::percent(10),
+                interval: 10,
+            }),
+        };
+        for msg in std::iter::once(set_decay).chain(setup) {
+            execute(deps.as_mut(), at(start), admin_info.clone(), msg).unwrap();
+        }
+        deps
+    };
+    let decay = |deps: &mut OwnedDeps<_, _, _>| {
+        execute(
+            deps.as_mut(),
+            at(start + 10),
+            mock_info(USER2, &[]),
+            ExecuteMsg::DecayWeights {},
+        )
+    };
+
+    // locked members keep their weight
+    let mut deps = decaying(vec![ExecuteMsg::LockMember { addr: USER1.into() }]);
+    decay(&mut deps).unwrap();
+    assert_users(&deps, Some(11), Some(5), None, None);
+    assert_eq!(query_total_weight(deps.as_ref(), None).unwrap().weight, 16);
+
+    // 11 + 6 decays to 9 + 5
+    let mut deps = decaying(vec![ExecuteMsg::SetMinActiveTotal { min: Some(15) }]);
+    let err = decay(&mut deps).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WouldBreakQuorum {
+            projected_total: 14,
+            min: 15
+        }
+    );
+
+    // USER3 drops from 2 of 19 to 1 of 15
+    let min = Decimal::percent(10);
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 2,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    let floor = ExecuteMsg::SetMinMemberPercent { percent: Some(min) };
+    let mut deps = decaying(vec![add, floor]);
+    let err = decay(&mut deps).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BelowMinMemberPercent {
+            addr: USER3.into(),
+            min
+        }
+    );
+}
+
+#[test]
+fn decay_compounds_over_intervals() {
+    let mut deps = mock_dependencies();
+    do_instantiate(deps.as_mut());
+    let start = mock_env().block.height;
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let anyone = mock_info(USER2, &[]);
+    let decay = |deps: &mut OwnedDeps<_, _, _>, height| {
+        execute(
+            deps.as_mut(),
+            at(height),
+            anyone.clone(),
+            ExecuteMsg::DecayWeights {},
+        )
+    };
+
+    let err = decay(&mut deps, start).unwrap_err();
+    assert_eq!(err, ContractError::DecayNotConfigured {});
+
+    let add = ExecuteMsg::UpdateMembers {
+        add: vec![Member {
+            addr: USER3.into(),
+            weight: 1,
+            identity: "identity_3".to_string(),
+        }],
+        remove: vec![],
+        expected_total: None,
+    };
+    execute(deps.as_mut(), at(start), mock_info(INIT_ADMIN, &[]), add).unwrap();
+    let set_decay = ExecuteMsg::SetDecay {
+        decay: Some(WeightDecay {
+            percent: Decimal::percent(10),
+            interval: 10,
+        }),
+    };
+    let err = execute(deps.as_mut(), at(start), anyone.clone(), set_decay.clone()).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    execute(
+        deps.as_mut(),
+        at(start),
+        mock_info(INIT_ADMIN, &[]),
+        set_decay,
+    )
+    .unwrap();
+
+    let err = decay(&mut deps, start + 5).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DecayNotDue {
+            next_height: start + 10
+        }
+    );
+
+    // two intervals keep 81%, rounding down, and USER3 decays away
+    decay(&mut deps, start + 25).unwrap();
+    assert_users(&deps, Some(8), Some(4), None, None);
+
+    // the next interval still ends at start + 30
+    let err = decay(&mut deps, start + 29).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DecayNotDue {
+            next_height: start + 30
+        }
+    );
+    decay(&mut deps, start + 30).unwrap();
+    assert_users(&deps, Some(7), Some(3), None, None);
+}
@@ -39,6 +39,9 @@ pub struct Member {
 #[cw_serde]
 pub struct MemberListResponse {
     pub members: Vec<Member>,
+    /// Whether more members follow this page
+    #[serde(default)]
+    pub has_more: bool,
 }
 
 #[cw_serde]